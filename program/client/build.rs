@@ -0,0 +1,22 @@
+fn main() {
+    // Transpile the circuit wasm into native code for witness generation.
+    // The circuit artifacts are built outside this repository, see README.md.
+    #[cfg(feature = "prover")]
+    {
+        println!("cargo:rerun-if-env-changed=YONA_CIRCUITS_DIR");
+        let circuits_dir = std::env::var("YONA_CIRCUITS_DIR").unwrap_or_else(|_| {
+            panic!(
+                "the `prover` feature needs the transaction2 circuit artifacts: set \
+                 YONA_CIRCUITS_DIR to the directory holding transaction2.wasm (see client/README.md)"
+            )
+        });
+        let wasm_path = std::path::Path::new(&circuits_dir).join("transaction2.wasm");
+        if !wasm_path.is_file() {
+            panic!(
+                "YONA_CIRCUITS_DIR={circuits_dir} has no transaction2.wasm (see client/README.md)"
+            );
+        }
+        println!("cargo:rerun-if-changed={}", wasm_path.display());
+        rust_witness::transpile::transpile_wasm(circuits_dir);
+    }
+}
@@ -0,0 +1,39 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ClientError {
+    #[error("Failed to read proving key: {0}")]
+    InvalidProvingKey(String),
+    #[error("Witness generation failed: {0}")]
+    WitnessGeneration(String),
+    #[error("Proof generation failed: {0}")]
+    ProofGeneration(String),
+    #[error("Generated proof does not verify against the proving key")]
+    ProofVerificationFailed,
+    #[error("Failed to serialize proof point")]
+    ProofSerialization,
+    #[error("Invalid shielded private key")]
+    InvalidPrivateKey,
+    #[error("Poseidon hash error: {0}")]
+    Poseidon(#[from] light_poseidon::PoseidonError),
+    #[error("Note encryption failed")]
+    NoteEncryption,
+    #[error("Malformed encrypted output envelope")]
+    InvalidNoteEnvelope,
+    #[error("Unsupported encrypted output version: {0}")]
+    UnsupportedNoteVersion(u8),
+    #[error("Merkle tree is full")]
+    MerkleTreeFull,
+    #[error("Merkle tree hashing failed")]
+    MerkleHash,
+    #[error("Missing commitments: expected index {expected}, got {got}")]
+    MissingCommitments { expected: u64, got: u64 },
+    #[error("Commitment at index {0} does not match the ingested leaf")]
+    ConflictingCommitment(u64),
+    #[error("Unknown leaf index {0}")]
+    UnknownLeaf(u64),
+    #[error("Merkle tree mirror root is not known on-chain")]
+    RootMismatch,
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
@@ -0,0 +1,50 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use yona::CommitmentData;
+
+const PROGRAM_DATA: &str = "Program data: ";
+
+/// Extracts the `CommitmentData` events emitted by the yona program from transaction logs.
+///
+/// Only `Program data:` lines logged while the yona program is the innermost invoked program
+/// are considered, so events from CPI'd programs (Jupiter, Light) are ignored.
+pub fn parse_commitment_events(logs: &[String]) -> Vec<CommitmentData> {
+    let program_id = yona::ID.to_string();
+    let mut invoke_stack: Vec<&str> = Vec::new();
+    let mut events = Vec::new();
+
+    for log in logs {
+        if let Some(data) = log.strip_prefix(PROGRAM_DATA) {
+            if invoke_stack.last() == Some(&program_id.as_str()) {
+                if let Some(event) = decode_commitment_event(data) {
+                    events.push(event);
+                }
+            }
+            continue;
+        }
+
+        let mut parts = log.split_whitespace();
+        if parts.next() != Some("Program") {
+            continue;
+        }
+        let (Some(id), Some(action)) = (parts.next(), parts.next()) else {
+            continue;
+        };
+        match action {
+            "invoke" => invoke_stack.push(id),
+            "success" | "failed:" => {
+                invoke_stack.pop();
+            }
+            _ => {}
+        }
+    }
+
+    events
+}
+
+fn decode_commitment_event(data: &str) -> Option<CommitmentData> {
+    let bytes = BASE64_STANDARD.decode(data).ok()?;
+    let payload = bytes.strip_prefix(CommitmentData::DISCRIMINATOR)?;
+    CommitmentData::try_from_slice(payload).ok()
+}
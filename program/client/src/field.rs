@@ -0,0 +1,22 @@
+use anchor_lang::prelude::Pubkey;
+use ark_bn254::Fr;
+use ark_ff::{BigInteger, PrimeField};
+
+/// Maps a mint address to the field element used by the circuit.
+/// Same as `publicKeyToFieldElement` in the TypeScript helpers: pubkey bytes read big-endian,
+/// reduced modulo the BN254 scalar field.
+pub fn mint_to_field(mint: &Pubkey) -> Fr {
+    Fr::from_be_bytes_mod_order(&mint.to_bytes())
+}
+
+/// Big-endian encoding of a field element, as used for the proof public inputs
+/// (root, nullifiers, commitments, public amounts).
+pub fn fr_to_be_bytes(value: &Fr) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(&value.into_bigint().to_bytes_be());
+    bytes
+}
+
+pub fn fr_from_be_bytes(bytes: &[u8; 32]) -> Fr {
+    Fr::from_be_bytes_mod_order(bytes)
+}
@@ -0,0 +1,167 @@
+//! Off-chain mirror of the on-chain merkle tree.
+//!
+//! The program only keeps the filled subtrees and the root history, so inclusion paths have to
+//! be rebuilt off-chain by replaying every `CommitmentData` event in order. The mirror hashes
+//! exactly like `MerkleTree::append` (same `light_hasher::Poseidon` zero bytes), so its roots
+//! can be checked against `MerkleTreeAccount::root_history`.
+use light_hasher::{Hasher, Poseidon};
+use yona::merkle_tree::MerkleTree;
+use yona::state::MERKLE_TREE_HEIGHT;
+use yona::{CommitmentData, MerkleTreeAccount};
+
+use crate::errors::ClientError;
+
+/// Inclusion proof in the format expected by the circuit (`inPathIndices`, `inPathElements`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerkleProof {
+    pub leaf: [u8; 32],
+    pub index: u64,
+    pub path_elements: Vec<[u8; 32]>,
+    pub root: [u8; 32],
+}
+
+pub struct MerkleTreeMirror {
+    height: usize,
+    // layers[0] are the leaves, layers[height] the root. Only the filled part of every
+    // layer is stored, missing nodes are the zero bytes of that level.
+    layers: Vec<Vec<[u8; 32]>>,
+}
+
+impl Default for MerkleTreeMirror {
+    fn default() -> Self {
+        Self::new(MERKLE_TREE_HEIGHT as usize)
+    }
+}
+
+impl MerkleTreeMirror {
+    pub fn new(height: usize) -> Self {
+        Self {
+            height,
+            layers: vec![Vec::new(); height + 1],
+        }
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Index of the next leaf, same as `MerkleTreeAccount::next_index`
+    pub fn next_index(&self) -> u64 {
+        self.layers[0].len() as u64
+    }
+
+    pub fn leaf(&self, index: u64) -> Option<[u8; 32]> {
+        self.layers[0].get(index as usize).copied()
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.layers[self.height]
+            .first()
+            .copied()
+            .unwrap_or(Poseidon::zero_bytes()[self.height])
+    }
+
+    pub fn append(&mut self, leaf: [u8; 32]) -> Result<u64, ClientError> {
+        let index = self.next_index();
+        if index >= 1u64 << self.height {
+            return Err(ClientError::MerkleTreeFull);
+        }
+
+        self.layers[0].push(leaf);
+        let mut current_index = index as usize;
+        let mut current_level_hash = leaf;
+        for level in 0..self.height {
+            let (left, right) = if current_index.is_multiple_of(2) {
+                (current_level_hash, self.node(level, current_index + 1))
+            } else {
+                (self.node(level, current_index - 1), current_level_hash)
+            };
+            current_level_hash = Poseidon::hashv(&[&left, &right])
+                .map_err(|_| ClientError::MerkleHash)?;
+            current_index /= 2;
+
+            let parent_layer = &mut self.layers[level + 1];
+            if current_index < parent_layer.len() {
+                parent_layer[current_index] = current_level_hash;
+            } else {
+                parent_layer.push(current_level_hash);
+            }
+        }
+
+        Ok(index)
+    }
+
+    /// Replays a `CommitmentData` event: `commitment0` is the leaf at `index`,
+    /// `commitment1` the leaf at `index + 1`.
+    ///
+    /// Events that were already ingested are ignored (as long as they match), so the same
+    /// transaction can safely be replayed. Gaps are an error, events must be ingested in order.
+    /// A mirror tracks a single tree, events of other pools or trees (`CommitmentData::pool_id`,
+    /// `CommitmentData::tree_index`) must be filtered out by the caller.
+    pub fn ingest(&mut self, event: &CommitmentData) -> Result<(), ClientError> {
+        let next_index = self.next_index();
+        if event.index > next_index {
+            return Err(ClientError::MissingCommitments {
+                expected: next_index,
+                got: event.index,
+            });
+        }
+
+        for (offset, commitment) in [event.commitment0, event.commitment1].into_iter().enumerate() {
+            let index = event.index + offset as u64;
+            match self.leaf(index) {
+                Some(existing) if existing == commitment => {}
+                Some(_) => return Err(ClientError::ConflictingCommitment(index)),
+                None => {
+                    self.append(commitment)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn proof(&self, index: u64) -> Result<MerkleProof, ClientError> {
+        let leaf = self.leaf(index).ok_or(ClientError::UnknownLeaf(index))?;
+        let mut current_index = index as usize;
+        let path_elements = (0..self.height)
+            .map(|level| {
+                let sibling = self.node(level, current_index ^ 1);
+                current_index /= 2;
+                sibling
+            })
+            .collect();
+
+        Ok(MerkleProof {
+            leaf,
+            index,
+            path_elements,
+            root: self.root(),
+        })
+    }
+
+    /// Checks the mirror against the on-chain account. When both are at the same `next_index`
+    /// the roots must be equal, otherwise the mirror root must still be in the root history.
+    pub fn verify_root(&self, tree_account: &MerkleTreeAccount) -> Result<(), ClientError> {
+        // On-chain roots span MERKLE_TREE_HEIGHT levels whatever the tree height
+        if self.height != MERKLE_TREE_HEIGHT as usize {
+            return Err(ClientError::RootMismatch);
+        }
+        let root = self.root();
+        let matches = if tree_account.next_index == self.next_index() {
+            tree_account.root == root
+        } else {
+            MerkleTree::is_known_root(tree_account, root)
+        };
+        if !matches {
+            return Err(ClientError::RootMismatch);
+        }
+        Ok(())
+    }
+
+    fn node(&self, level: usize, index: usize) -> [u8; 32] {
+        self.layers[level]
+            .get(index)
+            .copied()
+            .unwrap_or(Poseidon::zero_bytes()[level])
+    }
+}
@@ -0,0 +1,867 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::InstructionData;
+use yona::groth16::Groth16Verifyingkey;
+use yona::{CompressedProof, CompressedProof4, DualExtDataMinified, ExtDataMinified, SwapExtDataMinified};
+
+use crate::light::{
+    light_remaining_account_metas, pack_light_accounts, packed_address_tree_infos,
+    LightNullifierParams, OUTPUT_STATE_TREE_INDEX,
+};
+use crate::pda::{
+    find_aggregator_config_address, find_deposit_authority_address, find_global_config_address,
+    find_merkle_tree_address, find_mint_config_address, find_relayer_address,
+    find_swap_authority_address, find_verifying_key_address, reserve_token_account,
+};
+use crate::verifying_key::{verifying_key_data, verifying_key_hash};
+
+/// Accounts for a `deposit` instruction.
+pub struct DepositAccounts {
+    /// Pool the instruction operates on, `yona::DEFAULT_POOL_ID` for the default pool
+    pub pool_id: u16,
+    /// `GlobalConfig::active_tree_index` of the pool
+    pub active_tree_index: u32,
+    /// Tree index of the proof root when it comes from a retired tree
+    pub retired_tree_index: Option<u32>,
+    pub user: Pubkey,
+    pub input_mint: Pubkey,
+    pub fee_recipient_account: Pubkey,
+    /// Token program of `input_mint` (Token or Token-2022)
+    pub token_program: Pubkey,
+}
+
+/// Accounts for a `deposit_from_token_account` instruction.
+pub struct DepositFromTokenAccountAccounts {
+    /// Pool the instruction operates on, `yona::DEFAULT_POOL_ID` for the default pool
+    pub pool_id: u16,
+    /// `GlobalConfig::active_tree_index` of the pool
+    pub active_tree_index: u32,
+    /// Tree index of the proof root when it comes from a retired tree
+    pub retired_tree_index: Option<u32>,
+    /// Owner or delegate of `user_token_account`
+    pub authority: Pubkey,
+    /// Pays the nullifier accounts, may be `authority`
+    pub payer: Pubkey,
+    pub input_mint: Pubkey,
+    /// Source token account, not necessarily an associated token account
+    pub user_token_account: Pubkey,
+    pub fee_recipient_account: Pubkey,
+    /// Token program of `input_mint` (Token or Token-2022)
+    pub token_program: Pubkey,
+}
+
+/// Accounts for a `deposit_relayed` instruction.
+pub struct DepositRelayedAccounts {
+    /// Pool the instruction operates on, `yona::DEFAULT_POOL_ID` for the default pool
+    pub pool_id: u16,
+    /// `GlobalConfig::active_tree_index` of the pool
+    pub active_tree_index: u32,
+    /// Tree index of the proof root when it comes from a retired tree
+    pub retired_tree_index: Option<u32>,
+    pub relayer: Pubkey,
+    pub input_mint: Pubkey,
+    /// Depositor's token account, approved with [`approve_deposit`]
+    pub user_token_account: Pubkey,
+    /// Token account of `input_mint` owned by the relayer's registered fee recipient
+    pub fee_recipient_account: Pubkey,
+    /// Token program of `input_mint` (Token or Token-2022)
+    pub token_program: Pubkey,
+}
+
+/// Accounts for a `withdraw` instruction.
+pub struct WithdrawAccounts {
+    /// Pool the instruction operates on, `yona::DEFAULT_POOL_ID` for the default pool
+    pub pool_id: u16,
+    /// `GlobalConfig::active_tree_index` of the pool
+    pub active_tree_index: u32,
+    /// Tree index of the proof root when it comes from a retired tree
+    pub retired_tree_index: Option<u32>,
+    pub relayer: Pubkey,
+    /// False for a self-relayed withdrawal by an unregistered relayer, which takes no fee
+    pub registered_relayer: bool,
+    pub input_mint: Pubkey,
+    pub recipient: Pubkey,
+    pub recipient_token_account: Pubkey,
+    /// Token account of `input_mint` owned by the relayer's registered fee recipient
+    pub fee_recipient_account: Pubkey,
+    /// Token program of `input_mint` (Token or Token-2022)
+    pub token_program: Pubkey,
+}
+
+/// Accounts for a `swap` instruction.
+pub struct SwapAccounts {
+    /// Pool the instruction operates on, `yona::DEFAULT_POOL_ID` for the default pool
+    pub pool_id: u16,
+    /// `GlobalConfig::active_tree_index` of the pool
+    pub active_tree_index: u32,
+    /// Tree index of the proof root when it comes from a retired tree
+    pub retired_tree_index: Option<u32>,
+    /// Registered relayer submitting the swap
+    pub relayer: Pubkey,
+    pub input_mint: Pubkey,
+    pub output_mint: Pubkey,
+    /// Output mint token account owned by the relayer's registered fee recipient
+    pub fee_recipient_account: Pubkey,
+    pub input_token_program: Pubkey,
+    pub output_token_program: Pubkey,
+    /// Accounts of the Jupiter instruction, in the order expected by `jupiter_swap_data`. The
+    /// route's transfer authority must be the pool's swap authority
+    /// ([`find_swap_authority_address`]), not the global config.
+    pub jupiter_accounts: Vec<AccountMeta>,
+}
+
+/// Accounts for a `transact` instruction.
+pub struct TransactAccounts {
+    /// Pool the instruction operates on, `yona::DEFAULT_POOL_ID` for the default pool
+    pub pool_id: u16,
+    /// `GlobalConfig::active_tree_index` of the pool
+    pub active_tree_index: u32,
+    /// Tree index of the proof root when it comes from a retired tree
+    pub retired_tree_index: Option<u32>,
+    pub payer: Pubkey,
+    pub mint: Pubkey,
+}
+
+/// Accounts for a `transact_dual` instruction.
+pub struct TransactDualAccounts {
+    /// Pool the instruction operates on, `yona::DEFAULT_POOL_ID` for the default pool
+    pub pool_id: u16,
+    /// `GlobalConfig::active_tree_index` of the pool
+    pub active_tree_index: u32,
+    /// Tree index of the proof root when it comes from a retired tree
+    pub retired_tree_index: Option<u32>,
+    pub user: Pubkey,
+    pub mint0: Pubkey,
+    pub mint1: Pubkey,
+    /// Registered relayer paid the fees, required when `fee0` or `fee1` is not zero
+    pub relayer: Option<Pubkey>,
+    /// Token account of `mint0` owned by the relayer's registered fee recipient
+    pub fee_recipient_account0: Pubkey,
+    /// Token account of `mint1` owned by the relayer's registered fee recipient
+    pub fee_recipient_account1: Pubkey,
+    /// Token program of `mint0` (Token or Token-2022)
+    pub token_program0: Pubkey,
+    /// Token program of `mint1` (Token or Token-2022)
+    pub token_program1: Pubkey,
+}
+
+/// Builds a `deposit` instruction.
+pub fn deposit(
+    accounts: DepositAccounts,
+    proof: CompressedProof,
+    ext_data_minified: ExtDataMinified,
+    encrypted_output: Vec<u8>,
+    light: &LightNullifierParams,
+) -> Instruction {
+    let program_id = yona::ID;
+    let (tree_account, _) =
+        find_merkle_tree_address(&program_id, accounts.pool_id, accounts.active_tree_index);
+    let retired_tree_account = accounts
+        .retired_tree_index
+        .map(|tree_index| find_merkle_tree_address(&program_id, accounts.pool_id, tree_index).0);
+    let (global_config, _) = find_global_config_address(&program_id, accounts.pool_id);
+    let packed = pack_light_accounts(light);
+
+    let mut account_metas = yona::accounts::Deposit {
+        tree_account,
+        retired_tree_account,
+        global_config,
+        verifying_key: find_verifying_key_address(&program_id, yona::CIRCUIT_TRANSACTION2).0,
+        input_mint: accounts.input_mint,
+        mint_config: find_mint_config_address(&program_id, &global_config, &accounts.input_mint).0,
+        reserve_token_account: reserve_token_account(
+            &program_id,
+            accounts.pool_id,
+            &accounts.input_mint,
+            &accounts.token_program,
+        ),
+        fee_recipient_account: accounts.fee_recipient_account,
+        user_token_account: anchor_spl::associated_token::get_associated_token_address_with_program_id(
+            &accounts.user,
+            &accounts.input_mint,
+            &accounts.token_program,
+        ),
+        user: accounts.user,
+        system_program: anchor_lang::system_program::ID,
+        token_program: accounts.token_program,
+    }
+    .to_account_metas(None);
+    account_metas.extend(packed.remaining_accounts);
+
+    Instruction {
+        program_id,
+        accounts: account_metas,
+        data: yona::instruction::Deposit {
+            proof,
+            ext_data_minified,
+            encrypted_output,
+            light_proof: light.light_proof,
+            nullifier0_address_tree_info: packed.nullifier0_address_tree_info,
+            nullifier1_address_tree_info: packed.nullifier1_address_tree_info,
+            output_state_tree_index: packed.output_state_tree_index,
+        }
+        .data(),
+    }
+}
+
+/// Builds a `deposit_from_token_account` instruction, for deposits from multisig vaults,
+/// program-owned or other non-associated token accounts.
+pub fn deposit_from_token_account(
+    accounts: DepositFromTokenAccountAccounts,
+    proof: CompressedProof,
+    ext_data_minified: ExtDataMinified,
+    encrypted_output: Vec<u8>,
+    light: &LightNullifierParams,
+) -> Instruction {
+    let program_id = yona::ID;
+    let (tree_account, _) =
+        find_merkle_tree_address(&program_id, accounts.pool_id, accounts.active_tree_index);
+    let retired_tree_account = accounts
+        .retired_tree_index
+        .map(|tree_index| find_merkle_tree_address(&program_id, accounts.pool_id, tree_index).0);
+    let (global_config, _) = find_global_config_address(&program_id, accounts.pool_id);
+    let packed = pack_light_accounts(light);
+
+    let mut account_metas = yona::accounts::DepositFromTokenAccount {
+        tree_account,
+        retired_tree_account,
+        global_config,
+        verifying_key: find_verifying_key_address(&program_id, yona::CIRCUIT_TRANSACTION2).0,
+        input_mint: accounts.input_mint,
+        mint_config: find_mint_config_address(&program_id, &global_config, &accounts.input_mint).0,
+        reserve_token_account: reserve_token_account(
+            &program_id,
+            accounts.pool_id,
+            &accounts.input_mint,
+            &accounts.token_program,
+        ),
+        fee_recipient_account: accounts.fee_recipient_account,
+        user_token_account: accounts.user_token_account,
+        authority: accounts.authority,
+        payer: accounts.payer,
+        system_program: anchor_lang::system_program::ID,
+        token_program: accounts.token_program,
+    }
+    .to_account_metas(None);
+    account_metas.extend(packed.remaining_accounts);
+
+    Instruction {
+        program_id,
+        accounts: account_metas,
+        data: yona::instruction::DepositFromTokenAccount {
+            proof,
+            ext_data_minified,
+            encrypted_output,
+            light_proof: light.light_proof,
+            nullifier0_address_tree_info: packed.nullifier0_address_tree_info,
+            nullifier1_address_tree_info: packed.nullifier1_address_tree_info,
+            output_state_tree_index: packed.output_state_tree_index,
+        }
+        .data(),
+    }
+}
+
+/// Builds the approval signed by the depositor for `deposit_relayed`: delegates
+/// `amount` (deposit amount plus fee) of `user_token_account` to the deposit authority of
+/// `output_commitment0`. It can be sent in the same transaction as the deposit, with the
+/// relayer as fee payer.
+#[allow(clippy::too_many_arguments)]
+pub fn approve_deposit(
+    user: Pubkey,
+    user_token_account: Pubkey,
+    mint: Pubkey,
+    decimals: u8,
+    token_program: Pubkey,
+    output_commitment0: &[u8; 32],
+    amount: u64,
+) -> std::result::Result<Instruction, ProgramError> {
+    let (deposit_authority, _) = find_deposit_authority_address(&yona::ID, output_commitment0);
+    anchor_spl::token_2022::spl_token_2022::instruction::approve_checked(
+        &token_program,
+        &user_token_account,
+        &mint,
+        &deposit_authority,
+        &user,
+        &[],
+        amount,
+        decimals,
+    )
+}
+
+/// Builds a `deposit_relayed` instruction, the relayer pays the transaction and the nullifier
+/// accounts. The depositor doesn't sign it, see [`approve_deposit`].
+pub fn deposit_relayed(
+    accounts: DepositRelayedAccounts,
+    proof: CompressedProof,
+    ext_data_minified: ExtDataMinified,
+    encrypted_output: Vec<u8>,
+    light: &LightNullifierParams,
+) -> Instruction {
+    let program_id = yona::ID;
+    let (tree_account, _) =
+        find_merkle_tree_address(&program_id, accounts.pool_id, accounts.active_tree_index);
+    let retired_tree_account = accounts
+        .retired_tree_index
+        .map(|tree_index| find_merkle_tree_address(&program_id, accounts.pool_id, tree_index).0);
+    let (global_config, _) = find_global_config_address(&program_id, accounts.pool_id);
+    let packed = pack_light_accounts(light);
+
+    let mut account_metas = yona::accounts::DepositRelayed {
+        tree_account,
+        retired_tree_account,
+        global_config,
+        verifying_key: find_verifying_key_address(&program_id, yona::CIRCUIT_TRANSACTION2).0,
+        input_mint: accounts.input_mint,
+        mint_config: find_mint_config_address(&program_id, &global_config, &accounts.input_mint).0,
+        reserve_token_account: reserve_token_account(
+            &program_id,
+            accounts.pool_id,
+            &accounts.input_mint,
+            &accounts.token_program,
+        ),
+        fee_recipient_account: accounts.fee_recipient_account,
+        user_token_account: accounts.user_token_account,
+        deposit_authority: find_deposit_authority_address(&program_id, &proof.output_commitments[0]).0,
+        relayer: accounts.relayer,
+        relayer_account: find_relayer_address(&program_id, &global_config, &accounts.relayer).0,
+        system_program: anchor_lang::system_program::ID,
+        token_program: accounts.token_program,
+    }
+    .to_account_metas(None);
+    account_metas.extend(packed.remaining_accounts);
+
+    Instruction {
+        program_id,
+        accounts: account_metas,
+        data: yona::instruction::DepositRelayed {
+            proof,
+            ext_data_minified,
+            encrypted_output,
+            light_proof: light.light_proof,
+            nullifier0_address_tree_info: packed.nullifier0_address_tree_info,
+            nullifier1_address_tree_info: packed.nullifier1_address_tree_info,
+            output_state_tree_index: packed.output_state_tree_index,
+        }
+        .data(),
+    }
+}
+
+/// Builds a `withdraw` instruction.
+pub fn withdraw(
+    accounts: WithdrawAccounts,
+    proof: CompressedProof,
+    ext_data_minified: ExtDataMinified,
+    encrypted_output: Vec<u8>,
+    light: &LightNullifierParams,
+) -> Instruction {
+    let program_id = yona::ID;
+    let (tree_account, _) =
+        find_merkle_tree_address(&program_id, accounts.pool_id, accounts.active_tree_index);
+    let retired_tree_account = accounts
+        .retired_tree_index
+        .map(|tree_index| find_merkle_tree_address(&program_id, accounts.pool_id, tree_index).0);
+    let (global_config, _) = find_global_config_address(&program_id, accounts.pool_id);
+    let packed = pack_light_accounts(light);
+
+    let mut account_metas = yona::accounts::Withdraw {
+        tree_account,
+        retired_tree_account,
+        global_config,
+        verifying_key: find_verifying_key_address(&program_id, yona::CIRCUIT_TRANSACTION2).0,
+        input_mint: accounts.input_mint,
+        mint_config: find_mint_config_address(&program_id, &global_config, &accounts.input_mint).0,
+        reserve_token_account: reserve_token_account(
+            &program_id,
+            accounts.pool_id,
+            &accounts.input_mint,
+            &accounts.token_program,
+        ),
+        recipient: accounts.recipient,
+        recipient_token_account: accounts.recipient_token_account,
+        fee_recipient_account: accounts.fee_recipient_account,
+        relayer: accounts.relayer,
+        relayer_account: accounts
+            .registered_relayer
+            .then(|| find_relayer_address(&program_id, &global_config, &accounts.relayer).0),
+        system_program: anchor_lang::system_program::ID,
+        token_program: accounts.token_program,
+    }
+    .to_account_metas(None);
+    account_metas.extend(packed.remaining_accounts);
+
+    Instruction {
+        program_id,
+        accounts: account_metas,
+        data: yona::instruction::Withdraw {
+            proof,
+            ext_data_minified,
+            encrypted_output,
+            light_proof: light.light_proof,
+            nullifier0_address_tree_info: packed.nullifier0_address_tree_info,
+            nullifier1_address_tree_info: packed.nullifier1_address_tree_info,
+            output_state_tree_index: packed.output_state_tree_index,
+        }
+        .data(),
+    }
+}
+
+/// Builds a `transact` instruction (private transfer, no token movement).
+pub fn transact(
+    accounts: TransactAccounts,
+    proof: CompressedProof,
+    encrypted_output: Vec<u8>,
+    light: &LightNullifierParams,
+) -> Instruction {
+    let program_id = yona::ID;
+    let (tree_account, _) =
+        find_merkle_tree_address(&program_id, accounts.pool_id, accounts.active_tree_index);
+    let retired_tree_account = accounts
+        .retired_tree_index
+        .map(|tree_index| find_merkle_tree_address(&program_id, accounts.pool_id, tree_index).0);
+    let (global_config, _) = find_global_config_address(&program_id, accounts.pool_id);
+    let packed = pack_light_accounts(light);
+
+    let mut account_metas = yona::accounts::Transact {
+        tree_account,
+        retired_tree_account,
+        global_config,
+        verifying_key: find_verifying_key_address(&program_id, yona::CIRCUIT_TRANSACTION2).0,
+        mint: accounts.mint,
+        mint_config: find_mint_config_address(&program_id, &global_config, &accounts.mint).0,
+        payer: accounts.payer,
+        system_program: anchor_lang::system_program::ID,
+    }
+    .to_account_metas(None);
+    account_metas.extend(packed.remaining_accounts);
+
+    Instruction {
+        program_id,
+        accounts: account_metas,
+        data: yona::instruction::Transact {
+            proof,
+            encrypted_output,
+            light_proof: light.light_proof,
+            nullifier0_address_tree_info: packed.nullifier0_address_tree_info,
+            nullifier1_address_tree_info: packed.nullifier1_address_tree_info,
+            output_state_tree_index: packed.output_state_tree_index,
+        }
+        .data(),
+    }
+}
+
+/// Builds a `transact4` instruction (4-input / 2-output private transfer), takes the same
+/// accounts as `transact`.
+pub fn transact4(
+    accounts: TransactAccounts,
+    proof: CompressedProof4,
+    encrypted_output: Vec<u8>,
+    light: &LightNullifierParams<4>,
+) -> Instruction {
+    let program_id = yona::ID;
+    let (tree_account, _) =
+        find_merkle_tree_address(&program_id, accounts.pool_id, accounts.active_tree_index);
+    let retired_tree_account = accounts
+        .retired_tree_index
+        .map(|tree_index| find_merkle_tree_address(&program_id, accounts.pool_id, tree_index).0);
+    let (global_config, _) = find_global_config_address(&program_id, accounts.pool_id);
+
+    let mut account_metas = yona::accounts::Transact4 {
+        tree_account,
+        retired_tree_account,
+        global_config,
+        verifying_key: find_verifying_key_address(&program_id, yona::CIRCUIT_TRANSACTION4).0,
+        mint: accounts.mint,
+        mint_config: find_mint_config_address(&program_id, &global_config, &accounts.mint).0,
+        payer: accounts.payer,
+        system_program: anchor_lang::system_program::ID,
+    }
+    .to_account_metas(None);
+    account_metas.extend(light_remaining_account_metas(&light.trees));
+
+    Instruction {
+        program_id,
+        accounts: account_metas,
+        data: yona::instruction::Transact4 {
+            proof,
+            encrypted_output,
+            light_proof: light.light_proof,
+            nullifier_address_tree_infos: packed_address_tree_infos(&light.address_root_indices),
+            output_state_tree_index: OUTPUT_STATE_TREE_INDEX,
+        }
+        .data(),
+    }
+}
+
+/// Builds a `transact_dual` instruction. Deposits are taken from and withdrawals sent to
+/// the user's associated token accounts of both mints.
+pub fn transact_dual(
+    accounts: TransactDualAccounts,
+    proof: CompressedProof,
+    ext_data_minified: DualExtDataMinified,
+    encrypted_output: Vec<u8>,
+    light: &LightNullifierParams,
+) -> Instruction {
+    let program_id = yona::ID;
+    let (tree_account, _) =
+        find_merkle_tree_address(&program_id, accounts.pool_id, accounts.active_tree_index);
+    let retired_tree_account = accounts
+        .retired_tree_index
+        .map(|tree_index| find_merkle_tree_address(&program_id, accounts.pool_id, tree_index).0);
+    let (global_config, _) = find_global_config_address(&program_id, accounts.pool_id);
+    let packed = pack_light_accounts(light);
+
+    let mut account_metas = yona::accounts::TransactDual {
+        tree_account,
+        retired_tree_account,
+        global_config,
+        verifying_key: find_verifying_key_address(&program_id, yona::CIRCUIT_TRANSACTION2).0,
+        mint0: accounts.mint0,
+        mint1: accounts.mint1,
+        mint_config0: find_mint_config_address(&program_id, &global_config, &accounts.mint0).0,
+        mint_config1: find_mint_config_address(&program_id, &global_config, &accounts.mint1).0,
+        reserve_token_account0: reserve_token_account(
+            &program_id,
+            accounts.pool_id,
+            &accounts.mint0,
+            &accounts.token_program0,
+        ),
+        reserve_token_account1: reserve_token_account(
+            &program_id,
+            accounts.pool_id,
+            &accounts.mint1,
+            &accounts.token_program1,
+        ),
+        user_token_account0: anchor_spl::associated_token::get_associated_token_address_with_program_id(
+            &accounts.user,
+            &accounts.mint0,
+            &accounts.token_program0,
+        ),
+        user_token_account1: anchor_spl::associated_token::get_associated_token_address_with_program_id(
+            &accounts.user,
+            &accounts.mint1,
+            &accounts.token_program1,
+        ),
+        fee_recipient_account0: accounts.fee_recipient_account0,
+        fee_recipient_account1: accounts.fee_recipient_account1,
+        user: accounts.user,
+        relayer_account: accounts
+            .relayer
+            .map(|relayer| find_relayer_address(&program_id, &global_config, &relayer).0),
+        system_program: anchor_lang::system_program::ID,
+        token_program0: accounts.token_program0,
+        token_program1: accounts.token_program1,
+    }
+    .to_account_metas(None);
+    account_metas.extend(packed.remaining_accounts);
+
+    Instruction {
+        program_id,
+        accounts: account_metas,
+        data: yona::instruction::TransactDual {
+            proof,
+            ext_data_minified,
+            encrypted_output,
+            light_proof: light.light_proof,
+            nullifier0_address_tree_info: packed.nullifier0_address_tree_info,
+            nullifier1_address_tree_info: packed.nullifier1_address_tree_info,
+            output_state_tree_index: packed.output_state_tree_index,
+        }
+        .data(),
+    }
+}
+
+/// Builds a `swap` instruction.
+///
+/// The Jupiter accounts are appended after the 12 Light Protocol accounts, which is
+/// where the program expects them.
+pub fn swap(
+    accounts: SwapAccounts,
+    proof: CompressedProof,
+    ext_data_minified: SwapExtDataMinified,
+    encrypted_output: Vec<u8>,
+    jupiter_swap_data: Vec<u8>,
+    light: &LightNullifierParams,
+) -> Instruction {
+    let program_id = yona::ID;
+    let (tree_account, _) =
+        find_merkle_tree_address(&program_id, accounts.pool_id, accounts.active_tree_index);
+    let retired_tree_account = accounts
+        .retired_tree_index
+        .map(|tree_index| find_merkle_tree_address(&program_id, accounts.pool_id, tree_index).0);
+    let (global_config, _) = find_global_config_address(&program_id, accounts.pool_id);
+    let packed = pack_light_accounts(light);
+
+    let mut account_metas = yona::accounts::Swap {
+        tree_account,
+        retired_tree_account,
+        global_config,
+        verifying_key: find_verifying_key_address(&program_id, yona::CIRCUIT_TRANSACTION2).0,
+        input_mint: accounts.input_mint,
+        output_mint: accounts.output_mint,
+        input_mint_config: find_mint_config_address(&program_id, &global_config, &accounts.input_mint).0,
+        output_mint_config: find_mint_config_address(&program_id, &global_config, &accounts.output_mint).0,
+        reserve_token_account_input: reserve_token_account(
+            &program_id,
+            accounts.pool_id,
+            &accounts.input_mint,
+            &accounts.input_token_program,
+        ),
+        reserve_token_account_output: reserve_token_account(
+            &program_id,
+            accounts.pool_id,
+            &accounts.output_mint,
+            &accounts.output_token_program,
+        ),
+        fee_recipient_account: accounts.fee_recipient_account,
+        jupiter_program: yona::jupiter_aggregator::ID,
+        aggregator_config: find_aggregator_config_address(
+            &program_id,
+            &global_config,
+            &yona::jupiter_aggregator::ID,
+        )
+        .0,
+        swap_authority: find_swap_authority_address(&program_id, &global_config).0,
+        relayer: accounts.relayer,
+        relayer_account: find_relayer_address(&program_id, &global_config, &accounts.relayer).0,
+        system_program: anchor_lang::system_program::ID,
+        input_token_program: accounts.input_token_program,
+        output_token_program: accounts.output_token_program,
+    }
+    .to_account_metas(None);
+    account_metas.extend(packed.remaining_accounts);
+    account_metas.extend(accounts.jupiter_accounts);
+
+    Instruction {
+        program_id,
+        accounts: account_metas,
+        data: yona::instruction::Swap {
+            proof,
+            ext_data_minified,
+            encrypted_output,
+            jupiter_swap_data,
+            light_proof: light.light_proof,
+            nullifier0_address_tree_info: packed.nullifier0_address_tree_info,
+            nullifier1_address_tree_info: packed.nullifier1_address_tree_info,
+            output_state_tree_index: packed.output_state_tree_index,
+        }
+        .data(),
+    }
+}
+
+/// Builds the `set_aggregator_program` instruction allowing or disallowing the Jupiter
+/// aggregator as the CPI target of `swap`, the only program the pool accepts.
+pub fn set_aggregator_program(pool_id: u16, authority: Pubkey, enabled: bool) -> Instruction {
+    let program_id = yona::ID;
+    let (global_config, _) = find_global_config_address(&program_id, pool_id);
+    let aggregator_program = yona::jupiter_aggregator::ID;
+    Instruction {
+        program_id,
+        accounts: yona::accounts::SetAggregatorProgram {
+            global_config,
+            aggregator_program,
+            aggregator_config: find_aggregator_config_address(
+                &program_id,
+                &global_config,
+                &aggregator_program,
+            )
+            .0,
+            authority,
+            system_program: anchor_lang::system_program::ID,
+        }
+        .to_account_metas(None),
+        data: yona::instruction::SetAggregatorProgram { enabled }.data(),
+    }
+}
+
+/// Builds the `register_relayer` instruction registering `authority` as a relayer of the pool,
+/// bonding `bond` lamports.
+pub fn register_relayer(
+    pool_id: u16,
+    authority: Pubkey,
+    fee_rate: u16,
+    fee_recipient: Pubkey,
+    bond: u64,
+) -> Instruction {
+    let program_id = yona::ID;
+    let (global_config, _) = find_global_config_address(&program_id, pool_id);
+    Instruction {
+        program_id,
+        accounts: yona::accounts::RegisterRelayer {
+            global_config,
+            relayer_account: find_relayer_address(&program_id, &global_config, &authority).0,
+            authority,
+            system_program: anchor_lang::system_program::ID,
+        }
+        .to_account_metas(None),
+        data: yona::instruction::RegisterRelayer {
+            fee_rate,
+            fee_recipient,
+            bond,
+        }
+        .data(),
+    }
+}
+
+/// Builds the `update_relayer` instruction, `None` leaves the field unchanged.
+pub fn update_relayer(
+    pool_id: u16,
+    authority: Pubkey,
+    fee_rate: Option<u16>,
+    fee_recipient: Option<Pubkey>,
+    bond_top_up: Option<u64>,
+) -> Instruction {
+    let program_id = yona::ID;
+    let (global_config, _) = find_global_config_address(&program_id, pool_id);
+    Instruction {
+        program_id,
+        accounts: yona::accounts::UpdateRelayer {
+            relayer_account: find_relayer_address(&program_id, &global_config, &authority).0,
+            authority,
+            system_program: anchor_lang::system_program::ID,
+        }
+        .to_account_metas(None),
+        data: yona::instruction::UpdateRelayer {
+            fee_rate,
+            fee_recipient,
+            bond_top_up,
+        }
+        .data(),
+    }
+}
+
+/// Builds the `request_relayer_deregistration` instruction, starting the unbonding period.
+pub fn request_relayer_deregistration(pool_id: u16, authority: Pubkey) -> Instruction {
+    let program_id = yona::ID;
+    let (global_config, _) = find_global_config_address(&program_id, pool_id);
+    Instruction {
+        program_id,
+        accounts: yona::accounts::RequestRelayerDeregistration {
+            relayer_account: find_relayer_address(&program_id, &global_config, &authority).0,
+            authority,
+        }
+        .to_account_metas(None),
+        data: yona::instruction::RequestRelayerDeregistration {}.data(),
+    }
+}
+
+/// Builds the `deregister_relayer` instruction, returning the bond to `authority` once the
+/// unbonding period has passed.
+pub fn deregister_relayer(pool_id: u16, authority: Pubkey) -> Instruction {
+    let program_id = yona::ID;
+    let (global_config, _) = find_global_config_address(&program_id, pool_id);
+    Instruction {
+        program_id,
+        accounts: yona::accounts::DeregisterRelayer {
+            relayer_account: find_relayer_address(&program_id, &global_config, &authority).0,
+            authority,
+        }
+        .to_account_metas(None),
+        data: yona::instruction::DeregisterRelayer {}.data(),
+    }
+}
+
+/// Builds the `slash_relayer` instruction, moving `amount` lamports of the bond of the relayer
+/// `relayer` to `destination`. Signed by the pool authority.
+pub fn slash_relayer(
+    pool_id: u16,
+    authority: Pubkey,
+    relayer: Pubkey,
+    destination: Pubkey,
+    amount: u64,
+) -> Instruction {
+    let program_id = yona::ID;
+    let (global_config, _) = find_global_config_address(&program_id, pool_id);
+    Instruction {
+        program_id,
+        accounts: yona::accounts::SlashRelayer {
+            global_config,
+            relayer_account: find_relayer_address(&program_id, &global_config, &relayer).0,
+            destination,
+            authority,
+        }
+        .to_account_metas(None),
+        data: yona::instruction::SlashRelayer { amount }.data(),
+    }
+}
+
+// Key bytes per `write_verifying_key` instruction, keeps the transaction below the size limit
+const VERIFYING_KEY_CHUNK_SIZE: usize = 800;
+
+/// Builds the `initialize_verifying_key` instruction of `circuit`.
+pub fn initialize_verifying_key(
+    authority: Pubkey,
+    circuit: u8,
+    timelock_seconds: i64,
+    migration_window_seconds: i64,
+) -> Instruction {
+    let program_id = yona::ID;
+    Instruction {
+        program_id,
+        accounts: yona::accounts::InitializeVerifyingKey {
+            verifying_key: find_verifying_key_address(&program_id, circuit).0,
+            authority,
+            system_program: anchor_lang::system_program::ID,
+        }
+        .to_account_metas(None),
+        data: yona::instruction::InitializeVerifyingKey {
+            circuit,
+            timelock_seconds,
+            migration_window_seconds,
+        }
+        .data(),
+    }
+}
+
+/// Builds the `stage_verifying_key` instruction followed by the `write_verifying_key`
+/// instructions uploading `verifying_key` as the pending key of `circuit`.
+/// Each instruction should be sent in its own transaction.
+pub fn upload_verifying_key(
+    authority: Pubkey,
+    circuit: u8,
+    version: u32,
+    verifying_key: &Groth16Verifyingkey,
+) -> Vec<Instruction> {
+    let program_id = yona::ID;
+    let accounts = yona::accounts::UpdateVerifyingKey {
+        verifying_key: find_verifying_key_address(&program_id, circuit).0,
+        authority,
+    }
+    .to_account_metas(None);
+
+    let mut instructions = vec![Instruction {
+        program_id,
+        accounts: accounts.clone(),
+        data: yona::instruction::StageVerifyingKey {
+            version,
+            key_hash: verifying_key_hash(verifying_key),
+        }
+        .data(),
+    }];
+    let data = verifying_key_data(verifying_key);
+    for (i, chunk) in data.chunks(VERIFYING_KEY_CHUNK_SIZE).enumerate() {
+        instructions.push(Instruction {
+            program_id,
+            accounts: accounts.clone(),
+            data: yona::instruction::WriteVerifyingKey {
+                offset: (i * VERIFYING_KEY_CHUNK_SIZE) as u32,
+                data: chunk.to_vec(),
+            }
+            .data(),
+        });
+    }
+    instructions
+}
+
+/// Builds the `activate_verifying_key` instruction of `circuit`.
+pub fn activate_verifying_key(authority: Pubkey, circuit: u8) -> Instruction {
+    let program_id = yona::ID;
+    Instruction {
+        program_id,
+        accounts: yona::accounts::UpdateVerifyingKey {
+            verifying_key: find_verifying_key_address(&program_id, circuit).0,
+            authority,
+        }
+        .to_account_metas(None),
+        data: yona::instruction::ActivateVerifyingKey {}.data(),
+    }
+}
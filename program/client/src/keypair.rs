@@ -0,0 +1,54 @@
+use ark_bn254::Fr;
+use ark_ff::PrimeField;
+use light_poseidon::{Poseidon, PoseidonHasher};
+use rand::{CryptoRng, RngCore};
+
+use crate::errors::ClientError;
+
+/// Shielded keypair, mirrors `Keypair` in `tests/lib/keypair.ts`.
+///
+/// This is not a Solana keypair: the private key is a field element and the public key is
+/// `Poseidon(privkey)`. It is only used inside the circuit to bind UTXOs to their owner
+/// and to make nullifiers unique.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShieldedKeypair {
+    pub privkey: Fr,
+    pub pubkey: Fr,
+}
+
+impl ShieldedKeypair {
+    pub fn new(privkey: Fr) -> Result<Self, ClientError> {
+        let pubkey = Poseidon::<Fr>::new_circom(1)?.hash(&[privkey])?;
+        Ok(Self { privkey, pubkey })
+    }
+
+    /// Private key bytes are read big-endian and reduced modulo the field size,
+    /// like `BigInt(privkeyHex) % FIELD_SIZE` in TypeScript.
+    pub fn from_private_key_bytes(bytes: &[u8; 32]) -> Result<Self, ClientError> {
+        Self::new(Fr::from_be_bytes_mod_order(bytes))
+    }
+
+    /// Accepts the same `0x`-prefixed hex string as the TypeScript constructor
+    /// (e.g. an ethers wallet private key).
+    pub fn from_private_key_hex(hex: &str) -> Result<Self, ClientError> {
+        let hex = hex.strip_prefix("0x").unwrap_or(hex);
+        let decoded = hex::decode(hex).map_err(|_| ClientError::InvalidPrivateKey)?;
+        if decoded.len() > 32 {
+            return Err(ClientError::InvalidPrivateKey);
+        }
+        let mut bytes = [0u8; 32];
+        bytes[32 - decoded.len()..].copy_from_slice(&decoded);
+        Self::from_private_key_bytes(&bytes)
+    }
+
+    pub fn generate<R: RngCore + CryptoRng>(rng: &mut R) -> Result<Self, ClientError> {
+        let mut bytes = [0u8; 32];
+        rng.fill_bytes(&mut bytes);
+        Self::from_private_key_bytes(&bytes)
+    }
+
+    /// `Poseidon(privkey, commitment, merklePath)`
+    pub fn sign(&self, commitment: Fr, merkle_path: Fr) -> Result<Fr, ClientError> {
+        Ok(Poseidon::<Fr>::new_circom(3)?.hash(&[self.privkey, commitment, merkle_path])?)
+    }
+}
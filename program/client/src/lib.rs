@@ -0,0 +1,41 @@
+//! Rust client SDK for the yona program.
+//!
+//! Builds fully-formed `deposit`, `withdraw` and `swap` instructions, reusing the
+//! on-chain types (`CompressedProof`, `ExtDataMinified`, `SwapExtDataMinified`) so the
+//! serialized instruction data always matches what the program expects.
+//!
+//! [`preflight`] runs the on-chain checks of relayed `withdraw` / `swap` transactions on the
+//! host, so relayers can reject them before paying for a failing transaction.
+//!
+//! With the `prover` feature enabled, [`prover::TransactionProver`] generates the Groth16
+//! proofs natively (no snarkjs / Node required).
+
+pub mod errors;
+pub mod field;
+pub mod keypair;
+pub mod utxo;
+pub mod note;
+pub mod events;
+pub mod indexer;
+pub mod scanner;
+pub mod pda;
+pub mod light;
+pub mod instructions;
+pub mod preflight;
+pub mod verifying_key;
+#[cfg(feature = "prover")]
+pub mod prover;
+
+pub use yona::{
+    CompressedProof, CompressedProof4, DualExtDataMinified, ExtDataMinified, SwapExtDataMinified,
+    PackedAddressTreeInfo, ValidityProof,
+};
+pub use errors::ClientError;
+pub use keypair::ShieldedKeypair;
+pub use utxo::Utxo;
+pub use indexer::{MerkleProof, MerkleTreeMirror};
+pub use scanner::{NullifierSource, OwnedUtxo, WalletScanner};
+pub use note::{NotePlaintext, ViewingKey, ViewingPublicKey};
+pub use light::{LightNullifierParams, LightTrees};
+pub use preflight::{PreflightCheck, PreflightReport, PreflightState};
+pub use instructions::*;
@@ -0,0 +1,123 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::AccountMeta;
+use light_sdk::address::v1::derive_address;
+use yona::light::CANONICAL_ADDRESS_TREE;
+use yona::{PackedAddressTreeInfo, ValidityProof};
+
+// Light Protocol static accounts (same values as `defaultStaticAccountsStruct` in stateless.js)
+pub const LIGHT_SYSTEM_PROGRAM_ID: Pubkey = pubkey!("SySTEM1eSU2p4BGQfQpimFEWWSC1XDFeun3Nqzz3rT7");
+pub const REGISTERED_PROGRAM_PDA: Pubkey = pubkey!("35hkDgaAKwMCaxRz2ocSZ6NaUrtKkyNqU6c4RV3tYJRh");
+pub const NOOP_PROGRAM_ID: Pubkey = pubkey!("noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV");
+pub const ACCOUNT_COMPRESSION_AUTHORITY: Pubkey = pubkey!("HwXnGK3tPkkVY6P439H2p68AxpeuWXd5PcrAxFpbmfbA");
+pub const ACCOUNT_COMPRESSION_PROGRAM_ID: Pubkey = pubkey!("compr6CUsB5m2jS4Y3831ztGSTnDpnKJTKS95d64XVq");
+
+/// Light Protocol trees used to create the nullifier compressed accounts.
+#[derive(Clone, Copy, Debug)]
+pub struct LightTrees {
+    pub address_tree: Pubkey,
+    pub address_queue: Pubkey,
+    pub output_state_tree: Pubkey,
+    pub nullifier_queue: Pubkey,
+}
+
+impl LightTrees {
+    /// Trees deployed by `light test-validator` (same values as `defaultTestStateTreeAccounts`).
+    pub fn localnet() -> Self {
+        Self {
+            address_tree: CANONICAL_ADDRESS_TREE,
+            address_queue: pubkey!("aq1S9z4reTSQAdgWHGD2zDaS39sjGrAxbR31vxJ2F4F"),
+            output_state_tree: pubkey!("smt1NamzXdq4AMqS2fS2F1i5KTYPZRhoHgWx38d8WsT"),
+            nullifier_queue: pubkey!("nfq1NvQDJ2GEgnS8zt9prAe8rjjpAW1zFkrvZoBR148"),
+        }
+    }
+}
+
+/// Everything needed to create the `N` nullifier compressed accounts of a proof
+/// (2 for the 2x2 circuit, 4 for `transact4`).
+///
+/// `light_proof` and `address_root_indices` come from the Light RPC
+/// (`getValidityProof` over the addresses returned by [`nullifier_address`]).
+#[derive(Clone)]
+pub struct LightNullifierParams<const N: usize = 2> {
+    pub light_proof: ValidityProof,
+    pub address_root_indices: [u16; N],
+    pub trees: LightTrees,
+}
+
+/// Light Protocol accounts and packed indices for `create_light_nullifiers`.
+pub struct PackedLightAccounts {
+    pub remaining_accounts: Vec<AccountMeta>,
+    pub nullifier0_address_tree_info: PackedAddressTreeInfo,
+    pub nullifier1_address_tree_info: PackedAddressTreeInfo,
+    pub output_state_tree_index: u8,
+}
+
+// Packed tree account indices, relative to the first account after the system accounts
+const ADDRESS_TREE_INDEX: u8 = 0;
+const ADDRESS_QUEUE_INDEX: u8 = 1;
+pub const OUTPUT_STATE_TREE_INDEX: u8 = 2;
+
+/// Address of the nullifier compressed account, derived the same way as in
+/// `create_light_nullifiers`: seeds `[b"nullifier", nullifier]` under the canonical address tree.
+pub fn nullifier_address(nullifier: &[u8; 32]) -> [u8; 32] {
+    let (address, _) = derive_address(
+        &[b"nullifier", nullifier.as_ref()],
+        &CANONICAL_ADDRESS_TREE,
+        &yona::ID,
+    );
+    address
+}
+
+/// Light system accounts expected by `CpiAccounts::new`:
+/// [light_system_program, cpi_signer, registered_program_pda, noop_program,
+///  account_compression_authority, account_compression_program, self_program, system_program]
+pub fn light_system_account_metas(program_id: &Pubkey) -> Vec<AccountMeta> {
+    let (cpi_signer, _) = Pubkey::find_program_address(&[b"cpi_authority"], program_id);
+    vec![
+        AccountMeta::new_readonly(LIGHT_SYSTEM_PROGRAM_ID, false),
+        AccountMeta::new_readonly(cpi_signer, false),
+        AccountMeta::new_readonly(REGISTERED_PROGRAM_PDA, false),
+        AccountMeta::new_readonly(NOOP_PROGRAM_ID, false),
+        AccountMeta::new_readonly(ACCOUNT_COMPRESSION_AUTHORITY, false),
+        AccountMeta::new_readonly(ACCOUNT_COMPRESSION_PROGRAM_ID, false),
+        AccountMeta::new_readonly(*program_id, false),
+        AccountMeta::new_readonly(anchor_lang::system_program::ID, false),
+    ]
+}
+
+/// Builds the remaining accounts layout used by `create_light_nullifiers`:
+/// the 8 system accounts followed by
+/// [address_tree, address_queue, output_state_tree, nullifier_queue].
+pub fn pack_light_accounts(params: &LightNullifierParams) -> PackedLightAccounts {
+    let [nullifier0_address_tree_info, nullifier1_address_tree_info] =
+        packed_address_tree_infos(&params.address_root_indices);
+
+    PackedLightAccounts {
+        remaining_accounts: light_remaining_account_metas(&params.trees),
+        nullifier0_address_tree_info,
+        nullifier1_address_tree_info,
+        output_state_tree_index: OUTPUT_STATE_TREE_INDEX,
+    }
+}
+
+/// Remaining accounts used by `create_light_nullifier_batch`, same layout as
+/// [`pack_light_accounts`] for any number of nullifiers.
+pub fn light_remaining_account_metas(trees: &LightTrees) -> Vec<AccountMeta> {
+    let mut remaining_accounts = light_system_account_metas(&yona::ID);
+    remaining_accounts.push(AccountMeta::new(trees.address_tree, false));
+    remaining_accounts.push(AccountMeta::new(trees.address_queue, false));
+    remaining_accounts.push(AccountMeta::new(trees.output_state_tree, false));
+    remaining_accounts.push(AccountMeta::new(trees.nullifier_queue, false));
+    remaining_accounts
+}
+
+/// One packed address tree info per nullifier, all pointing at the canonical address tree.
+pub fn packed_address_tree_infos<const N: usize>(
+    address_root_indices: &[u16; N],
+) -> [PackedAddressTreeInfo; N] {
+    address_root_indices.map(|root_index| PackedAddressTreeInfo {
+        root_index,
+        address_merkle_tree_pubkey_index: ADDRESS_TREE_INDEX,
+        address_queue_pubkey_index: ADDRESS_QUEUE_INDEX,
+    })
+}
@@ -0,0 +1,219 @@
+//! Note encryption for the `encrypted_output` payload emitted in `CommitmentData`.
+//!
+//! Each output UTXO is sealed to its recipient's viewing key:
+//! X25519 ECDH with a fresh ephemeral key, HKDF-SHA256 key derivation and ChaCha20-Poly1305.
+//! Since every note uses a new ephemeral key, every note also gets a new AEAD key and the
+//! nonce can be fixed.
+//!
+//! Envelope (version 1, 241 bytes):
+//! ```text
+//! version (1) || note0 || note1
+//! note = ephemeral_pubkey (32) || ciphertext (amount u64 LE (8) || blinding (32) || mint (32)) || tag (16)
+//! ```
+//! Both notes are always present so every envelope has the same size, dummy outputs are
+//! sealed like any other note.
+use anchor_lang::prelude::Pubkey;
+use ark_bn254::Fr;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use hkdf::Hkdf;
+use rand::{CryptoRng, RngCore};
+use sha2::Sha256;
+use x25519_dalek::{EphemeralSecret, PublicKey, StaticSecret};
+
+use crate::errors::ClientError;
+use crate::field::{fr_from_be_bytes, fr_to_be_bytes};
+use crate::keypair::ShieldedKeypair;
+use crate::utxo::Utxo;
+
+pub const NOTE_ENVELOPE_VERSION: u8 = 1;
+pub const NOTE_PLAINTEXT_LEN: usize = 8 + 32 + 32;
+pub const NOTE_TAG_LEN: usize = 16;
+pub const SEALED_NOTE_LEN: usize = 32 + NOTE_PLAINTEXT_LEN + NOTE_TAG_LEN;
+pub const NOTE_ENVELOPE_LEN: usize = 1 + 2 * SEALED_NOTE_LEN;
+
+const VIEWING_KEY_INFO: &[u8] = b"yona-viewing-key";
+const NOTE_KEY_INFO: &[u8] = b"yona-note-v1";
+const NOTE_NONCE: [u8; 12] = [0u8; 12];
+
+/// Secret viewing key, derived from the shielded private key so a wallet only has to back up one secret.
+pub struct ViewingKey(StaticSecret);
+
+/// Public viewing key, shared with senders together with the shielded public key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ViewingPublicKey(pub [u8; 32]);
+
+impl ViewingKey {
+    pub fn from_keypair(keypair: &ShieldedKeypair) -> Result<Self, ClientError> {
+        let mut secret = [0u8; 32];
+        Hkdf::<Sha256>::new(None, &fr_to_be_bytes(&keypair.privkey))
+            .expand(VIEWING_KEY_INFO, &mut secret)
+            .map_err(|_| ClientError::NoteEncryption)?;
+        Ok(Self(StaticSecret::from(secret)))
+    }
+
+    pub fn public_key(&self) -> ViewingPublicKey {
+        ViewingPublicKey(PublicKey::from(&self.0).to_bytes())
+    }
+}
+
+/// Everything the recipient needs, besides the leaf index and its own keypair, to rebuild the UTXO.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NotePlaintext {
+    pub amount: u64,
+    pub blinding: Fr,
+    pub mint_address: Pubkey,
+}
+
+impl From<&Utxo> for NotePlaintext {
+    fn from(utxo: &Utxo) -> Self {
+        Self {
+            amount: utxo.amount,
+            blinding: utxo.blinding,
+            mint_address: utxo.mint_address,
+        }
+    }
+}
+
+impl NotePlaintext {
+    fn to_bytes(&self) -> [u8; NOTE_PLAINTEXT_LEN] {
+        let mut bytes = [0u8; NOTE_PLAINTEXT_LEN];
+        bytes[..8].copy_from_slice(&self.amount.to_le_bytes());
+        bytes[8..40].copy_from_slice(&fr_to_be_bytes(&self.blinding));
+        bytes[40..].copy_from_slice(&self.mint_address.to_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, ClientError> {
+        if bytes.len() != NOTE_PLAINTEXT_LEN {
+            return Err(ClientError::InvalidNoteEnvelope);
+        }
+        let mut amount = [0u8; 8];
+        amount.copy_from_slice(&bytes[..8]);
+        let mut blinding = [0u8; 32];
+        blinding.copy_from_slice(&bytes[8..40]);
+        let mut mint = [0u8; 32];
+        mint.copy_from_slice(&bytes[40..]);
+        Ok(Self {
+            amount: u64::from_le_bytes(amount),
+            blinding: fr_from_be_bytes(&blinding),
+            mint_address: Pubkey::new_from_array(mint),
+        })
+    }
+}
+
+/// Builds the `encrypted_output` envelope for the two output UTXOs of a transaction.
+pub fn encrypt_notes<R: RngCore + CryptoRng>(
+    notes: [(&NotePlaintext, &ViewingPublicKey); 2],
+    rng: &mut R,
+) -> Result<Vec<u8>, ClientError> {
+    let mut envelope = Vec::with_capacity(NOTE_ENVELOPE_LEN);
+    envelope.push(NOTE_ENVELOPE_VERSION);
+    for (slot, (note, recipient)) in notes.into_iter().enumerate() {
+        envelope.extend_from_slice(&seal_note(note, recipient, slot as u8, rng)?);
+    }
+    Ok(envelope)
+}
+
+/// Trial-decrypts both notes of an envelope.
+///
+/// A slot is `None` when the note was not sealed to `viewing_key`. Malformed envelopes and
+/// unknown versions are errors.
+pub fn decrypt_notes(
+    envelope: &[u8],
+    viewing_key: &ViewingKey,
+) -> Result<[Option<NotePlaintext>; 2], ClientError> {
+    match envelope.first() {
+        Some(&NOTE_ENVELOPE_VERSION) => {}
+        Some(&version) => return Err(ClientError::UnsupportedNoteVersion(version)),
+        None => return Err(ClientError::InvalidNoteEnvelope),
+    }
+    if envelope.len() != NOTE_ENVELOPE_LEN {
+        return Err(ClientError::InvalidNoteEnvelope);
+    }
+
+    let sealed = &envelope[1..];
+    Ok([
+        open_note(&sealed[..SEALED_NOTE_LEN], viewing_key, 0)?,
+        open_note(&sealed[SEALED_NOTE_LEN..], viewing_key, 1)?,
+    ])
+}
+
+fn seal_note<R: RngCore + CryptoRng>(
+    note: &NotePlaintext,
+    recipient: &ViewingPublicKey,
+    slot: u8,
+    rng: &mut R,
+) -> Result<Vec<u8>, ClientError> {
+    let ephemeral_secret = EphemeralSecret::random_from_rng(rng);
+    let ephemeral_public = PublicKey::from(&ephemeral_secret);
+    let recipient_public = PublicKey::from(recipient.0);
+
+    let shared_secret = ephemeral_secret.diffie_hellman(&recipient_public);
+    if !shared_secret.was_contributory() {
+        return Err(ClientError::NoteEncryption);
+    }
+    let cipher = note_cipher(shared_secret.as_bytes(), ephemeral_public.as_bytes(), &recipient.0)?;
+
+    let ciphertext = cipher
+        .encrypt(
+            Nonce::from_slice(&NOTE_NONCE),
+            Payload {
+                msg: &note.to_bytes(),
+                aad: &[NOTE_ENVELOPE_VERSION, slot],
+            },
+        )
+        .map_err(|_| ClientError::NoteEncryption)?;
+
+    let mut sealed = Vec::with_capacity(SEALED_NOTE_LEN);
+    sealed.extend_from_slice(ephemeral_public.as_bytes());
+    sealed.extend_from_slice(&ciphertext);
+    Ok(sealed)
+}
+
+fn open_note(
+    sealed: &[u8],
+    viewing_key: &ViewingKey,
+    slot: u8,
+) -> Result<Option<NotePlaintext>, ClientError> {
+    let mut ephemeral_public = [0u8; 32];
+    ephemeral_public.copy_from_slice(&sealed[..32]);
+
+    let shared_secret = viewing_key.0.diffie_hellman(&PublicKey::from(ephemeral_public));
+    if !shared_secret.was_contributory() {
+        return Ok(None);
+    }
+    let cipher = note_cipher(
+        shared_secret.as_bytes(),
+        &ephemeral_public,
+        &viewing_key.public_key().0,
+    )?;
+
+    match cipher.decrypt(
+        Nonce::from_slice(&NOTE_NONCE),
+        Payload {
+            msg: &sealed[32..],
+            aad: &[NOTE_ENVELOPE_VERSION, slot],
+        },
+    ) {
+        Ok(plaintext) => Ok(Some(NotePlaintext::from_bytes(&plaintext)?)),
+        // Authentication failure: the note belongs to someone else
+        Err(_) => Ok(None),
+    }
+}
+
+fn note_cipher(
+    shared_secret: &[u8; 32],
+    ephemeral_public: &[u8; 32],
+    recipient_public: &[u8; 32],
+) -> Result<ChaCha20Poly1305, ClientError> {
+    let mut salt = [0u8; 64];
+    salt[..32].copy_from_slice(ephemeral_public);
+    salt[32..].copy_from_slice(recipient_public);
+
+    let mut key = [0u8; 32];
+    Hkdf::<Sha256>::new(Some(&salt), shared_secret)
+        .expand(NOTE_KEY_INFO, &mut key)
+        .map_err(|_| ClientError::NoteEncryption)?;
+    Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+}
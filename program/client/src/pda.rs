@@ -0,0 +1,78 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+
+/// Merkle tree account PDA: `[b"merkle_tree", pool_id (u16 LE), tree_index (u32 LE)]`
+pub fn find_merkle_tree_address(program_id: &Pubkey, pool_id: u16, tree_index: u32) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"merkle_tree", &pool_id.to_le_bytes(), &tree_index.to_le_bytes()],
+        program_id,
+    )
+}
+
+/// Tree token account PDA: `[b"tree_token", pool_id (u16 LE)]`
+pub fn find_tree_token_address(program_id: &Pubkey, pool_id: u16) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"tree_token", &pool_id.to_le_bytes()], program_id)
+}
+
+/// Global config PDA: `[b"global_config", pool_id (u16 LE)]`. This is also the authority of
+/// every reserve of the pool.
+pub fn find_global_config_address(program_id: &Pubkey, pool_id: u16) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"global_config", &pool_id.to_le_bytes()], program_id)
+}
+
+/// Reserve token account for `mint`: the associated token account owned by the pool's global config.
+pub fn reserve_token_account(
+    program_id: &Pubkey,
+    pool_id: u16,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Pubkey {
+    let (global_config, _) = find_global_config_address(program_id, pool_id);
+    get_associated_token_address_with_program_id(&global_config, mint, token_program)
+}
+
+/// Mint config PDA: `[b"mint_config", global_config, mint]`
+pub fn find_mint_config_address(program_id: &Pubkey, global_config: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"mint_config", global_config.as_ref(), mint.as_ref()],
+        program_id,
+    )
+}
+
+/// Deposit authority PDA of `deposit_relayed`: `[b"deposit_authority", output_commitment0]`.
+/// The depositor approves it as delegate of the deposit amount plus fee.
+pub fn find_deposit_authority_address(program_id: &Pubkey, output_commitment0: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"deposit_authority", output_commitment0.as_ref()], program_id)
+}
+
+/// Aggregator config PDA: `[b"aggregator_config", global_config, aggregator_program]`
+pub fn find_aggregator_config_address(
+    program_id: &Pubkey,
+    global_config: &Pubkey,
+    aggregator_program: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"aggregator_config", global_config.as_ref(), aggregator_program.as_ref()],
+        program_id,
+    )
+}
+
+/// Swap authority PDA: `[b"swap_authority", global_config]`. Transfer authority of the Jupiter
+/// routes forwarded by `swap`, it is delegated the swap input on the input reserve.
+pub fn find_swap_authority_address(program_id: &Pubkey, global_config: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"swap_authority", global_config.as_ref()], program_id)
+}
+
+/// Relayer account PDA: `[b"relayer", global_config, authority]`
+pub fn find_relayer_address(program_id: &Pubkey, global_config: &Pubkey, authority: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"relayer", global_config.as_ref(), authority.as_ref()],
+        program_id,
+    )
+}
+
+/// Verifying key account PDA: `[b"verifying_key", circuit]` (`yona::CIRCUIT_TRANSACTION2`, ...).
+/// Not scoped by pool, every pool uses the same key.
+pub fn find_verifying_key_address(program_id: &Pubkey, circuit: u8) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"verifying_key", &circuit.to_le_bytes()], program_id)
+}
@@ -0,0 +1,468 @@
+//! Host-side preflight of relayed transactions.
+//!
+//! Runs the checks of the `withdraw` and `swap` handlers with the program's own functions
+//! (`yona::utils`) against fetched accounts, so a relayer can drop a transaction that would
+//! fail on-chain before paying for it. Every check runs, the report lists all failures with
+//! the error the program would return.
+use anchor_lang::prelude::*;
+use anchor_spl::token::spl_token::native_mint;
+use ark_bn254::Fr;
+use ark_ff::PrimeField;
+use yona::jupiter::{decode_route, validate_route, ExpectedRoute};
+use yona::utils;
+use yona::{
+    AggregatorConfig, CompressedProof, ErrorCode, ExtData, ExtDataMinified, GlobalConfig,
+    MerkleTreeAccount, MintConfig, RelayerAccount, SwapExtData, SwapExtDataMinified,
+    VerifyingKeyAccount,
+};
+
+use crate::instructions::SwapAccounts;
+use crate::pda::{find_global_config_address, find_swap_authority_address, reserve_token_account};
+
+/// Check performed by the preflight, in handler order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PreflightCheck {
+    /// The relayer is bonded and not deregistering (`relayer_account` account constraints)
+    Relayer,
+    /// The fee token account is owned by the relayer's registered fee recipient
+    /// (`fee_recipient_account` account constraint)
+    FeeRecipient,
+    /// The swap output mint is enabled (`output_mint_config` account constraint), withdrawals
+    /// and swap inputs may use disabled mints
+    Mint,
+    /// The pool allows the Jupiter program (`aggregator_config` account constraint)
+    Aggregator,
+    /// `proof.root` is known by the active tree or the retired tree
+    KnownRoot,
+    /// The ext data hashes to `proof.ext_data_hash`
+    ExtDataHash,
+    /// Sign and range of the external amounts
+    ExtAmount,
+    /// Public amounts match the external amounts and fee
+    PublicAmount,
+    /// Fee matches the mint's withdrawal fee rate, self-relayed withdrawals and the input side
+    /// of swaps take no fee
+    Fee,
+    /// Fee is within the fee rate advertised by the relayer
+    RelayerFee,
+    /// Groth16 proof against the accepted verifying keys
+    Proof,
+    /// The recipient token account is owned by the recipient, or by the relayer for wrapped SOL
+    RecipientTokenAccount,
+    /// The Jupiter route swaps the input amount from the input reserve into the output reserve
+    Route,
+    /// The quoted output covers `ext_min_amount_out` and the surplus is within the relayer's rate
+    SwapSurplus,
+}
+
+#[derive(Debug)]
+pub struct PreflightFailure {
+    pub check: PreflightCheck,
+    pub error: Error,
+}
+
+impl PreflightFailure {
+    /// Custom program error code the transaction would fail with (`u32::from(ErrorCode)`)
+    pub fn error_code(&self) -> Option<u32> {
+        match &self.error {
+            Error::AnchorError(error) => Some(error.error_code_number),
+            Error::ProgramError(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct PreflightReport {
+    pub failures: Vec<PreflightFailure>,
+}
+
+impl PreflightReport {
+    /// True when the transaction passes every check
+    pub fn is_ok(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    pub fn failure(&self, check: PreflightCheck) -> Option<&PreflightFailure> {
+        self.failures.iter().find(|failure| failure.check == check)
+    }
+
+    fn record(&mut self, check: PreflightCheck, result: Result<()>) {
+        if let Err(error) = result {
+            self.failures.push(PreflightFailure { check, error });
+        }
+    }
+}
+
+/// On-chain state the checks run against, as fetched by the relayer.
+pub struct PreflightState<'a> {
+    pub tree_account: &'a MerkleTreeAccount,
+    /// Tree passed as `retired_tree_account`, if any
+    pub retired_tree_account: Option<&'a MerkleTreeAccount>,
+    /// `VerifyingKeyAccount` of `yona::CIRCUIT_TRANSACTION2`
+    pub verifying_key: &'a VerifyingKeyAccount,
+    pub global_config: &'a GlobalConfig,
+    /// Cluster unix timestamp, decides whether the previous verifying key is still accepted
+    pub now: i64,
+}
+
+/// Runs the checks of the `withdraw` handler.
+/// `relayer_account` is `None` for a withdrawal self-relayed by an unregistered relayer.
+/// `recipient_token_account_owner` is the owner of the `recipient_token_account` token account.
+/// `fee_recipient_owner` is the owner of the `fee_recipient` token account.
+#[allow(clippy::too_many_arguments)]
+pub fn preflight_withdraw(
+    state: &PreflightState,
+    mint_config: &MintConfig,
+    relayer_account: Option<&RelayerAccount>,
+    input_mint: Pubkey,
+    relayer: Pubkey,
+    recipient: Pubkey,
+    recipient_token_account_owner: Pubkey,
+    fee_recipient: Pubkey,
+    fee_recipient_owner: Pubkey,
+    proof: &CompressedProof,
+    ext_data_minified: &ExtDataMinified,
+    encrypted_output: &[u8],
+) -> PreflightReport {
+    let ext_data = ExtData::from_minified(&recipient, &fee_recipient, ext_data_minified.clone());
+    let mut report = PreflightReport::default();
+
+    // Account constraints of the registered relayer, a self-relayed withdrawal has none
+    if let Some(relayer_account) = relayer_account {
+        report.record(
+            PreflightCheck::Relayer,
+            check_relayer(state, relayer_account),
+        );
+        report.record(
+            PreflightCheck::FeeRecipient,
+            check_fee_recipient(relayer_account, fee_recipient_owner),
+        );
+    }
+    report.record(PreflightCheck::KnownRoot, check_known_root(state, proof));
+    report.record(
+        PreflightCheck::ExtDataHash,
+        utils::calculate_complete_ext_data_hash(
+            ext_data.recipient,
+            ext_data.ext_amount,
+            encrypted_output,
+            ext_data.fee,
+            ext_data.fee_recipient,
+            input_mint,
+            input_mint,
+        )
+        .and_then(|hash| check_ext_data_hash(hash, proof)),
+    );
+    report.record(
+        PreflightCheck::ExtAmount,
+        check_withdraw_ext_amount(&ext_data),
+    );
+    report.record(
+        PreflightCheck::PublicAmount,
+        check_withdraw_public_amounts(&ext_data, proof),
+    );
+    report.record(
+        PreflightCheck::Fee,
+        match relayer_account {
+            Some(_) => utils::validate_fee(
+                ext_data.ext_amount,
+                ext_data.fee,
+                mint_config.deposit_fee_rate(state.global_config),
+                mint_config.withdrawal_fee_rate(state.global_config),
+                state.global_config.fee_error_margin,
+            ),
+            None => check_self_relayed_fee(&ext_data),
+        },
+    );
+    if let Some(relayer_account) = relayer_account {
+        report.record(
+            PreflightCheck::RelayerFee,
+            check_relayer_fee(relayer_account, &ext_data),
+        );
+    }
+    report.record(
+        PreflightCheck::Proof,
+        check_proof(state, proof, input_mint, input_mint),
+    );
+    report.record(
+        PreflightCheck::RecipientTokenAccount,
+        check_recipient_token_account(input_mint, relayer, recipient, recipient_token_account_owner),
+    );
+
+    report
+}
+
+/// Runs the checks of the `swap` handler against the accounts the relayer is about to send.
+/// The relayer is paid the output above `ext_min_amount_out`, the ext data fee must be zero.
+/// `aggregator_config` is the pool's entry for the Jupiter program, `None` when missing.
+/// `fee_recipient_owner` is the owner of `accounts.fee_recipient_account`.
+#[allow(clippy::too_many_arguments)]
+pub fn preflight_swap(
+    state: &PreflightState,
+    output_mint_config: &MintConfig,
+    relayer_account: &RelayerAccount,
+    fee_recipient_owner: Pubkey,
+    aggregator_config: Option<&AggregatorConfig>,
+    accounts: &SwapAccounts,
+    proof: &CompressedProof,
+    ext_data_minified: &SwapExtDataMinified,
+    encrypted_output: &[u8],
+    jupiter_swap_data: &[u8],
+) -> PreflightReport {
+    let route_accounts: Vec<(Pubkey, bool)> = accounts
+        .jupiter_accounts
+        .iter()
+        .map(|account| (account.pubkey, account.is_writable))
+        .collect();
+    let route_hash = utils::calculate_route_hash(jupiter_swap_data, &route_accounts);
+    let ext_data = SwapExtData::from_minified(
+        &accounts.fee_recipient_account,
+        route_hash.as_ref().copied().unwrap_or_default(),
+        ext_data_minified.clone(),
+    );
+    let mut report = PreflightReport::default();
+
+    report.record(
+        PreflightCheck::Relayer,
+        check_relayer(state, relayer_account),
+    );
+    report.record(
+        PreflightCheck::FeeRecipient,
+        check_fee_recipient(relayer_account, fee_recipient_owner),
+    );
+    report.record(PreflightCheck::Mint, check_mint_enabled(output_mint_config));
+    report.record(
+        PreflightCheck::Aggregator,
+        check_aggregator(aggregator_config),
+    );
+    report.record(PreflightCheck::KnownRoot, check_known_root(state, proof));
+    report.record(
+        PreflightCheck::ExtDataHash,
+        route_hash
+            .and_then(|route_hash| {
+                utils::calculate_swap_ext_data_hash(
+                    ext_data.ext_amount,
+                    ext_data.ext_min_amount_out,
+                    encrypted_output,
+                    ext_data.fee,
+                    ext_data.fee_recipient,
+                    accounts.input_mint,
+                    accounts.output_mint,
+                    route_hash,
+                )
+            })
+            .and_then(|hash| check_ext_data_hash(hash, proof)),
+    );
+    report.record(PreflightCheck::ExtAmount, check_swap_ext_amounts(&ext_data));
+    report.record(
+        PreflightCheck::PublicAmount,
+        check_swap_public_amounts(&ext_data, proof),
+    );
+    report.record(PreflightCheck::Fee, check_swap_fee(&ext_data));
+    report.record(
+        PreflightCheck::Proof,
+        check_proof(state, proof, accounts.input_mint, accounts.output_mint),
+    );
+    report.record(
+        PreflightCheck::Route,
+        check_swap_route(accounts, &ext_data, jupiter_swap_data),
+    );
+    report.record(
+        PreflightCheck::SwapSurplus,
+        check_swap_surplus(relayer_account, &ext_data, jupiter_swap_data),
+    );
+
+    report
+}
+
+fn check_relayer(state: &PreflightState, relayer_account: &RelayerAccount) -> Result<()> {
+    require!(
+        relayer_account.bond >= state.global_config.min_relayer_bond,
+        ErrorCode::RelayerBondTooLow
+    );
+    require!(
+        !relayer_account.is_deregistering(),
+        ErrorCode::RelayerDeregistering
+    );
+    Ok(())
+}
+
+fn check_fee_recipient(
+    relayer_account: &RelayerAccount,
+    fee_recipient_owner: Pubkey,
+) -> Result<()> {
+    require!(
+        fee_recipient_owner == relayer_account.fee_recipient,
+        ErrorCode::InvalidFeeRecipient
+    );
+    Ok(())
+}
+
+fn check_mint_enabled(mint_config: &MintConfig) -> Result<()> {
+    require!(mint_config.enabled, ErrorCode::UnsupportedMintAddress);
+    Ok(())
+}
+
+fn check_known_root(state: &PreflightState, proof: &CompressedProof) -> Result<()> {
+    utils::check_known_root(state.tree_account, state.retired_tree_account, proof.root)
+}
+
+fn check_ext_data_hash(calculated_ext_data_hash: [u8; 32], proof: &CompressedProof) -> Result<()> {
+    require!(
+        Fr::from_le_bytes_mod_order(&calculated_ext_data_hash)
+            == Fr::from_be_bytes_mod_order(&proof.ext_data_hash),
+        ErrorCode::ExtDataHashMismatch
+    );
+    Ok(())
+}
+
+fn check_withdraw_ext_amount(ext_data: &ExtData) -> Result<()> {
+    require!(ext_data.ext_amount < 0, ErrorCode::InvalidExtAmount);
+    Ok(())
+}
+
+fn check_withdraw_public_amounts(ext_data: &ExtData, proof: &CompressedProof) -> Result<()> {
+    require!(
+        utils::check_public_amount(ext_data.ext_amount, ext_data.fee, proof.public_amount0),
+        ErrorCode::InvalidPublicAmountData
+    );
+    require!(
+        proof.public_amount1 == [0; 32],
+        ErrorCode::DualTokenNotSupported
+    );
+    Ok(())
+}
+
+fn check_recipient_token_account(
+    input_mint: Pubkey,
+    relayer: Pubkey,
+    recipient: Pubkey,
+    recipient_token_account_owner: Pubkey,
+) -> Result<()> {
+    // Wrapped SOL goes through an account of the relayer, which closes it and pays the
+    // recipient in lamports
+    let expected_owner = if input_mint == native_mint::ID { relayer } else { recipient };
+    require!(
+        recipient_token_account_owner == expected_owner,
+        ErrorCode::Unauthorized
+    );
+    Ok(())
+}
+
+fn check_self_relayed_fee(ext_data: &ExtData) -> Result<()> {
+    require!(ext_data.fee == 0, ErrorCode::InvalidFeeRecipient);
+    Ok(())
+}
+
+fn check_relayer_fee(relayer_account: &RelayerAccount, ext_data: &ExtData) -> Result<()> {
+    require!(
+        ext_data.fee <= relayer_account.max_fee(ext_data.ext_amount.unsigned_abs())?,
+        ErrorCode::RelayerFeeTooHigh
+    );
+    Ok(())
+}
+
+fn check_swap_ext_amounts(ext_data: &SwapExtData) -> Result<()> {
+    require!(ext_data.ext_amount < 0, ErrorCode::InvalidExtAmount);
+    require!(
+        ext_data.ext_min_amount_out >= 0,
+        ErrorCode::InvalidExtAmount
+    );
+    Ok(())
+}
+
+fn check_swap_public_amounts(ext_data: &SwapExtData, proof: &CompressedProof) -> Result<()> {
+    require!(
+        utils::check_public_amount(ext_data.ext_amount, ext_data.fee, proof.public_amount0),
+        ErrorCode::InvalidPublicAmountData
+    );
+    // zero fee for swap out
+    require!(
+        utils::check_public_amount(ext_data.ext_min_amount_out, 0, proof.public_amount1),
+        ErrorCode::InvalidPublicAmountData
+    );
+    Ok(())
+}
+
+fn check_swap_fee(ext_data: &SwapExtData) -> Result<()> {
+    require!(ext_data.fee == 0, ErrorCode::InvalidFeeAmount);
+    Ok(())
+}
+
+fn check_aggregator(aggregator_config: Option<&AggregatorConfig>) -> Result<()> {
+    require!(
+        aggregator_config.is_some_and(|config| config.enabled),
+        ErrorCode::AggregatorNotAllowed
+    );
+    Ok(())
+}
+
+fn check_swap_route(
+    accounts: &SwapAccounts,
+    ext_data: &SwapExtData,
+    jupiter_swap_data: &[u8],
+) -> Result<()> {
+    require!(
+        !jupiter_swap_data.is_empty(),
+        ErrorCode::InvalidJupiterSwapData
+    );
+    let program_id = yona::ID;
+    let (global_config, _) = find_global_config_address(&program_id, accounts.pool_id);
+    let route_keys: Vec<Pubkey> = accounts
+        .jupiter_accounts
+        .iter()
+        .map(|account| account.pubkey)
+        .collect();
+    validate_route(
+        jupiter_swap_data,
+        &route_keys,
+        &ExpectedRoute {
+            program_id: yona::jupiter_aggregator::ID,
+            authority: find_swap_authority_address(&program_id, &global_config).0,
+            source_token_account: reserve_token_account(
+                &program_id,
+                accounts.pool_id,
+                &accounts.input_mint,
+                &accounts.input_token_program,
+            ),
+            destination_token_account: reserve_token_account(
+                &program_id,
+                accounts.pool_id,
+                &accounts.output_mint,
+                &accounts.output_token_program,
+            ),
+            source_mint: accounts.input_mint,
+            destination_mint: accounts.output_mint,
+            in_amount: ext_data.ext_amount.unsigned_abs(),
+        },
+    )?;
+    Ok(())
+}
+
+/// Output check of the handler, with the route's quote as the amount received.
+fn check_swap_surplus(
+    relayer_account: &RelayerAccount,
+    ext_data: &SwapExtData,
+    jupiter_swap_data: &[u8],
+) -> Result<()> {
+    let route = decode_route(jupiter_swap_data)?;
+    let surplus = u64::try_from(ext_data.ext_min_amount_out)
+        .ok()
+        .and_then(|min_amount| route.quoted_out_amount.checked_sub(min_amount))
+        .ok_or(ErrorCode::InsufficientSwapOutput)?;
+    require!(
+        surplus <= relayer_account.max_fee(route.quoted_out_amount)?,
+        ErrorCode::RelayerFeeTooHigh
+    );
+    Ok(())
+}
+
+fn check_proof(
+    state: &PreflightState,
+    proof: &CompressedProof,
+    mint_address_a: Pubkey,
+    mint_address_b: Pubkey,
+) -> Result<()> {
+    utils::check_valid_proof(state.verifying_key, state.now, |verifying_key| {
+        utils::verify_compressed_proof(proof.clone(), verifying_key, mint_address_a, mint_address_b)
+    })
+}
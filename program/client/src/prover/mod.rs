@@ -0,0 +1,231 @@
+//! Native Groth16 prover for the `transaction2` circuit.
+//!
+//! Witness generation uses the circuit wasm transpiled by `rust-witness` at build time
+//! (see `build.rs`), proving uses arkworks Groth16 with the snarkjs (circom) QAP reduction
+//! so the proofs verify against the verifying key exported from the same zkey.
+
+mod qap;
+mod zkey;
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek};
+use std::path::Path;
+
+use ark_bn254::{Bn254, Fr, G1Affine, G2Affine};
+use ark_ff::{BigInteger, PrimeField};
+use ark_groth16::{prepare_verifying_key, Groth16, PreparedVerifyingKey, ProvingKey};
+use ark_relations::r1cs::ConstraintMatrices;
+use ark_serialize::CanonicalSerialize;
+use ark_std::rand::{CryptoRng, RngCore};
+use ark_std::UniformRand;
+use num_bigint::{BigInt, BigUint, Sign};
+use yona::CompressedProof;
+
+use crate::errors::ClientError;
+use crate::field::fr_to_be_bytes;
+pub use qap::CircomReduction;
+pub use zkey::read_zkey;
+
+rust_witness::witness!(transaction2);
+
+/// Number of public inputs of the transaction circuit
+pub const NR_PUBLIC_INPUTS: usize = 10;
+
+/// Private and public inputs of the `transaction2` circuit.
+///
+/// Field names follow the circuit signals. Mint addresses are the pubkey bytes read
+/// big-endian modulo the field size, `ext_data_hash` is the sha256 ext data hash read
+/// little-endian modulo the field size (see [`ext_data_hash_to_field`]).
+#[derive(Clone, Debug)]
+pub struct TransactionInputs {
+    pub root: Fr,
+    pub input_nullifier: [Fr; 2],
+    pub output_commitment: [Fr; 2],
+    pub public_amount0: Fr,
+    pub public_amount1: Fr,
+    pub ext_data_hash: Fr,
+    pub mint_address0: Fr,
+    pub mint_address1: Fr,
+    pub in_amount: [Fr; 2],
+    pub in_mint_address: [Fr; 2],
+    pub in_private_key: [Fr; 2],
+    pub in_blinding: [Fr; 2],
+    pub in_path_indices: [Fr; 2],
+    pub in_path_elements: [Vec<Fr>; 2],
+    pub out_amount: [Fr; 2],
+    pub out_mint_address: [Fr; 2],
+    pub out_pubkey: [Fr; 2],
+    pub out_blinding: [Fr; 2],
+}
+
+impl TransactionInputs {
+    fn to_witness_inputs(&self) -> HashMap<String, Vec<BigInt>> {
+        let single = |x: &Fr| vec![fr_to_bigint(x)];
+        let pair = |x: &[Fr; 2]| x.iter().map(fr_to_bigint).collect::<Vec<_>>();
+
+        let mut inputs = HashMap::new();
+        inputs.insert("root".to_string(), single(&self.root));
+        inputs.insert("inputNullifier".to_string(), pair(&self.input_nullifier));
+        inputs.insert("outputCommitment".to_string(), pair(&self.output_commitment));
+        inputs.insert("publicAmount0".to_string(), single(&self.public_amount0));
+        inputs.insert("publicAmount1".to_string(), single(&self.public_amount1));
+        inputs.insert("extDataHash".to_string(), single(&self.ext_data_hash));
+        inputs.insert("mintAddress0".to_string(), single(&self.mint_address0));
+        inputs.insert("mintAddress1".to_string(), single(&self.mint_address1));
+        inputs.insert("inAmount".to_string(), pair(&self.in_amount));
+        inputs.insert("inMintAddress".to_string(), pair(&self.in_mint_address));
+        inputs.insert("inPrivateKey".to_string(), pair(&self.in_private_key));
+        inputs.insert("inBlinding".to_string(), pair(&self.in_blinding));
+        inputs.insert("inPathIndices".to_string(), pair(&self.in_path_indices));
+        // 2D signals are flattened row by row, as snarkjs does
+        inputs.insert(
+            "inPathElements".to_string(),
+            self.in_path_elements.iter().flatten().map(fr_to_bigint).collect(),
+        );
+        inputs.insert("outAmount".to_string(), pair(&self.out_amount));
+        inputs.insert("outMintAddress".to_string(), pair(&self.out_mint_address));
+        inputs.insert("outPubkey".to_string(), pair(&self.out_pubkey));
+        inputs.insert("outBlinding".to_string(), pair(&self.out_blinding));
+        inputs
+    }
+}
+
+/// Converts the sha256 ext data hash into the field element fed to the circuit.
+/// Matches the on-chain check `Fr::from_le_bytes_mod_order(&calculated_ext_data_hash)`.
+pub fn ext_data_hash_to_field(ext_data_hash: &[u8; 32]) -> Fr {
+    Fr::from_le_bytes_mod_order(ext_data_hash)
+}
+
+pub struct TransactionProver {
+    proving_key: ProvingKey<Bn254>,
+    prepared_verifying_key: PreparedVerifyingKey<Bn254>,
+    matrices: ConstraintMatrices<Fr>,
+}
+
+impl TransactionProver {
+    /// Loads the prover from a snarkjs zkey file (e.g. `transaction2.zkey`).
+    pub fn from_zkey_file<P: AsRef<Path>>(path: P) -> Result<Self, ClientError> {
+        let mut reader = BufReader::new(File::open(path)?);
+        Self::from_zkey(&mut reader)
+    }
+
+    pub fn from_zkey<R: Read + Seek>(reader: &mut R) -> Result<Self, ClientError> {
+        let (proving_key, matrices) =
+            read_zkey(reader).map_err(|e| ClientError::InvalidProvingKey(e.to_string()))?;
+        if proving_key.vk.gamma_abc_g1.len() != NR_PUBLIC_INPUTS + 1 {
+            return Err(ClientError::InvalidProvingKey(format!(
+                "expected {} public inputs, got {}",
+                NR_PUBLIC_INPUTS,
+                proving_key.vk.gamma_abc_g1.len().saturating_sub(1)
+            )));
+        }
+        let prepared_verifying_key = prepare_verifying_key(&proving_key.vk);
+        Ok(Self {
+            proving_key,
+            prepared_verifying_key,
+            matrices,
+        })
+    }
+
+    /// Generates a proof for `inputs` and encodes it the way `verify_compressed_proof` expects:
+    /// negated and compressed `proof_a`, compressed `proof_b`/`proof_c`, and the public inputs
+    /// as big-endian field elements in circuit order
+    /// [root, publicAmount0, publicAmount1, extDataHash, mintAddress0, mintAddress1,
+    ///  inputNullifier0, inputNullifier1, outputCommitment0, outputCommitment1].
+    pub fn prove<R: RngCore + CryptoRng>(
+        &self,
+        inputs: &TransactionInputs,
+        rng: &mut R,
+    ) -> Result<CompressedProof, ClientError> {
+        let witness = transaction2_witness(inputs.to_witness_inputs());
+        let full_assignment = witness.iter().map(bigint_to_fr).collect::<Vec<_>>();
+
+        let num_inputs = self.matrices.num_instance_variables;
+        let num_constraints = self.matrices.num_constraints;
+        if full_assignment.len() < num_inputs {
+            return Err(ClientError::WitnessGeneration(format!(
+                "witness has {} elements, expected at least {}",
+                full_assignment.len(),
+                num_inputs
+            )));
+        }
+
+        let r = Fr::rand(rng);
+        let s = Fr::rand(rng);
+        let proof = Groth16::<Bn254, CircomReduction>::create_proof_with_reduction_and_matrices(
+            &self.proving_key,
+            r,
+            s,
+            &self.matrices,
+            num_inputs,
+            num_constraints,
+            &full_assignment,
+        )
+        .map_err(|e| ClientError::ProofGeneration(e.to_string()))?;
+
+        // Public signals come right after the constant 1 in the witness
+        let public_inputs = &full_assignment[1..num_inputs];
+        let verified = Groth16::<Bn254>::verify_proof(
+            &self.prepared_verifying_key,
+            &proof,
+            public_inputs,
+        )
+        .map_err(|e| ClientError::ProofGeneration(e.to_string()))?;
+        if !verified {
+            return Err(ClientError::ProofVerificationFailed);
+        }
+
+        // The on-chain verifier expects -A so the pairing check becomes a product equal to one
+        let proof_a = compress_g1(&(-proof.a))?;
+        let proof_b = compress_g2(&proof.b)?;
+        let proof_c = compress_g1(&proof.c)?;
+
+        let public = public_inputs.iter().map(fr_to_be_bytes).collect::<Vec<_>>();
+        Ok(CompressedProof {
+            proof_a,
+            proof_b,
+            proof_c,
+            root: public[0],
+            public_amount0: public[1],
+            public_amount1: public[2],
+            ext_data_hash: public[3],
+            input_nullifiers: [public[6], public[7]],
+            output_commitments: [public[8], public[9]],
+        })
+    }
+}
+
+// arkworks compresses little-endian with the flags in the last byte,
+// the alt_bn128 syscalls expect big-endian with the flags in the first byte
+fn compress_g1(point: &G1Affine) -> Result<[u8; 32], ClientError> {
+    let mut bytes = [0u8; 32];
+    point
+        .serialize_compressed(&mut bytes[..])
+        .map_err(|_| ClientError::ProofSerialization)?;
+    bytes.reverse();
+    Ok(bytes)
+}
+
+fn compress_g2(point: &G2Affine) -> Result<[u8; 64], ClientError> {
+    let mut bytes = [0u8; 64];
+    point
+        .serialize_compressed(&mut bytes[..])
+        .map_err(|_| ClientError::ProofSerialization)?;
+    bytes.reverse();
+    Ok(bytes)
+}
+
+fn fr_to_bigint(value: &Fr) -> BigInt {
+    BigInt::from_biguint(Sign::Plus, BigUint::from_bytes_le(&value.into_bigint().to_bytes_le()))
+}
+
+fn bigint_to_fr(value: &BigInt) -> Fr {
+    let (sign, magnitude) = value.to_bytes_le();
+    let fr = Fr::from_le_bytes_mod_order(&magnitude);
+    if sign == Sign::Minus {
+        -fr
+    } else {
+        fr
+    }
+}
@@ -0,0 +1,265 @@
+//! snarkjs zkey parsing
+//!
+//! Adapted from ark-circom (https://github.com/arkworks-rs/circom-compat/blob/master/src/zkey.rs)
+//! and ported to arkworks 0.5, which is what the on-chain program uses.
+//!
+//! Each zkey file is broken into sections:
+//!  Header(1)
+//!       Prover Type 1 Groth
+//!  HeaderGroth(2)
+//!       n8q, q, n8r, r, NVars, NPub, DomainSize,
+//!       alpha1, beta1, delta1, beta2, gamma2, delta2
+//!  IC(3)
+//!  Coefs(4)
+//!  PointsA(5)
+//!  PointsB1(6)
+//!  PointsB2(7)
+//!  PointsC(8)
+//!  PointsH(9)
+//!  Contributions(10)
+use ark_bn254::{Bn254, Fq, Fq2, Fr, G1Affine, G2Affine};
+use ark_ff::{BigInteger256, PrimeField, Zero};
+use ark_groth16::{ProvingKey, VerifyingKey};
+use ark_relations::r1cs::ConstraintMatrices;
+use ark_serialize::{CanonicalDeserialize, SerializationError};
+
+use std::{
+    collections::HashMap,
+    io::{Read, Seek, SeekFrom},
+};
+
+type IoResult<T> = Result<T, SerializationError>;
+
+#[derive(Clone, Debug)]
+struct Section {
+    position: u64,
+}
+
+/// Reads a snarkjs zkey file into an arkworks proving key and the R1CS constraint matrices.
+pub fn read_zkey<R: Read + Seek>(
+    reader: &mut R,
+) -> IoResult<(ProvingKey<Bn254>, ConstraintMatrices<Fr>)> {
+    let mut binfile = BinFile::new(reader)?;
+    let proving_key = binfile.proving_key()?;
+    let matrices = binfile.matrices()?;
+    Ok((proving_key, matrices))
+}
+
+struct BinFile<'a, R> {
+    sections: HashMap<u32, Vec<Section>>,
+    reader: &'a mut R,
+}
+
+impl<'a, R: Read + Seek> BinFile<'a, R> {
+    fn new(reader: &'a mut R) -> IoResult<Self> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != b"zkey" {
+            return Err(SerializationError::InvalidData);
+        }
+
+        let _version = u32::deserialize_uncompressed(&mut *reader)?;
+        let num_sections = u32::deserialize_uncompressed(&mut *reader)?;
+
+        let mut sections = HashMap::new();
+        for _ in 0..num_sections {
+            let section_id = u32::deserialize_uncompressed(&mut *reader)?;
+            let section_length = u64::deserialize_uncompressed(&mut *reader)?;
+
+            sections
+                .entry(section_id)
+                .or_insert_with(Vec::new)
+                .push(Section {
+                    position: reader.stream_position()?,
+                });
+
+            reader.seek(SeekFrom::Current(section_length as i64))?;
+        }
+
+        Ok(Self { sections, reader })
+    }
+
+    fn proving_key(&mut self) -> IoResult<ProvingKey<Bn254>> {
+        let header = self.groth_header()?;
+        let ic = self.g1_section(header.n_public + 1, 3)?;
+
+        let a_query = self.g1_section(header.n_vars, 5)?;
+        let b_g1_query = self.g1_section(header.n_vars, 6)?;
+        let b_g2_query = self.g2_section(header.n_vars, 7)?;
+        let l_query = self.g1_section(header.n_vars - header.n_public - 1, 8)?;
+        let h_query = self.g1_section(header.domain_size as usize, 9)?;
+
+        let vk = VerifyingKey::<Bn254> {
+            alpha_g1: header.alpha_g1,
+            beta_g2: header.beta_g2,
+            gamma_g2: header.gamma_g2,
+            delta_g2: header.delta_g2,
+            gamma_abc_g1: ic,
+        };
+
+        Ok(ProvingKey::<Bn254> {
+            vk,
+            beta_g1: header.beta_g1,
+            delta_g1: header.delta_g1,
+            a_query,
+            b_g1_query,
+            b_g2_query,
+            h_query,
+            l_query,
+        })
+    }
+
+    fn seek_section(&mut self, id: u32) -> IoResult<()> {
+        let position = self
+            .sections
+            .get(&id)
+            .and_then(|sections| sections.first())
+            .ok_or(SerializationError::InvalidData)?
+            .position;
+        self.reader.seek(SeekFrom::Start(position))?;
+        Ok(())
+    }
+
+    fn groth_header(&mut self) -> IoResult<HeaderGroth> {
+        self.seek_section(2)?;
+        HeaderGroth::read(&mut *self.reader)
+    }
+
+    /// Returns the A and B constraint matrices stored in the zkey.
+    /// C is not needed since the circom reduction only uses A and B.
+    fn matrices(&mut self) -> IoResult<ConstraintMatrices<Fr>> {
+        let header = self.groth_header()?;
+
+        self.seek_section(4)?;
+        let num_coeffs = u32::deserialize_uncompressed(&mut *self.reader)?;
+
+        let mut matrices = vec![vec![vec![]; header.domain_size as usize]; 2];
+        let mut max_constraint_index = 0;
+        for _ in 0..num_coeffs {
+            let matrix = u32::deserialize_uncompressed(&mut *self.reader)? as usize;
+            let constraint = u32::deserialize_uncompressed(&mut *self.reader)? as usize;
+            let signal = u32::deserialize_uncompressed(&mut *self.reader)? as usize;
+
+            let value = deserialize_field_fr(&mut *self.reader)?;
+            max_constraint_index = std::cmp::max(max_constraint_index, constraint);
+            matrices
+                .get_mut(matrix)
+                .and_then(|m| m.get_mut(constraint))
+                .ok_or(SerializationError::InvalidData)?
+                .push((value, signal));
+        }
+
+        // Remove the public input constraints, arkworks adds them later
+        let num_constraints = max_constraint_index - header.n_public;
+        matrices.iter_mut().for_each(|m| m.truncate(num_constraints));
+
+        let b = matrices.pop().unwrap_or_default();
+        let a = matrices.pop().unwrap_or_default();
+        let a_num_non_zero = a.iter().map(|lc| lc.len()).sum();
+        let b_num_non_zero = b.iter().map(|lc| lc.len()).sum();
+
+        Ok(ConstraintMatrices {
+            num_instance_variables: header.n_public + 1,
+            num_witness_variables: header.n_vars - header.n_public,
+            num_constraints,
+            a_num_non_zero,
+            b_num_non_zero,
+            c_num_non_zero: 0,
+            a,
+            b,
+            c: vec![],
+        })
+    }
+
+    fn g1_section(&mut self, num: usize, section_id: u32) -> IoResult<Vec<G1Affine>> {
+        self.seek_section(section_id)?;
+        (0..num).map(|_| deserialize_g1(&mut *self.reader)).collect()
+    }
+
+    fn g2_section(&mut self, num: usize, section_id: u32) -> IoResult<Vec<G2Affine>> {
+        self.seek_section(section_id)?;
+        (0..num).map(|_| deserialize_g2(&mut *self.reader)).collect()
+    }
+}
+
+struct HeaderGroth {
+    n_vars: usize,
+    n_public: usize,
+    domain_size: u32,
+    alpha_g1: G1Affine,
+    beta_g1: G1Affine,
+    beta_g2: G2Affine,
+    gamma_g2: G2Affine,
+    delta_g1: G1Affine,
+    delta_g2: G2Affine,
+}
+
+impl HeaderGroth {
+    fn read<R: Read>(reader: &mut R) -> IoResult<Self> {
+        // n8q, q (base field modulus), n8r, r (scalar field modulus)
+        let _n8q = u32::deserialize_uncompressed(&mut *reader)?;
+        let _q = BigInteger256::deserialize_uncompressed(&mut *reader)?;
+        let _n8r = u32::deserialize_uncompressed(&mut *reader)?;
+        let _r = BigInteger256::deserialize_uncompressed(&mut *reader)?;
+
+        let n_vars = u32::deserialize_uncompressed(&mut *reader)? as usize;
+        let n_public = u32::deserialize_uncompressed(&mut *reader)? as usize;
+        let domain_size = u32::deserialize_uncompressed(&mut *reader)?;
+
+        Ok(Self {
+            n_vars,
+            n_public,
+            domain_size,
+            alpha_g1: deserialize_g1(&mut *reader)?,
+            beta_g1: deserialize_g1(&mut *reader)?,
+            beta_g2: deserialize_g2(&mut *reader)?,
+            gamma_g2: deserialize_g2(&mut *reader)?,
+            delta_g1: deserialize_g1(&mut *reader)?,
+            delta_g2: deserialize_g2(&mut *reader)?,
+        })
+    }
+}
+
+// snarkjs stores the coefficients multiplied by R^2, so we need to divide by R once
+fn deserialize_field_fr<R: Read>(reader: &mut R) -> IoResult<Fr> {
+    let bigint = BigInteger256::deserialize_uncompressed(reader)?;
+    Ok(Fr::new_unchecked(Fr::new_unchecked(bigint).into_bigint()))
+}
+
+// Circom points are already in Montgomery form, Fq::new would multiply by R again
+fn deserialize_field<R: Read>(reader: &mut R) -> IoResult<Fq> {
+    let bigint = BigInteger256::deserialize_uncompressed(reader)?;
+    Ok(Fq::new_unchecked(bigint))
+}
+
+fn deserialize_field2<R: Read>(reader: &mut R) -> IoResult<Fq2> {
+    let c0 = deserialize_field(reader)?;
+    let c1 = deserialize_field(reader)?;
+    Ok(Fq2::new(c0, c1))
+}
+
+fn deserialize_g1<R: Read>(reader: &mut R) -> IoResult<G1Affine> {
+    let x = deserialize_field(reader)?;
+    let y = deserialize_field(reader)?;
+    if x.is_zero() && y.is_zero() {
+        return Ok(G1Affine::identity());
+    }
+    let point = G1Affine::new_unchecked(x, y);
+    if !point.is_on_curve() {
+        return Err(SerializationError::InvalidData);
+    }
+    Ok(point)
+}
+
+fn deserialize_g2<R: Read>(reader: &mut R) -> IoResult<G2Affine> {
+    let x = deserialize_field2(reader)?;
+    let y = deserialize_field2(reader)?;
+    if x.is_zero() && y.is_zero() {
+        return Ok(G2Affine::identity());
+    }
+    let point = G2Affine::new_unchecked(x, y);
+    if !point.is_on_curve() {
+        return Err(SerializationError::InvalidData);
+    }
+    Ok(point)
+}
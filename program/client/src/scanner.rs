@@ -0,0 +1,153 @@
+//! Wallet scanner: finds the UTXOs owned by a shielded keypair in the `CommitmentData` stream.
+//!
+//! Every `encrypted_output` is trial-decrypted with the wallet viewing key. A decrypted note is
+//! only accepted when the commitment rebuilt from it matches the on-chain commitment, and its
+//! spent status is read from the Light nullifier address space (see [`nullifier_address`]).
+use std::collections::{BTreeMap, HashSet};
+
+use anchor_lang::prelude::Pubkey;
+use yona::CommitmentData;
+
+use crate::errors::ClientError;
+use crate::field::fr_to_be_bytes;
+use crate::keypair::ShieldedKeypair;
+use crate::light::nullifier_address;
+use crate::note::{decrypt_notes, ViewingKey};
+use crate::utxo::Utxo;
+
+/// Tells whether a nullifier compressed account exists, i.e. whether the UTXO was spent.
+///
+/// Implemented on top of the Light indexer (`getCompressedAccount` by address) in services,
+/// [`HashSet`] is provided for tests and cached snapshots.
+pub trait NullifierSource {
+    fn is_spent(&self, nullifier_address: &[u8; 32]) -> Result<bool, ClientError>;
+}
+
+impl NullifierSource for HashSet<[u8; 32]> {
+    fn is_spent(&self, nullifier_address: &[u8; 32]) -> Result<bool, ClientError> {
+        Ok(self.contains(nullifier_address))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct OwnedUtxo {
+    pub pool_id: u16,
+    /// Tree holding the commitment, `utxo.index` is the leaf index in that tree
+    pub tree_index: u32,
+    pub utxo: Utxo,
+    pub commitment: [u8; 32],
+    pub nullifier: [u8; 32],
+    /// Address of the nullifier compressed account created when this UTXO is spent
+    pub nullifier_address: [u8; 32],
+    pub spent: bool,
+}
+
+pub struct WalletScanner {
+    keypair: ShieldedKeypair,
+    viewing_key: ViewingKey,
+    // keyed by leaf position so replayed events don't create duplicates. Leaf indices restart
+    // at 0 in every pool and after every rollover, so the pool and the tree are part of the key
+    utxos: BTreeMap<(u16, u32, u64), OwnedUtxo>,
+}
+
+impl WalletScanner {
+    /// The viewing key is derived from `keypair`, the private key is needed to compute nullifiers.
+    pub fn new(keypair: ShieldedKeypair) -> Result<Self, ClientError> {
+        let viewing_key = ViewingKey::from_keypair(&keypair)?;
+        Ok(Self {
+            keypair,
+            viewing_key,
+            utxos: BTreeMap::new(),
+        })
+    }
+
+    /// Trial-decrypts one event, returns the number of new UTXOs found.
+    /// Envelopes that can't be parsed (unknown version, legacy payloads) are skipped.
+    pub fn scan_event(&mut self, event: &CommitmentData) -> Result<usize, ClientError> {
+        let notes = match decrypt_notes(&event.encrypted_output, &self.viewing_key) {
+            Ok(notes) => notes,
+            Err(ClientError::InvalidNoteEnvelope) | Err(ClientError::UnsupportedNoteVersion(_)) => {
+                return Ok(0)
+            }
+            Err(e) => return Err(e),
+        };
+
+        let mut found = 0;
+        for (offset, (note, commitment)) in notes
+            .into_iter()
+            .zip([event.commitment0, event.commitment1])
+            .enumerate()
+        {
+            let Some(note) = note else { continue };
+            let index = event.index + offset as u64;
+            let position = (event.pool_id, event.tree_index, index);
+            if self.utxos.contains_key(&position) {
+                continue;
+            }
+
+            let utxo = Utxo::new(
+                note.amount,
+                note.blinding,
+                self.keypair.clone(),
+                index,
+                note.mint_address,
+            );
+            // The note must open the on-chain commitment, otherwise it's not spendable by us
+            if fr_to_be_bytes(&utxo.commitment()?) != commitment {
+                continue;
+            }
+
+            let nullifier = fr_to_be_bytes(&utxo.nullifier()?);
+            self.utxos.insert(
+                position,
+                OwnedUtxo {
+                    pool_id: event.pool_id,
+                    tree_index: event.tree_index,
+                    utxo,
+                    commitment,
+                    nullifier,
+                    nullifier_address: nullifier_address(&nullifier),
+                    spent: false,
+                },
+            );
+            found += 1;
+        }
+        Ok(found)
+    }
+
+    pub fn scan<'a, I>(&mut self, events: I) -> Result<usize, ClientError>
+    where
+        I: IntoIterator<Item = &'a CommitmentData>,
+    {
+        let mut found = 0;
+        for event in events {
+            found += self.scan_event(event)?;
+        }
+        Ok(found)
+    }
+
+    /// Updates the spent status of every unspent UTXO. Spent is final, so spent UTXOs are not queried again.
+    pub fn refresh_spent<N: NullifierSource>(&mut self, source: &N) -> Result<(), ClientError> {
+        for owned in self.utxos.values_mut().filter(|owned| !owned.spent) {
+            owned.spent = source.is_spent(&owned.nullifier_address)?;
+        }
+        Ok(())
+    }
+
+    pub fn utxos(&self) -> impl Iterator<Item = &OwnedUtxo> {
+        self.utxos.values()
+    }
+
+    pub fn unspent(&self) -> impl Iterator<Item = &OwnedUtxo> {
+        self.utxos.values().filter(|owned| !owned.spent)
+    }
+
+    /// Shielded balance per mint, over unspent UTXOs.
+    pub fn balances(&self) -> BTreeMap<Pubkey, u128> {
+        let mut balances = BTreeMap::new();
+        for owned in self.unspent().filter(|owned| owned.utxo.amount > 0) {
+            *balances.entry(owned.utxo.mint_address).or_insert(0u128) += owned.utxo.amount as u128;
+        }
+        balances
+    }
+}
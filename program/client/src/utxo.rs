@@ -0,0 +1,72 @@
+use anchor_lang::prelude::Pubkey;
+use ark_bn254::Fr;
+use ark_ff::PrimeField;
+use light_poseidon::{Poseidon, PoseidonHasher};
+use rand::{CryptoRng, RngCore};
+
+use crate::errors::ClientError;
+use crate::field::mint_to_field;
+use crate::keypair::ShieldedKeypair;
+
+/// Shielded UTXO, mirrors `Utxo` in `tests/lib/utxo.ts`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Utxo {
+    pub amount: u64,
+    pub blinding: Fr,
+    pub keypair: ShieldedKeypair,
+    /// Leaf index in the merkle tree, only meaningful for spent UTXOs
+    pub index: u64,
+    pub mint_address: Pubkey,
+}
+
+impl Utxo {
+    pub fn new(
+        amount: u64,
+        blinding: Fr,
+        keypair: ShieldedKeypair,
+        index: u64,
+        mint_address: Pubkey,
+    ) -> Self {
+        Self {
+            amount,
+            blinding,
+            keypair,
+            index,
+            mint_address,
+        }
+    }
+
+    /// Zero-amount UTXO used to pad unused circuit inputs/outputs.
+    pub fn dummy<R: RngCore + CryptoRng>(
+        keypair: ShieldedKeypair,
+        mint_address: Pubkey,
+        rng: &mut R,
+    ) -> Self {
+        Self::new(0, random_blinding(rng), keypair, 0, mint_address)
+    }
+
+    /// `Poseidon(amount, pubkey, blinding, mint)`
+    pub fn commitment(&self) -> Result<Fr, ClientError> {
+        Ok(Poseidon::<Fr>::new_circom(4)?.hash(&[
+            Fr::from(self.amount),
+            self.keypair.pubkey,
+            self.blinding,
+            mint_to_field(&self.mint_address),
+        ])?)
+    }
+
+    /// `Poseidon(commitment, index, sign(commitment, index))`
+    pub fn nullifier(&self) -> Result<Fr, ClientError> {
+        let commitment = self.commitment()?;
+        let index = Fr::from(self.index);
+        let signature = self.keypair.sign(commitment, index)?;
+        Ok(Poseidon::<Fr>::new_circom(3)?.hash(&[commitment, index, signature])?)
+    }
+}
+
+/// Random 31-byte blinding, always below the field size.
+pub fn random_blinding<R: RngCore + CryptoRng>(rng: &mut R) -> Fr {
+    let mut bytes = [0u8; 31];
+    rng.fill_bytes(&mut bytes);
+    Fr::from_be_bytes_mod_order(&bytes)
+}
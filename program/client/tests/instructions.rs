@@ -0,0 +1,380 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use yona::{CompressedProof, ExtDataMinified, SwapExtDataMinified, ValidityProof};
+use yona_client::instructions::{self, DepositAccounts, SwapAccounts, WithdrawAccounts};
+use yona_client::light::{
+    LightNullifierParams, LightTrees, ACCOUNT_COMPRESSION_AUTHORITY,
+    ACCOUNT_COMPRESSION_PROGRAM_ID, LIGHT_SYSTEM_PROGRAM_ID, NOOP_PROGRAM_ID,
+    REGISTERED_PROGRAM_PDA,
+};
+
+const POOL_ID: u16 = 3;
+const ACTIVE_TREE_INDEX: u32 = 2;
+const RETIRED_TREE_INDEX: u32 = 1;
+
+// sha256("global:<instruction name>")[..8]
+const DEPOSIT_DISCRIMINATOR: [u8; 8] = [242, 35, 198, 137, 82, 225, 242, 182];
+const WITHDRAW_DISCRIMINATOR: [u8; 8] = [183, 18, 70, 156, 148, 109, 161, 34];
+const SWAP_DISCRIMINATOR: [u8; 8] = [248, 198, 158, 145, 225, 117, 135, 200];
+
+// PDAs are derived from the seeds of the program's account constraints, not with `yona_client::pda`
+fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &yona::ID).0
+}
+
+fn global_config() -> Pubkey {
+    pda(&[b"global_config", &POOL_ID.to_le_bytes()])
+}
+
+fn merkle_tree(tree_index: u32) -> Pubkey {
+    pda(&[b"merkle_tree", &POOL_ID.to_le_bytes(), &tree_index.to_le_bytes()])
+}
+
+fn mint_config(mint: &Pubkey) -> Pubkey {
+    pda(&[b"mint_config", global_config().as_ref(), mint.as_ref()])
+}
+
+fn verifying_key() -> Pubkey {
+    pda(&[b"verifying_key", &[yona::CIRCUIT_TRANSACTION2]])
+}
+
+fn relayer_account(relayer: &Pubkey) -> Pubkey {
+    pda(&[b"relayer", global_config().as_ref(), relayer.as_ref()])
+}
+
+fn reserve(mint: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(&global_config(), mint, &anchor_spl::token::ID)
+}
+
+fn proof() -> CompressedProof {
+    CompressedProof {
+        proof_a: [1; 32],
+        proof_b: [2; 64],
+        proof_c: [3; 32],
+        root: [4; 32],
+        public_amount0: [5; 32],
+        public_amount1: [6; 32],
+        ext_data_hash: [7; 32],
+        input_nullifiers: [[8; 32], [9; 32]],
+        output_commitments: [[10; 32], [11; 32]],
+    }
+}
+
+/// Borsh encoding of `proof()`: the fields in declaration order, without length prefixes
+fn proof_bytes() -> Vec<u8> {
+    [
+        vec![1; 32],
+        vec![2; 64],
+        vec![3; 32],
+        vec![4; 32],
+        vec![5; 32],
+        vec![6; 32],
+        vec![7; 32],
+        vec![8; 32],
+        vec![9; 32],
+        vec![10; 32],
+        vec![11; 32],
+    ]
+    .concat()
+}
+
+fn light() -> LightNullifierParams {
+    LightNullifierParams {
+        light_proof: ValidityProof(None),
+        address_root_indices: [17, 18],
+        trees: LightTrees::localnet(),
+    }
+}
+
+/// Borsh encoding of the Light arguments of `light()`: no validity proof, the two address tree
+/// infos (address tree 0, address queue 1, root index) and the output state tree index 2
+fn light_bytes() -> Vec<u8> {
+    [
+        vec![0],
+        vec![0, 1],
+        17u16.to_le_bytes().to_vec(),
+        vec![0, 1],
+        18u16.to_le_bytes().to_vec(),
+        vec![2],
+    ]
+    .concat()
+}
+
+/// Light system accounts followed by the trees of `light()`, all passed after the anchor accounts
+fn light_accounts() -> Vec<AccountMeta> {
+    let trees = LightTrees::localnet();
+    vec![
+        AccountMeta::new_readonly(LIGHT_SYSTEM_PROGRAM_ID, false),
+        AccountMeta::new_readonly(pda(&[b"cpi_authority"]), false),
+        AccountMeta::new_readonly(REGISTERED_PROGRAM_PDA, false),
+        AccountMeta::new_readonly(NOOP_PROGRAM_ID, false),
+        AccountMeta::new_readonly(ACCOUNT_COMPRESSION_AUTHORITY, false),
+        AccountMeta::new_readonly(ACCOUNT_COMPRESSION_PROGRAM_ID, false),
+        AccountMeta::new_readonly(yona::ID, false),
+        AccountMeta::new_readonly(anchor_lang::system_program::ID, false),
+        AccountMeta::new(trees.address_tree, false),
+        AccountMeta::new(trees.address_queue, false),
+        AccountMeta::new(trees.output_state_tree, false),
+        AccountMeta::new(trees.nullifier_queue, false),
+    ]
+}
+
+fn assert_instruction(instruction: &Instruction, accounts: Vec<AccountMeta>, data: Vec<u8>) {
+    assert_eq!(instruction.program_id, yona::ID);
+    assert_eq!(instruction.accounts.len(), accounts.len());
+    for (index, (actual, expected)) in instruction.accounts.iter().zip(&accounts).enumerate() {
+        assert_eq!(actual, expected, "account {index}");
+    }
+    assert_eq!(instruction.data, data);
+}
+
+#[test]
+fn deposit_matches_program_accounts() {
+    let (user, mint, fee_recipient_account) =
+        (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+
+    let instruction = instructions::deposit(
+        DepositAccounts {
+            pool_id: POOL_ID,
+            active_tree_index: ACTIVE_TREE_INDEX,
+            retired_tree_index: Some(RETIRED_TREE_INDEX),
+            user,
+            input_mint: mint,
+            fee_recipient_account,
+            token_program: anchor_spl::token::ID,
+        },
+        proof(),
+        ExtDataMinified {
+            ext_amount: 1_000,
+            fee: 0,
+        },
+        b"note".to_vec(),
+        &light(),
+    );
+
+    let accounts = [
+        vec![
+            AccountMeta::new(merkle_tree(ACTIVE_TREE_INDEX), false),
+            AccountMeta::new_readonly(merkle_tree(RETIRED_TREE_INDEX), false),
+            AccountMeta::new_readonly(global_config(), false),
+            AccountMeta::new_readonly(verifying_key(), false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(mint_config(&mint), false),
+            AccountMeta::new(reserve(&mint), false),
+            AccountMeta::new_readonly(fee_recipient_account, false),
+            AccountMeta::new(
+                get_associated_token_address_with_program_id(&user, &mint, &anchor_spl::token::ID),
+                false,
+            ),
+            AccountMeta::new(user, true),
+            AccountMeta::new_readonly(anchor_lang::system_program::ID, false),
+            AccountMeta::new_readonly(anchor_spl::token::ID, false),
+        ],
+        light_accounts(),
+    ]
+    .concat();
+    let data = [
+        DEPOSIT_DISCRIMINATOR.to_vec(),
+        proof_bytes(),
+        1_000i64.to_le_bytes().to_vec(),
+        0u64.to_le_bytes().to_vec(),
+        4u32.to_le_bytes().to_vec(),
+        b"note".to_vec(),
+        light_bytes(),
+    ]
+    .concat();
+    assert_instruction(&instruction, accounts, data);
+}
+
+#[test]
+fn withdraw_matches_program_accounts() {
+    let (relayer, mint, recipient, fee_recipient_account) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let recipient_token_account =
+        get_associated_token_address_with_program_id(&recipient, &mint, &anchor_spl::token::ID);
+
+    let instruction = instructions::withdraw(
+        WithdrawAccounts {
+            pool_id: POOL_ID,
+            active_tree_index: ACTIVE_TREE_INDEX,
+            retired_tree_index: Some(RETIRED_TREE_INDEX),
+            relayer,
+            registered_relayer: true,
+            input_mint: mint,
+            recipient,
+            recipient_token_account,
+            fee_recipient_account,
+            token_program: anchor_spl::token::ID,
+        },
+        proof(),
+        ExtDataMinified {
+            ext_amount: -1_000,
+            fee: 3,
+        },
+        Vec::new(),
+        &light(),
+    );
+
+    let accounts = [
+        vec![
+            AccountMeta::new(merkle_tree(ACTIVE_TREE_INDEX), false),
+            AccountMeta::new_readonly(merkle_tree(RETIRED_TREE_INDEX), false),
+            AccountMeta::new_readonly(global_config(), false),
+            AccountMeta::new_readonly(verifying_key(), false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(mint_config(&mint), false),
+            AccountMeta::new(reserve(&mint), false),
+            AccountMeta::new(recipient, false),
+            AccountMeta::new(recipient_token_account, false),
+            AccountMeta::new(fee_recipient_account, false),
+            AccountMeta::new(relayer, true),
+            AccountMeta::new_readonly(relayer_account(&relayer), false),
+            AccountMeta::new_readonly(anchor_lang::system_program::ID, false),
+            AccountMeta::new_readonly(anchor_spl::token::ID, false),
+        ],
+        light_accounts(),
+    ]
+    .concat();
+    let data = [
+        WITHDRAW_DISCRIMINATOR.to_vec(),
+        proof_bytes(),
+        (-1_000i64).to_le_bytes().to_vec(),
+        3u64.to_le_bytes().to_vec(),
+        0u32.to_le_bytes().to_vec(),
+        light_bytes(),
+    ]
+    .concat();
+    assert_instruction(&instruction, accounts, data);
+}
+
+#[test]
+fn self_relayed_withdraw_leaves_out_the_relayer_account() {
+    let (relayer, mint, recipient) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let recipient_token_account =
+        get_associated_token_address_with_program_id(&recipient, &mint, &anchor_spl::token::ID);
+
+    let instruction = instructions::withdraw(
+        WithdrawAccounts {
+            pool_id: POOL_ID,
+            active_tree_index: ACTIVE_TREE_INDEX,
+            retired_tree_index: None,
+            relayer,
+            registered_relayer: false,
+            input_mint: mint,
+            recipient,
+            recipient_token_account,
+            fee_recipient_account: recipient_token_account,
+            token_program: anchor_spl::token::ID,
+        },
+        proof(),
+        ExtDataMinified {
+            ext_amount: -1_000,
+            fee: 0,
+        },
+        Vec::new(),
+        &light(),
+    );
+
+    // Anchor passes the program id for an absent optional account
+    assert_eq!(
+        instruction.accounts[11],
+        AccountMeta::new_readonly(yona::ID, false)
+    );
+}
+
+#[test]
+fn swap_appends_route_accounts_after_light_accounts() {
+    let (relayer, input_mint, output_mint, fee_recipient_account) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let swap_authority = pda(&[b"swap_authority", global_config().as_ref()]);
+    let jupiter_accounts = vec![
+        AccountMeta::new_readonly(anchor_spl::token::ID, false),
+        AccountMeta::new_readonly(swap_authority, false),
+        AccountMeta::new(reserve(&input_mint), false),
+        AccountMeta::new(reserve(&output_mint), false),
+    ];
+
+    let instruction = instructions::swap(
+        SwapAccounts {
+            pool_id: POOL_ID,
+            active_tree_index: ACTIVE_TREE_INDEX,
+            retired_tree_index: Some(RETIRED_TREE_INDEX),
+            relayer,
+            input_mint,
+            output_mint,
+            fee_recipient_account,
+            input_token_program: anchor_spl::token::ID,
+            output_token_program: anchor_spl::token::ID,
+            jupiter_accounts: jupiter_accounts.clone(),
+        },
+        proof(),
+        SwapExtDataMinified {
+            ext_amount: -1_000,
+            ext_min_amount_out: 900,
+            fee: 0,
+        },
+        Vec::new(),
+        vec![0xAA; 16],
+        &light(),
+    );
+
+    let accounts = [
+        vec![
+            AccountMeta::new(merkle_tree(ACTIVE_TREE_INDEX), false),
+            AccountMeta::new_readonly(merkle_tree(RETIRED_TREE_INDEX), false),
+            AccountMeta::new_readonly(global_config(), false),
+            AccountMeta::new_readonly(verifying_key(), false),
+            AccountMeta::new_readonly(input_mint, false),
+            AccountMeta::new_readonly(output_mint, false),
+            AccountMeta::new_readonly(mint_config(&input_mint), false),
+            AccountMeta::new_readonly(mint_config(&output_mint), false),
+            AccountMeta::new(reserve(&input_mint), false),
+            AccountMeta::new(reserve(&output_mint), false),
+            AccountMeta::new(fee_recipient_account, false),
+            AccountMeta::new_readonly(yona::jupiter_aggregator::ID, false),
+            AccountMeta::new_readonly(
+                pda(&[
+                    b"aggregator_config",
+                    global_config().as_ref(),
+                    yona::jupiter_aggregator::ID.as_ref(),
+                ]),
+                false,
+            ),
+            AccountMeta::new_readonly(swap_authority, false),
+            AccountMeta::new(relayer, true),
+            AccountMeta::new_readonly(relayer_account(&relayer), false),
+            AccountMeta::new_readonly(anchor_lang::system_program::ID, false),
+            AccountMeta::new_readonly(anchor_spl::token::ID, false),
+            AccountMeta::new_readonly(anchor_spl::token::ID, false),
+        ],
+        light_accounts(),
+        jupiter_accounts,
+    ]
+    .concat();
+    let data = [
+        SWAP_DISCRIMINATOR.to_vec(),
+        proof_bytes(),
+        (-1_000i64).to_le_bytes().to_vec(),
+        900i64.to_le_bytes().to_vec(),
+        0u64.to_le_bytes().to_vec(),
+        0u32.to_le_bytes().to_vec(),
+        16u32.to_le_bytes().to_vec(),
+        vec![0xAA; 16],
+        light_bytes(),
+    ]
+    .concat();
+    assert_instruction(&instruction, accounts, data);
+}
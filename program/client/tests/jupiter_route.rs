@@ -0,0 +1,172 @@
+use anchor_lang::prelude::*;
+use anchor_lang::InstructionData;
+use yona::jupiter::{decode_route, validate_route, ExpectedRoute, RouteKind};
+use yona::jupiter_aggregator::client::args::{Route, SharedAccountsRoute};
+use yona::ErrorCode;
+
+const IN_AMOUNT: u64 = 1_000_000;
+
+fn expected() -> ExpectedRoute {
+    ExpectedRoute {
+        program_id: yona::jupiter_aggregator::ID,
+        authority: Pubkey::new_unique(),
+        source_token_account: Pubkey::new_unique(),
+        destination_token_account: Pubkey::new_unique(),
+        source_mint: Pubkey::new_unique(),
+        destination_mint: Pubkey::new_unique(),
+        in_amount: IN_AMOUNT,
+    }
+}
+
+fn route_data(in_amount: u64, platform_fee_bps: u8) -> Vec<u8> {
+    Route {
+        route_plan: vec![],
+        in_amount,
+        quoted_out_amount: 2_000_000,
+        slippage_bps: 50,
+        platform_fee_bps,
+    }
+    .data()
+}
+
+fn shared_route_data() -> Vec<u8> {
+    SharedAccountsRoute {
+        id: 1,
+        route_plan: vec![],
+        in_amount: IN_AMOUNT,
+        quoted_out_amount: 2_000_000,
+        slippage_bps: 50,
+        platform_fee_bps: 0,
+    }
+    .data()
+}
+
+/// `route` accounts up to the route plan, without destination token and platform fee accounts
+fn route_accounts(expected: &ExpectedRoute) -> Vec<Pubkey> {
+    vec![
+        anchor_spl::token::ID,
+        expected.authority,
+        expected.source_token_account,
+        expected.destination_token_account,
+        expected.program_id,
+        expected.destination_mint,
+        expected.program_id,
+        Pubkey::new_unique(),
+        expected.program_id,
+    ]
+}
+
+fn shared_route_accounts(expected: &ExpectedRoute) -> Vec<Pubkey> {
+    vec![
+        anchor_spl::token::ID,
+        Pubkey::new_unique(),
+        expected.authority,
+        expected.source_token_account,
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        expected.destination_token_account,
+        expected.source_mint,
+        expected.destination_mint,
+        expected.program_id,
+        expected.program_id,
+        Pubkey::new_unique(),
+        expected.program_id,
+    ]
+}
+
+fn validate_error(data: &[u8], accounts: &[Pubkey], expected: &ExpectedRoute) -> Error {
+    validate_route(data, accounts, expected).err().unwrap()
+}
+
+#[test]
+fn decodes_route_and_shared_accounts_route() {
+    let route = decode_route(&route_data(IN_AMOUNT, 0)).unwrap();
+    assert_eq!(route.kind, RouteKind::Route);
+    assert_eq!(route.in_amount, IN_AMOUNT);
+    assert_eq!(route.quoted_out_amount, 2_000_000);
+
+    let shared = shared_route_data();
+    let route = decode_route(&shared).unwrap();
+    assert_eq!(route.kind, RouteKind::SharedAccountsRoute);
+    assert_eq!(route.in_amount, IN_AMOUNT);
+}
+
+#[test]
+fn rejects_unknown_discriminators() {
+    let mut data = route_data(IN_AMOUNT, 0);
+    data[0] ^= 0xff;
+    assert_eq!(
+        decode_route(&data).err().unwrap(),
+        ErrorCode::UnsupportedJupiterRoute.into()
+    );
+    assert_eq!(
+        decode_route(&data[..4]).err().unwrap(),
+        ErrorCode::UnsupportedJupiterRoute.into()
+    );
+}
+
+#[test]
+fn accepts_a_route_between_the_reserves() {
+    let expected = expected();
+    validate_route(&route_data(IN_AMOUNT, 0), &route_accounts(&expected), &expected).unwrap();
+
+    // The output may be sent to the destination reserve through the optional account too
+    let mut accounts = route_accounts(&expected);
+    accounts[4] = expected.destination_token_account;
+    validate_route(&route_data(IN_AMOUNT, 0), &accounts, &expected).unwrap();
+
+    let shared = shared_route_data();
+    validate_route(&shared, &shared_route_accounts(&expected), &expected).unwrap();
+}
+
+#[test]
+fn rejects_the_authority_at_another_index() {
+    let expected = expected();
+    let mut accounts = route_accounts(&expected);
+    accounts.swap(0, 1);
+
+    assert_eq!(
+        validate_error(&route_data(IN_AMOUNT, 0), &accounts, &expected),
+        ErrorCode::InvalidJupiterRouteAccount.into()
+    );
+}
+
+#[test]
+fn rejects_other_source_or_destination_accounts() {
+    let expected = expected();
+    let data = route_data(IN_AMOUNT, 0);
+
+    for index in [2, 3, 4, 5] {
+        let mut accounts = route_accounts(&expected);
+        accounts[index] = Pubkey::new_unique();
+        assert_eq!(
+            validate_error(&data, &accounts, &expected),
+            ErrorCode::InvalidJupiterRouteAccount.into(),
+            "account {index}"
+        );
+    }
+
+    // Missing accounts
+    assert_eq!(
+        validate_error(&data, &route_accounts(&expected)[..3], &expected),
+        ErrorCode::InvalidJupiterRouteAccount.into()
+    );
+}
+
+#[test]
+fn rejects_platform_fees() {
+    let expected = expected();
+    assert_eq!(
+        validate_error(&route_data(IN_AMOUNT, 1), &route_accounts(&expected), &expected),
+        ErrorCode::UnsupportedJupiterRoute.into()
+    );
+}
+
+#[test]
+fn rejects_another_input_amount() {
+    let expected = expected();
+    assert_eq!(
+        validate_error(&route_data(IN_AMOUNT - 1, 0), &route_accounts(&expected), &expected),
+        ErrorCode::JupiterRouteAmountMismatch.into()
+    );
+}
@@ -0,0 +1,116 @@
+use light_hasher::{Hasher, Poseidon};
+use yona::merkle_tree::MerkleTree;
+use yona::state::{DEFAULT_ROOT_HISTORY_SIZE, MERKLE_TREE_HEIGHT};
+use yona::{CommitmentData, MerkleTreeAccount};
+use yona_client::{ClientError, MerkleTreeMirror};
+
+fn leaf(i: u64) -> [u8; 32] {
+    let mut leaf = [0u8; 32];
+    leaf[24..].copy_from_slice(&(i + 1).to_be_bytes());
+    leaf
+}
+
+fn new_tree_account() -> MerkleTreeAccount {
+    new_tree_account_with_height(MERKLE_TREE_HEIGHT)
+}
+
+fn new_tree_account_with_height(height: u8) -> MerkleTreeAccount {
+    let mut tree_account: MerkleTreeAccount = bytemuck::Zeroable::zeroed();
+    tree_account.height = height;
+    tree_account.root_history_size = DEFAULT_ROOT_HISTORY_SIZE;
+    MerkleTree::initialize::<Poseidon>(&mut tree_account).unwrap();
+    tree_account
+}
+
+#[test]
+fn mirror_matches_on_chain_tree() {
+    let mut tree_account = new_tree_account();
+    let mut mirror = MerkleTreeMirror::default();
+    assert_eq!(mirror.root(), tree_account.root);
+
+    for i in (0..10).step_by(2) {
+        MerkleTree::append::<Poseidon>(leaf(i), &mut tree_account).unwrap();
+        MerkleTree::append::<Poseidon>(leaf(i + 1), &mut tree_account).unwrap();
+        let event = CommitmentData {
+            pool_id: 0,
+            tree_index: 0,
+            index: i,
+            commitment0: leaf(i),
+            commitment1: leaf(i + 1),
+            encrypted_output: vec![],
+        };
+        mirror.ingest(&event).unwrap();
+        // Replaying the same event is a no-op
+        mirror.ingest(&event).unwrap();
+
+        assert_eq!(mirror.next_index(), tree_account.next_index);
+        assert_eq!(mirror.root(), tree_account.root);
+        mirror.verify_root(&tree_account).unwrap();
+    }
+
+    for index in 0..mirror.next_index() {
+        let proof = mirror.proof(index).unwrap();
+        let mut node = proof.leaf;
+        for (level, sibling) in proof.path_elements.iter().enumerate() {
+            node = if (index >> level) & 1 == 0 {
+                Poseidon::hashv(&[&node, sibling]).unwrap()
+            } else {
+                Poseidon::hashv(&[sibling, &node]).unwrap()
+            };
+        }
+        assert_eq!(node, tree_account.root);
+    }
+}
+
+#[test]
+fn shorter_tree_roots_match_the_circuit_depth_mirror() {
+    let mut tree_account = new_tree_account_with_height(2);
+    let mut mirror = MerkleTreeMirror::default();
+    assert_eq!(mirror.root(), tree_account.root);
+
+    for i in 0..4 {
+        MerkleTree::append::<Poseidon>(leaf(i), &mut tree_account).unwrap();
+        mirror.append(leaf(i)).unwrap();
+        assert_eq!(mirror.root(), tree_account.root);
+        mirror.verify_root(&tree_account).unwrap();
+    }
+
+    // The height only caps the number of leaves
+    assert!(MerkleTree::append::<Poseidon>(leaf(4), &mut tree_account).is_err());
+    assert!(matches!(
+        MerkleTreeMirror::new(2).verify_root(&tree_account),
+        Err(ClientError::RootMismatch)
+    ));
+}
+
+#[test]
+fn mirror_rejects_gaps_and_conflicts() {
+    let mut mirror = MerkleTreeMirror::default();
+    let gap = CommitmentData {
+        pool_id: 0,
+        tree_index: 0,
+        index: 2,
+        commitment0: leaf(2),
+        commitment1: leaf(3),
+        encrypted_output: vec![],
+    };
+    assert!(matches!(
+        mirror.ingest(&gap),
+        Err(ClientError::MissingCommitments { expected: 0, got: 2 })
+    ));
+
+    mirror.append(leaf(0)).unwrap();
+    mirror.append(leaf(1)).unwrap();
+    let conflict = CommitmentData {
+        pool_id: 0,
+        tree_index: 0,
+        index: 0,
+        commitment0: leaf(0),
+        commitment1: leaf(7),
+        encrypted_output: vec![],
+    };
+    assert!(matches!(
+        mirror.ingest(&conflict),
+        Err(ClientError::ConflictingCommitment(1))
+    ));
+}
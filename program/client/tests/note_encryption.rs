@@ -0,0 +1,43 @@
+use anchor_lang::prelude::Pubkey;
+use ark_bn254::Fr;
+use yona_client::note::{decrypt_notes, encrypt_notes, NOTE_ENVELOPE_LEN};
+use yona_client::{ClientError, NotePlaintext, ShieldedKeypair, ViewingKey};
+
+#[test]
+fn notes_are_only_readable_by_their_recipient() {
+    let mut rng = rand::rngs::OsRng;
+    let alice = ViewingKey::from_keypair(&ShieldedKeypair::generate(&mut rng).unwrap()).unwrap();
+    let bob = ViewingKey::from_keypair(&ShieldedKeypair::generate(&mut rng).unwrap()).unwrap();
+
+    let note0 = NotePlaintext {
+        amount: 1_000_000,
+        blinding: Fr::from(123456789u64),
+        mint_address: Pubkey::new_unique(),
+    };
+    let note1 = NotePlaintext {
+        amount: 0,
+        blinding: Fr::from(987654321u64),
+        mint_address: Pubkey::new_unique(),
+    };
+
+    let envelope = encrypt_notes(
+        [(&note0, &alice.public_key()), (&note1, &bob.public_key())],
+        &mut rng,
+    )
+    .unwrap();
+    assert_eq!(envelope.len(), NOTE_ENVELOPE_LEN);
+
+    assert_eq!(decrypt_notes(&envelope, &alice).unwrap(), [Some(note0), None]);
+    assert_eq!(decrypt_notes(&envelope, &bob).unwrap(), [None, Some(note1)]);
+
+    let mut tampered = envelope.clone();
+    tampered[NOTE_ENVELOPE_LEN - 1] ^= 1;
+    assert_eq!(decrypt_notes(&tampered, &bob).unwrap()[1], None);
+
+    let mut unknown_version = envelope;
+    unknown_version[0] = 2;
+    assert!(matches!(
+        decrypt_notes(&unknown_version, &alice),
+        Err(ClientError::UnsupportedNoteVersion(2))
+    ));
+}
@@ -0,0 +1,765 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::spl_token::native_mint;
+use anchor_lang::solana_program::instruction::AccountMeta;
+use anchor_lang::InstructionData;
+use ark_bn254::Fr;
+use ark_ff::PrimeField;
+use light_hasher::Poseidon;
+use yona::jupiter_aggregator::client::args::Route;
+use yona::merkle_tree::MerkleTree;
+use yona::state::{DEFAULT_ROOT_HISTORY_SIZE, MERKLE_TREE_HEIGHT};
+use yona::utils::{
+    calculate_complete_ext_data_hash, calculate_route_hash, calculate_swap_ext_data_hash,
+};
+use yona::{
+    AggregatorConfig, CompressedProof, ErrorCode, ExtDataMinified, GlobalConfig, MerkleTreeAccount,
+    MintConfig, RelayerAccount, SwapExtDataMinified, VerifyingKeyAccount,
+};
+use yona_client::field::fr_to_be_bytes;
+use yona_client::pda::{
+    find_global_config_address, find_swap_authority_address, reserve_token_account,
+};
+use yona_client::preflight::{
+    preflight_swap, preflight_withdraw, PreflightCheck, PreflightReport, PreflightState,
+};
+use yona_client::verifying_key::{verifying_key_data, TRANSACTION2_VERIFYING_KEY};
+use yona_client::SwapAccounts;
+
+const EXT_AMOUNT: i64 = -1_000_000;
+const FEE: u64 = 3_000; // 0.3%
+const SWAP_MIN_AMOUNT_OUT: i64 = 990_000;
+
+fn tree_account() -> MerkleTreeAccount {
+    let mut tree_account: MerkleTreeAccount = bytemuck::Zeroable::zeroed();
+    tree_account.height = MERKLE_TREE_HEIGHT;
+    tree_account.root_history_size = DEFAULT_ROOT_HISTORY_SIZE;
+    MerkleTree::initialize::<Poseidon>(&mut tree_account).unwrap();
+    tree_account
+}
+
+fn global_config() -> GlobalConfig {
+    GlobalConfig {
+        authority: Pubkey::new_unique(),
+        pool_id: 0,
+        active_tree_index: 0,
+        deposit_fee_rate: 0,
+        withdrawal_fee_rate: 30,
+        fee_error_margin: 500,
+        min_relayer_bond: 0,
+        bump: 255,
+    }
+}
+
+fn mint_config(mint: Pubkey) -> MintConfig {
+    MintConfig {
+        global_config: Pubkey::new_unique(),
+        mint,
+        enabled: true,
+        min_deposit_amount: 0,
+        max_deposit_amount: u64::MAX,
+        deposit_fee_rate: None,
+        withdrawal_fee_rate: None,
+        bump: 255,
+    }
+}
+
+fn relayer_account(fee_rate: u16) -> RelayerAccount {
+    RelayerAccount {
+        global_config: Pubkey::new_unique(),
+        authority: Pubkey::new_unique(),
+        fee_recipient: Pubkey::new_unique(),
+        fee_rate,
+        bond: 0,
+        bump: 255,
+        unbonded_at: 0,
+    }
+}
+
+fn verifying_key_account() -> VerifyingKeyAccount {
+    let mut account: VerifyingKeyAccount = bytemuck::Zeroable::zeroed();
+    account.current.version = 1;
+    account.current.nr_pubinputs = TRANSACTION2_VERIFYING_KEY.nr_pubinputs as u32;
+    account
+        .current
+        .write(0, &verifying_key_data(&TRANSACTION2_VERIFYING_KEY))
+        .unwrap();
+    account
+}
+
+/// Proof whose public inputs match the withdrawal, with invalid curve points.
+fn proof(
+    root: [u8; 32],
+    mint: Pubkey,
+    recipient: Pubkey,
+    fee_recipient: Pubkey,
+) -> CompressedProof {
+    let ext_data_hash = calculate_complete_ext_data_hash(
+        recipient,
+        EXT_AMOUNT,
+        &[],
+        FEE,
+        fee_recipient,
+        mint,
+        mint,
+    )
+    .unwrap();
+    let public_amount = -Fr::from(EXT_AMOUNT.unsigned_abs() + FEE);
+
+    CompressedProof {
+        proof_a: [0xff; 32],
+        proof_b: [0xff; 64],
+        proof_c: [0xff; 32],
+        root,
+        public_amount0: fr_to_be_bytes(&public_amount),
+        public_amount1: [0; 32],
+        ext_data_hash: fr_to_be_bytes(&Fr::from_le_bytes_mod_order(&ext_data_hash)),
+        input_nullifiers: [[1; 32], [2; 32]],
+        output_commitments: [[3; 32], [4; 32]],
+    }
+}
+
+/// Swap proof bound to the route `(route_data, route_accounts)`, with invalid curve points.
+fn swap_proof(
+    root: [u8; 32],
+    input_mint: Pubkey,
+    output_mint: Pubkey,
+    fee_recipient: Pubkey,
+    route_data: &[u8],
+    route_accounts: &[AccountMeta],
+) -> CompressedProof {
+    let route_accounts: Vec<(Pubkey, bool)> = route_accounts
+        .iter()
+        .map(|account| (account.pubkey, account.is_writable))
+        .collect();
+    let ext_data_hash = calculate_swap_ext_data_hash(
+        EXT_AMOUNT,
+        SWAP_MIN_AMOUNT_OUT,
+        &[],
+        0,
+        fee_recipient,
+        input_mint,
+        output_mint,
+        calculate_route_hash(route_data, &route_accounts).unwrap(),
+    )
+    .unwrap();
+
+    CompressedProof {
+        proof_a: [0xff; 32],
+        proof_b: [0xff; 64],
+        proof_c: [0xff; 32],
+        root,
+        public_amount0: fr_to_be_bytes(&-Fr::from(EXT_AMOUNT.unsigned_abs())),
+        public_amount1: fr_to_be_bytes(&Fr::from(SWAP_MIN_AMOUNT_OUT as u64)),
+        ext_data_hash: fr_to_be_bytes(&Fr::from_le_bytes_mod_order(&ext_data_hash)),
+        input_nullifiers: [[1; 32], [2; 32]],
+        output_commitments: [[3; 32], [4; 32]],
+    }
+}
+
+fn error_code(report: &PreflightReport, check: PreflightCheck) -> Option<u32> {
+    report
+        .failure(check)
+        .and_then(|failure| failure.error_code())
+}
+
+#[test]
+fn withdraw_reports_only_the_malformed_proof() {
+    let (tree_account, global_config) = (tree_account(), global_config());
+    let verifying_key = verifying_key_account();
+    let (mint, recipient, fee_recipient) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let state = PreflightState {
+        tree_account: &tree_account,
+        retired_tree_account: None,
+        verifying_key: &verifying_key,
+        global_config: &global_config,
+        now: 0,
+    };
+
+    let relayer = relayer_account(30);
+    let report = preflight_withdraw(
+        &state,
+        &mint_config(mint),
+        Some(&relayer),
+        mint,
+        Pubkey::new_unique(),
+        recipient,
+        recipient,
+        fee_recipient,
+        relayer.fee_recipient,
+        &proof(tree_account.root, mint, recipient, fee_recipient),
+        &ExtDataMinified {
+            ext_amount: EXT_AMOUNT,
+            fee: FEE,
+        },
+        &[],
+    );
+
+    assert_eq!(report.failures.len(), 1, "{:?}", report.failures);
+    assert_eq!(
+        error_code(&report, PreflightCheck::Proof),
+        Some(u32::from(ErrorCode::InvalidProofPointA))
+    );
+}
+
+#[test]
+fn withdraw_reports_every_failed_check() {
+    let (tree_account, global_config) = (tree_account(), global_config());
+    let verifying_key: VerifyingKeyAccount = bytemuck::Zeroable::zeroed();
+    let (mint, recipient, fee_recipient) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let state = PreflightState {
+        tree_account: &tree_account,
+        retired_tree_account: None,
+        verifying_key: &verifying_key,
+        global_config: &global_config,
+        now: 0,
+    };
+
+    // Unknown root, proof bound to another recipient, fee below the withdrawal rate, token
+    // account of someone else
+    let relayer = relayer_account(30);
+    let report = preflight_withdraw(
+        &state,
+        &mint_config(mint),
+        Some(&relayer),
+        mint,
+        Pubkey::new_unique(),
+        recipient,
+        Pubkey::new_unique(),
+        fee_recipient,
+        relayer.fee_recipient,
+        &proof([9; 32], mint, Pubkey::new_unique(), fee_recipient),
+        &ExtDataMinified {
+            ext_amount: EXT_AMOUNT,
+            fee: 0,
+        },
+        &[],
+    );
+
+    assert!(!report.is_ok());
+    assert_eq!(
+        error_code(&report, PreflightCheck::KnownRoot),
+        Some(u32::from(ErrorCode::UnknownRoot))
+    );
+    assert_eq!(
+        error_code(&report, PreflightCheck::ExtDataHash),
+        Some(u32::from(ErrorCode::ExtDataHashMismatch))
+    );
+    assert_eq!(error_code(&report, PreflightCheck::ExtAmount), None);
+    assert_eq!(
+        error_code(&report, PreflightCheck::PublicAmount),
+        Some(u32::from(ErrorCode::InvalidPublicAmountData))
+    );
+    assert!(report.failure(PreflightCheck::Fee).is_some());
+    assert_eq!(
+        error_code(&report, PreflightCheck::Proof),
+        Some(u32::from(ErrorCode::VerifyingKeyNotSet))
+    );
+    assert_eq!(
+        error_code(&report, PreflightCheck::RecipientTokenAccount),
+        Some(u32::from(ErrorCode::Unauthorized))
+    );
+}
+
+#[test]
+fn withdraw_reports_a_fee_above_the_relayer_rate() {
+    let (tree_account, global_config) = (tree_account(), global_config());
+    let verifying_key = verifying_key_account();
+    let (mint, recipient, fee_recipient) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let state = PreflightState {
+        tree_account: &tree_account,
+        retired_tree_account: None,
+        verifying_key: &verifying_key,
+        global_config: &global_config,
+        now: 0,
+    };
+
+    // The fee meets the pool's 0.3% withdrawal rate but the relayer advertises 0.2%
+    let relayer = relayer_account(20);
+    let report = preflight_withdraw(
+        &state,
+        &mint_config(mint),
+        Some(&relayer),
+        mint,
+        Pubkey::new_unique(),
+        recipient,
+        recipient,
+        fee_recipient,
+        relayer.fee_recipient,
+        &proof(tree_account.root, mint, recipient, fee_recipient),
+        &ExtDataMinified {
+            ext_amount: EXT_AMOUNT,
+            fee: FEE,
+        },
+        &[],
+    );
+
+    assert!(report.failure(PreflightCheck::Fee).is_none());
+    assert_eq!(
+        error_code(&report, PreflightCheck::RelayerFee),
+        Some(u32::from(ErrorCode::RelayerFeeTooHigh))
+    );
+}
+
+#[test]
+fn withdraw_reports_the_relayer_account_constraints() {
+    let tree_account = tree_account();
+    let global_config = GlobalConfig {
+        min_relayer_bond: 1_000,
+        ..global_config()
+    };
+    let verifying_key = verifying_key_account();
+    let (mint, recipient, fee_recipient) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let state = PreflightState {
+        tree_account: &tree_account,
+        retired_tree_account: None,
+        verifying_key: &verifying_key,
+        global_config: &global_config,
+        now: 0,
+    };
+    let preflight = |relayer: &RelayerAccount, fee_recipient_owner: Pubkey| {
+        preflight_withdraw(
+            &state,
+            &mint_config(mint),
+            Some(relayer),
+            mint,
+            Pubkey::new_unique(),
+            recipient,
+            recipient,
+            fee_recipient,
+            fee_recipient_owner,
+            &proof(tree_account.root, mint, recipient, fee_recipient),
+            &ExtDataMinified {
+                ext_amount: EXT_AMOUNT,
+                fee: FEE,
+            },
+            &[],
+        )
+    };
+    let bonded = RelayerAccount {
+        bond: 1_000,
+        ..relayer_account(30)
+    };
+
+    let report = preflight(&bonded, bonded.fee_recipient);
+    for check in [PreflightCheck::Relayer, PreflightCheck::FeeRecipient] {
+        assert!(report.failure(check).is_none(), "{:?}", report.failures);
+    }
+
+    // Bond below the pool minimum
+    let unbonded = RelayerAccount {
+        bond: 999,
+        ..bonded.clone()
+    };
+    assert_eq!(
+        error_code(
+            &preflight(&unbonded, unbonded.fee_recipient),
+            PreflightCheck::Relayer
+        ),
+        Some(u32::from(ErrorCode::RelayerBondTooLow))
+    );
+
+    // Deregistration requested
+    let deregistering = RelayerAccount {
+        unbonded_at: 1,
+        ..bonded.clone()
+    };
+    assert_eq!(
+        error_code(
+            &preflight(&deregistering, deregistering.fee_recipient),
+            PreflightCheck::Relayer
+        ),
+        Some(u32::from(ErrorCode::RelayerDeregistering))
+    );
+
+    // Fee token account of another owner than the registered fee recipient
+    assert_eq!(
+        error_code(
+            &preflight(&bonded, Pubkey::new_unique()),
+            PreflightCheck::FeeRecipient
+        ),
+        Some(u32::from(ErrorCode::InvalidFeeRecipient))
+    );
+}
+
+#[test]
+fn withdraw_of_wrapped_sol_goes_through_a_relayer_token_account() {
+    let (tree_account, global_config) = (tree_account(), global_config());
+    let verifying_key = verifying_key_account();
+    let (mint, relayer, recipient, fee_recipient) = (
+        native_mint::ID,
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let state = PreflightState {
+        tree_account: &tree_account,
+        retired_tree_account: None,
+        verifying_key: &verifying_key,
+        global_config: &global_config,
+        now: 0,
+    };
+    let preflight = |recipient_token_account_owner| {
+        preflight_withdraw(
+            &state,
+            &mint_config(mint),
+            Some(&relayer_account(30)),
+            mint,
+            relayer,
+            recipient,
+            recipient_token_account_owner,
+            fee_recipient,
+            Pubkey::new_unique(),
+            &proof(tree_account.root, mint, recipient, fee_recipient),
+            &ExtDataMinified {
+                ext_amount: EXT_AMOUNT,
+                fee: FEE,
+            },
+            &[],
+        )
+    };
+
+    assert!(preflight(relayer)
+        .failure(PreflightCheck::RecipientTokenAccount)
+        .is_none());
+    assert_eq!(
+        error_code(&preflight(recipient), PreflightCheck::RecipientTokenAccount),
+        Some(u32::from(ErrorCode::Unauthorized))
+    );
+}
+
+#[test]
+fn self_relayed_withdraw_reports_a_fee() {
+    let (tree_account, global_config) = (tree_account(), global_config());
+    let verifying_key = verifying_key_account();
+    let (mint, recipient, fee_recipient) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let state = PreflightState {
+        tree_account: &tree_account,
+        retired_tree_account: None,
+        verifying_key: &verifying_key,
+        global_config: &global_config,
+        now: 0,
+    };
+
+    // Without a registered relayer there is nobody to pay the pool's withdrawal rate to
+    let report = preflight_withdraw(
+        &state,
+        &mint_config(mint),
+        None,
+        mint,
+        Pubkey::new_unique(),
+        recipient,
+        recipient,
+        fee_recipient,
+        Pubkey::new_unique(),
+        &proof(tree_account.root, mint, recipient, fee_recipient),
+        &ExtDataMinified {
+            ext_amount: EXT_AMOUNT,
+            fee: FEE,
+        },
+        &[],
+    );
+
+    assert_eq!(
+        error_code(&report, PreflightCheck::Fee),
+        Some(u32::from(ErrorCode::InvalidFeeRecipient))
+    );
+    assert!(report.failure(PreflightCheck::RelayerFee).is_none());
+}
+
+/// Swap of pool 0 between two classic token mints, relayed by a new relayer
+fn swap_accounts(
+    input_mint: Pubkey,
+    output_mint: Pubkey,
+    fee_recipient_account: Pubkey,
+    jupiter_accounts: Vec<AccountMeta>,
+) -> SwapAccounts {
+    SwapAccounts {
+        pool_id: 0,
+        active_tree_index: 0,
+        retired_tree_index: None,
+        relayer: Pubkey::new_unique(),
+        input_mint,
+        output_mint,
+        fee_recipient_account,
+        input_token_program: anchor_spl::token::ID,
+        output_token_program: anchor_spl::token::ID,
+        jupiter_accounts,
+    }
+}
+
+fn aggregator_config(enabled: bool) -> AggregatorConfig {
+    AggregatorConfig {
+        global_config: find_global_config_address(&yona::ID, 0).0,
+        program_id: yona::jupiter_aggregator::ID,
+        enabled,
+        bump: 255,
+    }
+}
+
+/// Jupiter `route` of the swap input from the input reserve into the output reserve of pool 0
+fn jupiter_route(
+    input_mint: Pubkey,
+    output_mint: Pubkey,
+    quoted_out_amount: u64,
+) -> (Vec<u8>, Vec<AccountMeta>) {
+    let program_id = yona::jupiter_aggregator::ID;
+    let (global_config, _) = find_global_config_address(&yona::ID, 0);
+    let data = Route {
+        route_plan: vec![],
+        in_amount: EXT_AMOUNT.unsigned_abs(),
+        quoted_out_amount,
+        slippage_bps: 50,
+        platform_fee_bps: 0,
+    }
+    .data();
+    let accounts = vec![
+        AccountMeta::new_readonly(anchor_spl::token::ID, false),
+        AccountMeta::new_readonly(
+            find_swap_authority_address(&yona::ID, &global_config).0,
+            false,
+        ),
+        AccountMeta::new(
+            reserve_token_account(&yona::ID, 0, &input_mint, &anchor_spl::token::ID),
+            false,
+        ),
+        AccountMeta::new(
+            reserve_token_account(&yona::ID, 0, &output_mint, &anchor_spl::token::ID),
+            false,
+        ),
+        AccountMeta::new_readonly(program_id, false),
+        AccountMeta::new_readonly(output_mint, false),
+        AccountMeta::new_readonly(program_id, false),
+        AccountMeta::new_readonly(Pubkey::new_unique(), false),
+        AccountMeta::new_readonly(program_id, false),
+    ];
+    (data, accounts)
+}
+
+#[test]
+fn swap_rejects_a_route_other_than_the_proven_one() {
+    let (tree_account, global_config) = (tree_account(), global_config());
+    let verifying_key = verifying_key_account();
+    let aggregator_config = aggregator_config(true);
+    let (input_mint, output_mint, fee_recipient) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let state = PreflightState {
+        tree_account: &tree_account,
+        retired_tree_account: None,
+        verifying_key: &verifying_key,
+        global_config: &global_config,
+        now: 0,
+    };
+    let route_data = vec![0xaa; 16];
+    let route_accounts = vec![
+        AccountMeta::new(Pubkey::new_unique(), false),
+        AccountMeta::new_readonly(Pubkey::new_unique(), false),
+    ];
+    let proof = swap_proof(
+        tree_account.root,
+        input_mint,
+        output_mint,
+        fee_recipient,
+        &route_data,
+        &route_accounts,
+    );
+    let (output_mint_config, relayer) = (mint_config(output_mint), relayer_account(30));
+    let preflight = |data: &[u8], accounts: &[AccountMeta]| {
+        preflight_swap(
+            &state,
+            &output_mint_config,
+            &relayer,
+            relayer.fee_recipient,
+            Some(&aggregator_config),
+            &swap_accounts(input_mint, output_mint, fee_recipient, accounts.to_vec()),
+            &proof,
+            &SwapExtDataMinified {
+                ext_amount: EXT_AMOUNT,
+                ext_min_amount_out: SWAP_MIN_AMOUNT_OUT,
+                fee: 0,
+            },
+            &[],
+            data,
+        )
+    };
+
+    let report = preflight(&route_data, &route_accounts);
+    assert!(
+        report.failure(PreflightCheck::ExtDataHash).is_none(),
+        "{:?}",
+        report.failures
+    );
+
+    // Another account, the same account read-only, other instruction data
+    let mut other_account = route_accounts.clone();
+    other_account[1] = AccountMeta::new_readonly(Pubkey::new_unique(), false);
+    let mut read_only = route_accounts.clone();
+    read_only[0].is_writable = false;
+    let mut other_data = route_data.clone();
+    other_data[0] = 0xbb;
+
+    for (data, accounts) in [
+        (&route_data, &other_account),
+        (&route_data, &read_only),
+        (&other_data, &route_accounts),
+    ] {
+        assert_eq!(
+            error_code(&preflight(data, accounts), PreflightCheck::ExtDataHash),
+            Some(u32::from(ErrorCode::ExtDataHashMismatch))
+        );
+    }
+}
+
+#[test]
+fn swap_reports_the_route_aggregator_and_surplus_checks() {
+    let (tree_account, global_config) = (tree_account(), global_config());
+    let verifying_key = verifying_key_account();
+    let (input_mint, output_mint, fee_recipient) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let state = PreflightState {
+        tree_account: &tree_account,
+        retired_tree_account: None,
+        verifying_key: &verifying_key,
+        global_config: &global_config,
+        now: 0,
+    };
+    let relayer = relayer_account(30);
+    let preflight_with_mint = |output_mint_config: &MintConfig,
+                               aggregator_config: Option<&AggregatorConfig>,
+                               route: &(Vec<u8>, Vec<AccountMeta>),
+                               fee: u64| {
+        let (data, accounts) = route;
+        preflight_swap(
+            &state,
+            output_mint_config,
+            &relayer,
+            relayer.fee_recipient,
+            aggregator_config,
+            &swap_accounts(input_mint, output_mint, fee_recipient, accounts.clone()),
+            &swap_proof(
+                tree_account.root,
+                input_mint,
+                output_mint,
+                fee_recipient,
+                data,
+                accounts,
+            ),
+            &SwapExtDataMinified {
+                ext_amount: EXT_AMOUNT,
+                ext_min_amount_out: SWAP_MIN_AMOUNT_OUT,
+                fee,
+            },
+            &[],
+            data,
+        )
+    };
+    let output_mint_config = mint_config(output_mint);
+    let preflight = |aggregator_config: Option<&AggregatorConfig>,
+                     route: &(Vec<u8>, Vec<AccountMeta>),
+                     fee: u64| {
+        preflight_with_mint(&output_mint_config, aggregator_config, route, fee)
+    };
+    let enabled = aggregator_config(true);
+    // 2_000 above the user's minimum, within the relayer's 0.3%
+    let route = jupiter_route(input_mint, output_mint, 992_000);
+
+    let report = preflight(Some(&enabled), &route, 0);
+    for check in [
+        PreflightCheck::Relayer,
+        PreflightCheck::FeeRecipient,
+        PreflightCheck::Mint,
+        PreflightCheck::Aggregator,
+        PreflightCheck::ExtDataHash,
+        PreflightCheck::Fee,
+        PreflightCheck::Route,
+        PreflightCheck::SwapSurplus,
+    ] {
+        assert!(report.failure(check).is_none(), "{:?}", report.failures);
+    }
+
+    // New notes can't be created in a disabled output mint
+    let disabled = MintConfig {
+        enabled: false,
+        ..mint_config(output_mint)
+    };
+    assert_eq!(
+        error_code(
+            &preflight_with_mint(&disabled, Some(&enabled), &route, 0),
+            PreflightCheck::Mint
+        ),
+        Some(u32::from(ErrorCode::UnsupportedMintAddress))
+    );
+
+    // Missing or disabled aggregator entry
+    for aggregator_config in [None, Some(&aggregator_config(false))] {
+        assert_eq!(
+            error_code(
+                &preflight(aggregator_config, &route, 0),
+                PreflightCheck::Aggregator
+            ),
+            Some(u32::from(ErrorCode::AggregatorNotAllowed))
+        );
+    }
+
+    // The route pays out of another account than the input reserve
+    let mut other_source = route.clone();
+    other_source.1[2] = AccountMeta::new(Pubkey::new_unique(), false);
+    assert_eq!(
+        error_code(
+            &preflight(Some(&enabled), &other_source, 0),
+            PreflightCheck::Route
+        ),
+        Some(u32::from(ErrorCode::InvalidJupiterRouteAccount))
+    );
+
+    // The quote doesn't cover the user's minimum, or pays the relayer more than its rate
+    for (quoted_out_amount, error) in [
+        (SWAP_MIN_AMOUNT_OUT as u64 - 1, ErrorCode::InsufficientSwapOutput),
+        (1_000_000, ErrorCode::RelayerFeeTooHigh),
+    ] {
+        let route = jupiter_route(input_mint, output_mint, quoted_out_amount);
+        assert_eq!(
+            error_code(
+                &preflight(Some(&enabled), &route, 0),
+                PreflightCheck::SwapSurplus
+            ),
+            Some(u32::from(error))
+        );
+    }
+    // A relayer may take nothing
+    let no_surplus = jupiter_route(input_mint, output_mint, SWAP_MIN_AMOUNT_OUT as u64);
+    assert!(preflight(Some(&enabled), &no_surplus, 0)
+        .failure(PreflightCheck::SwapSurplus)
+        .is_none());
+
+    // The relayer is paid out of the output, a fee on the input side would be stranded
+    assert_eq!(
+        error_code(&preflight(Some(&enabled), &route, 1), PreflightCheck::Fee),
+        Some(u32::from(ErrorCode::InvalidFeeAmount))
+    );
+}
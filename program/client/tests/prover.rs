@@ -0,0 +1,124 @@
+//! Proves a deposit with the native prover and verifies it with the program's verifier.
+//! Needs the `transaction2` circuit artifacts in `YONA_CIRCUITS_DIR`, see `README.md`.
+#![cfg(feature = "prover")]
+
+use anchor_lang::prelude::*;
+use ark_bn254::Fr;
+use ark_ff::{BigInteger, PrimeField};
+use ark_std::UniformRand;
+use light_hasher::{Hasher, Poseidon};
+use light_poseidon::{Poseidon as Circom, PoseidonHasher};
+use yona::state::MERKLE_TREE_HEIGHT;
+use yona::utils::{calculate_complete_ext_data_hash, verify_compressed_proof};
+use yona::ErrorCode;
+use yona_client::prover::{ext_data_hash_to_field, TransactionInputs, TransactionProver};
+use yona_client::verifying_key::TRANSACTION2_VERIFYING_KEY;
+
+const DEPOSIT_AMOUNT: u64 = 1_000_000;
+
+fn zkey_path() -> String {
+    let circuits_dir = std::env::var("YONA_CIRCUITS_DIR")
+        .expect("YONA_CIRCUITS_DIR must point to the transaction2 circuit artifacts");
+    format!("{circuits_dir}/transaction2.zkey")
+}
+
+fn poseidon(inputs: &[Fr]) -> Fr {
+    Circom::<Fr>::new_circom(inputs.len()).unwrap().hash(inputs).unwrap()
+}
+
+fn fr_from_be_bytes(bytes: &[u8; 32]) -> Fr {
+    Fr::from_be_bytes_mod_order(bytes)
+}
+
+fn fr_to_be_bytes(value: &Fr) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(&value.into_bigint().to_bytes_be());
+    bytes
+}
+
+/// Private key, blinding and amount of one circuit UTXO
+struct Note {
+    privkey: Fr,
+    blinding: Fr,
+    amount: u64,
+}
+
+impl Note {
+    fn pubkey(&self) -> Fr {
+        poseidon(&[self.privkey])
+    }
+
+    fn commitment(&self, mint: Fr) -> Fr {
+        poseidon(&[Fr::from(self.amount), self.pubkey(), self.blinding, mint])
+    }
+
+    // Input notes sit at leaf index 0, zero amount notes are not checked against the root
+    fn nullifier(&self, mint: Fr) -> Fr {
+        let commitment = self.commitment(mint);
+        let signature = poseidon(&[self.privkey, commitment, Fr::from(0u64)]);
+        poseidon(&[commitment, Fr::from(0u64), signature])
+    }
+}
+
+#[test]
+fn deposit_proof_verifies_on_chain() {
+    let mut rng = ark_std::test_rng();
+    let prover = TransactionProver::from_zkey_file(zkey_path()).unwrap();
+
+    let root = Poseidon::zero_bytes()[MERKLE_TREE_HEIGHT as usize];
+
+    // Mint bytes below the field size, the verifier feeds them to the circuit unreduced
+    let mint = Pubkey::new_from_array([7; 32]);
+    let mint_field = fr_from_be_bytes(&mint.to_bytes());
+    let (reserve, fee_recipient) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let ext_data_hash = calculate_complete_ext_data_hash(
+        reserve,
+        DEPOSIT_AMOUNT as i64,
+        &[],
+        0,
+        fee_recipient,
+        mint,
+        mint,
+    )
+    .unwrap();
+
+    let privkey = Fr::rand(&mut rng);
+    let mut note = |amount| Note { privkey, blinding: Fr::rand(&mut rng), amount };
+    let inputs = [note(0), note(0)];
+    let outputs = [note(DEPOSIT_AMOUNT), note(0)];
+    let pair = |f: &dyn Fn(&Note) -> Fr, notes: &[Note; 2]| [f(&notes[0]), f(&notes[1])];
+    let zero_path = vec![Fr::from(0u64); MERKLE_TREE_HEIGHT as usize];
+
+    let transaction_inputs = TransactionInputs {
+        root: fr_from_be_bytes(&root),
+        input_nullifier: pair(&|note| note.nullifier(mint_field), &inputs),
+        output_commitment: pair(&|note| note.commitment(mint_field), &outputs),
+        public_amount0: Fr::from(DEPOSIT_AMOUNT),
+        public_amount1: Fr::from(0u64),
+        ext_data_hash: ext_data_hash_to_field(&ext_data_hash),
+        mint_address0: mint_field,
+        mint_address1: mint_field,
+        in_amount: pair(&|note| Fr::from(note.amount), &inputs),
+        in_mint_address: [mint_field, mint_field],
+        in_private_key: [privkey, privkey],
+        in_blinding: pair(&|note| note.blinding, &inputs),
+        in_path_indices: [Fr::from(0u64), Fr::from(0u64)],
+        in_path_elements: [zero_path.clone(), zero_path],
+        out_amount: pair(&|note| Fr::from(note.amount), &outputs),
+        out_mint_address: [mint_field, mint_field],
+        out_pubkey: pair(&|note| note.pubkey(), &outputs),
+        out_blinding: pair(&|note| note.blinding, &outputs),
+    };
+    let proof = prover.prove(&transaction_inputs, &mut rng).unwrap();
+    assert_eq!(proof.root, root);
+    assert_eq!(proof.output_commitments[0], fr_to_be_bytes(&transaction_inputs.output_commitment[0]));
+
+    verify_compressed_proof(proof.clone(), TRANSACTION2_VERIFYING_KEY, mint, mint).unwrap();
+
+    // The same proof bound to another output commitment is rejected
+    let mut tampered = proof;
+    tampered.output_commitments[1] = tampered.output_commitments[0];
+    let error = verify_compressed_proof(tampered, TRANSACTION2_VERIFYING_KEY, mint, mint)
+        .unwrap_err();
+    assert_eq!(error, ErrorCode::InvalidProof.into());
+}
@@ -0,0 +1,69 @@
+//! Cross-language test vectors shared with `tests/utxo_vectors.ts`.
+use std::str::FromStr;
+
+use anchor_lang::prelude::Pubkey;
+use ark_bn254::Fr;
+use serde::Deserialize;
+use yona_client::field::mint_to_field;
+use yona_client::{ShieldedKeypair, Utxo};
+
+#[derive(Deserialize)]
+struct Fixture {
+    vectors: Vec<Vector>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Vector {
+    private_key: String,
+    amount: String,
+    blinding: String,
+    index: u64,
+    mint_address: String,
+    expected: Expected,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Expected {
+    private_key_field: String,
+    public_key: String,
+    mint_address_field: String,
+    commitment: String,
+    signature: String,
+    nullifier: String,
+}
+
+fn fr(value: &str) -> Fr {
+    Fr::from_str(value).unwrap()
+}
+
+#[test]
+fn utxo_matches_typescript_vectors() {
+    let fixture: Fixture = serde_json::from_str(include_str!("../../tests/fixtures/utxo_vectors.json")).unwrap();
+    assert!(!fixture.vectors.is_empty());
+
+    for vector in fixture.vectors {
+        let keypair = ShieldedKeypair::from_private_key_hex(&vector.private_key).unwrap();
+        assert_eq!(keypair.privkey, fr(&vector.expected.private_key_field));
+        assert_eq!(keypair.pubkey, fr(&vector.expected.public_key));
+
+        let mint = Pubkey::from_str(&vector.mint_address).unwrap();
+        assert_eq!(mint_to_field(&mint), fr(&vector.expected.mint_address_field));
+
+        let utxo = Utxo::new(
+            vector.amount.parse().unwrap(),
+            fr(&vector.blinding),
+            keypair.clone(),
+            vector.index,
+            mint,
+        );
+        let commitment = utxo.commitment().unwrap();
+        assert_eq!(commitment, fr(&vector.expected.commitment));
+        assert_eq!(
+            keypair.sign(commitment, Fr::from(vector.index)).unwrap(),
+            fr(&vector.expected.signature)
+        );
+        assert_eq!(utxo.nullifier().unwrap(), fr(&vector.expected.nullifier));
+    }
+}
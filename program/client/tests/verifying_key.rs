@@ -0,0 +1,69 @@
+use yona::state::{StoredVerifyingKey, VerifyingKeyAccount};
+use yona::utils::check_valid_proof;
+use yona::ErrorCode;
+use yona_client::verifying_key::{
+    verifying_key_data, verifying_key_hash, TRANSACTION2_VERIFYING_KEY,
+};
+
+/// Pending key as left by `stage_verifying_key` and the `write_verifying_key` chunks.
+fn written_key(version: u32, chunks: &[(usize, &[u8])]) -> StoredVerifyingKey {
+    let mut key = StoredVerifyingKey {
+        version,
+        nr_pubinputs: TRANSACTION2_VERIFYING_KEY.nr_pubinputs as u32,
+        ..StoredVerifyingKey::EMPTY
+    };
+    for (offset, data) in chunks {
+        key.write(*offset, data).unwrap();
+    }
+    key
+}
+
+#[test]
+fn complete_key_matches_the_staged_hash() {
+    let data = verifying_key_data(&TRANSACTION2_VERIFYING_KEY);
+    let key_hash = verifying_key_hash(&TRANSACTION2_VERIFYING_KEY);
+
+    let key = written_key(1, &[(0, &data[..800]), (800, &data[800..])]);
+    assert_eq!(key.data_len(), data.len());
+    assert_eq!(key.key_hash(), key_hash);
+    assert!(!key.has_identity_point());
+
+    // The last IC point was never written
+    let partial = written_key(1, &[(0, &data[..data.len() - 64])]);
+    assert_ne!(partial.key_hash(), key_hash);
+    assert!(partial.has_identity_point());
+
+    // Writes past the key of the circuit are rejected
+    let mut key = written_key(1, &[]);
+    assert_eq!(
+        key.write(data.len() - 1, &[1, 2]).unwrap_err(),
+        ErrorCode::InvalidVerifyingKey.into()
+    );
+}
+
+#[test]
+fn previous_key_is_accepted_during_the_migration_window() {
+    let data = verifying_key_data(&TRANSACTION2_VERIFYING_KEY);
+    let mut account: VerifyingKeyAccount = bytemuck::Zeroable::zeroed();
+    account.previous = written_key(1, &[(0, &data)]);
+    account.current = written_key(2, &[(0, &data)]);
+    account.current.vk_delta_g2[0] ^= 1;
+    account.previous_valid_until = 1_000;
+
+    assert_eq!(account.accepted_keys(1_000).count(), 2);
+    assert_eq!(account.accepted_keys(1_001).count(), 1);
+
+    // A proof of the previous key lands until the window closes
+    let previous_delta = account.previous.vk_delta_g2;
+    let verify_at = |now: i64| {
+        check_valid_proof(&account, now, |key| {
+            if key.vk_delta_g2 == previous_delta {
+                Ok(())
+            } else {
+                Err(ErrorCode::InvalidProof.into())
+            }
+        })
+    };
+    assert!(verify_at(1_000).is_ok());
+    assert_eq!(verify_at(1_001).unwrap_err(), ErrorCode::InvalidProof.into());
+}
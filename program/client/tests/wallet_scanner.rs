@@ -0,0 +1,142 @@
+use std::collections::HashSet;
+
+use anchor_lang::prelude::Pubkey;
+use yona::CommitmentData;
+use yona_client::field::fr_to_be_bytes;
+use yona_client::note::encrypt_notes;
+use yona_client::scanner::WalletScanner;
+use yona_client::utxo::random_blinding;
+use yona_client::{NotePlaintext, ShieldedKeypair, Utxo, ViewingKey};
+
+#[test]
+fn scanner_finds_owned_utxos_and_tracks_spent_status() {
+    let mut rng = rand::rngs::OsRng;
+    let alice = ShieldedKeypair::generate(&mut rng).unwrap();
+    let bob = ShieldedKeypair::generate(&mut rng).unwrap();
+    let alice_viewing_key = ViewingKey::from_keypair(&alice).unwrap().public_key();
+    let bob_viewing_key = ViewingKey::from_keypair(&bob).unwrap().public_key();
+    let mint = Pubkey::new_unique();
+
+    let to_alice = Utxo::new(700, random_blinding(&mut rng), alice.clone(), 0, mint);
+    let to_bob = Utxo::new(300, random_blinding(&mut rng), bob.clone(), 1, mint);
+    let first = CommitmentData {
+        pool_id: 0,
+        tree_index: 0,
+        index: 0,
+        commitment0: fr_to_be_bytes(&to_alice.commitment().unwrap()),
+        commitment1: fr_to_be_bytes(&to_bob.commitment().unwrap()),
+        encrypted_output: encrypt_notes(
+            [
+                (&NotePlaintext::from(&to_alice), &alice_viewing_key),
+                (&NotePlaintext::from(&to_bob), &bob_viewing_key),
+            ],
+            &mut rng,
+        )
+        .unwrap(),
+    };
+
+    // A note sealed to alice that doesn't open the on-chain commitment must be ignored
+    let forged = NotePlaintext {
+        amount: 1_000_000,
+        blinding: random_blinding(&mut rng),
+        mint_address: mint,
+    };
+    let second = CommitmentData {
+        pool_id: 0,
+        tree_index: 0,
+        index: 2,
+        commitment0: [1u8; 32],
+        commitment1: [2u8; 32],
+        encrypted_output: encrypt_notes(
+            [(&forged, &alice_viewing_key), (&forged, &alice_viewing_key)],
+            &mut rng,
+        )
+        .unwrap(),
+    };
+
+    // Legacy payloads are skipped
+    let legacy = CommitmentData {
+        pool_id: 0,
+        tree_index: 0,
+        index: 4,
+        commitment0: [3u8; 32],
+        commitment1: [4u8; 32],
+        encrypted_output: b"{}".to_vec(),
+    };
+
+    let mut scanner = WalletScanner::new(alice).unwrap();
+    assert_eq!(scanner.scan([&first, &second, &legacy]).unwrap(), 1);
+    // Replays don't duplicate UTXOs
+    assert_eq!(scanner.scan_event(&first).unwrap(), 0);
+    assert_eq!(scanner.balances().get(&mint), Some(&700));
+
+    let owned = scanner.utxos().next().unwrap().clone();
+    assert_eq!(owned.utxo.index, 0);
+    assert_eq!(owned.nullifier, fr_to_be_bytes(&to_alice.nullifier().unwrap()));
+
+    scanner.refresh_spent(&HashSet::new()).unwrap();
+    assert_eq!(scanner.unspent().count(), 1);
+
+    scanner
+        .refresh_spent(&HashSet::from([owned.nullifier_address]))
+        .unwrap();
+    assert_eq!(scanner.unspent().count(), 0);
+    assert!(scanner.balances().is_empty());
+}
+
+#[test]
+fn scanner_keeps_utxos_at_the_same_leaf_index_across_trees_and_pools() {
+    let mut rng = rand::rngs::OsRng;
+    let alice = ShieldedKeypair::generate(&mut rng).unwrap();
+    let alice_viewing_key = ViewingKey::from_keypair(&alice).unwrap().public_key();
+    let mint = Pubkey::new_unique();
+
+    // Leaf indices restart at 0 in the tree opened by a rollover and in every pool
+    let event = |pool_id: u16, tree_index: u32, amount: u64, rng: &mut rand::rngs::OsRng| {
+        let utxo = Utxo::new(amount, random_blinding(rng), alice.clone(), 0, mint);
+        let dummy = Utxo::new(0, random_blinding(rng), alice.clone(), 1, mint);
+        CommitmentData {
+            pool_id,
+            tree_index,
+            index: 0,
+            commitment0: fr_to_be_bytes(&utxo.commitment().unwrap()),
+            commitment1: fr_to_be_bytes(&dummy.commitment().unwrap()),
+            encrypted_output: encrypt_notes(
+                [
+                    (&NotePlaintext::from(&utxo), &alice_viewing_key),
+                    (&NotePlaintext::from(&dummy), &alice_viewing_key),
+                ],
+                rng,
+            )
+            .unwrap(),
+        }
+    };
+    let retired_tree = event(0, 0, 100, &mut rng);
+    let active_tree = event(0, 1, 200, &mut rng);
+    let other_pool = event(5, 0, 400, &mut rng);
+
+    let mut scanner = WalletScanner::new(alice.clone()).unwrap();
+    assert_eq!(scanner.scan([&retired_tree, &active_tree, &other_pool]).unwrap(), 6);
+    assert_eq!(scanner.scan([&retired_tree, &active_tree, &other_pool]).unwrap(), 0);
+    assert_eq!(scanner.balances().get(&mint), Some(&700));
+
+    let positions: Vec<_> = scanner
+        .utxos()
+        .filter(|owned| owned.utxo.amount > 0)
+        .map(|owned| (owned.pool_id, owned.tree_index, owned.utxo.index, owned.utxo.amount))
+        .collect();
+    assert_eq!(positions, vec![(0, 0, 0, 100), (0, 1, 0, 200), (5, 0, 0, 400)]);
+
+    // The same leaf index in another tree has its own nullifier
+    let nullifier_address = |tree_index: u32| {
+        scanner
+            .utxos()
+            .find(|owned| owned.pool_id == 0 && owned.tree_index == tree_index && owned.utxo.amount > 0)
+            .unwrap()
+            .nullifier_address
+    };
+    let spent = HashSet::from([nullifier_address(0)]);
+    assert_ne!(nullifier_address(0), nullifier_address(1));
+    scanner.refresh_spent(&spent).unwrap();
+    assert_eq!(scanner.balances().get(&mint), Some(&600));
+}
@@ -21,8 +21,8 @@ pub enum Groth16Error {
     #[error("Preparing inputs G1 addition failed")]
     PreparingInputsG1AdditionFailed,
     
-    #[error("Proof verification failed")]
-    ProofVerificationFailed,
+    #[error("Pairing syscall failed")]
+    PairingFailed,
 }
 
 #[error_code]
@@ -63,7 +63,7 @@ pub enum ErrorCode {
     MerkleTreeFull,
     #[msg("Unsupported mint address")]
     UnsupportedMintAddress,
-    #[msg("Dual-token transactions are not yet supported")]
+    #[msg("Dual-token transactions must use transact_dual")]
     DualTokenNotSupported,
     #[msg("Invalid Jupiter swap data")]
     InvalidJupiterSwapData,
@@ -77,4 +77,88 @@ pub enum ErrorCode {
     LightProtocolError,
     #[msg("Invalid address tree: must use the canonical address tree")]
     InvalidAddressTree,
+    #[msg("Invalid tree height: must be between 1 and MERKLE_TREE_HEIGHT")]
+    InvalidTreeHeight,
+    #[msg("Invalid root history size: must be between 1 and MAX_ROOT_HISTORY_SIZE")]
+    InvalidRootHistorySize,
+    #[msg("Tree is not full: only the pool authority can roll it over")]
+    TreeNotFull,
+    #[msg("Retired tree does not belong to this pool or is not retired")]
+    InvalidRetiredTree,
+    #[msg("Deposit amount is below the minimum for this mint")]
+    DepositBelowMinimum,
+    #[msg("Invalid mint config: min deposit must not exceed max deposit")]
+    InvalidMintConfig,
+    #[msg("Verifying key for this circuit is not set")]
+    VerifyingKeyNotSet,
+    #[msg("Invalid verifying key data")]
+    InvalidVerifyingKey,
+    #[msg("Verifying key version must be greater than the current version")]
+    InvalidVerifyingKeyVersion,
+    #[msg("Pending verifying key is still timelocked")]
+    VerifyingKeyTimelocked,
+    #[msg("Pending verifying key does not match the hash committed when it was staged")]
+    VerifyingKeyHashMismatch,
+    #[msg("Proof point A is not a valid compressed G1 point")]
+    InvalidProofPointA,
+    #[msg("Proof point B is not a valid compressed G2 point")]
+    InvalidProofPointB,
+    #[msg("Proof point C is not a valid compressed G1 point")]
+    InvalidProofPointC,
+    #[msg("Groth16: invalid G1 point length")]
+    Groth16InvalidG1Length,
+    #[msg("Groth16: invalid G2 point length")]
+    Groth16InvalidG2Length,
+    #[msg("Groth16: number of public inputs does not match the verifying key")]
+    Groth16InvalidPublicInputsLength,
+    #[msg("Groth16: public input greater than field size")]
+    Groth16PublicInputGreaterThanFieldSize,
+    #[msg("Groth16: preparing inputs G1 multiplication failed")]
+    Groth16PreparingInputsG1MulFailed,
+    #[msg("Groth16: preparing inputs G1 addition failed")]
+    Groth16PreparingInputsG1AdditionFailed,
+    #[msg("Groth16: pairing syscall failed")]
+    Groth16PairingFailed,
+    #[msg("Relayer bond is below the pool minimum")]
+    RelayerBondTooLow,
+    #[msg("Fee exceeds the fee rate advertised by the relayer")]
+    RelayerFeeTooHigh,
+    #[msg("Relayer has requested its deregistration and cannot relay transactions")]
+    RelayerDeregistering,
+    #[msg("Relayer bond is still unbonding, request the deregistration first and wait for the delay")]
+    RelayerBondUnbonding,
+    #[msg("Slash amount exceeds the relayer bond")]
+    SlashExceedsRelayerBond,
+    #[msg("Deposit authority is not approved for the deposit amount and fee")]
+    DepositNotApproved,
+    #[msg("Signer is neither the owner nor an approved delegate of the source token account")]
+    InvalidTokenAccountAuthority,
+    #[msg("Aggregator program is not allowed in this pool")]
+    AggregatorNotAllowed,
+    #[msg("Swap data is not a supported Jupiter route")]
+    UnsupportedJupiterRoute,
+    #[msg("Jupiter route must swap between the pool reserves")]
+    InvalidJupiterRouteAccount,
+    #[msg("Jupiter route input amount does not match the swap amount")]
+    JupiterRouteAmountMismatch,
+    #[msg("Swap route decreased the balance of a reserve other than the input one")]
+    ReserveBalanceDecreased,
+    #[msg("Swap route did not spend exactly the input amount from the input reserve")]
+    SwapInputMismatch,
+    #[msg("Remaining accounts are missing Light Protocol accounts")]
+    NotEnoughLightAccounts,
+}
+
+impl From<Groth16Error> for ErrorCode {
+    fn from(error: Groth16Error) -> Self {
+        match error {
+            Groth16Error::InvalidG1Length => ErrorCode::Groth16InvalidG1Length,
+            Groth16Error::InvalidG2Length => ErrorCode::Groth16InvalidG2Length,
+            Groth16Error::InvalidPublicInputsLength => ErrorCode::Groth16InvalidPublicInputsLength,
+            Groth16Error::PublicInputGreaterThanFieldSize => ErrorCode::Groth16PublicInputGreaterThanFieldSize,
+            Groth16Error::PreparingInputsG1MulFailed => ErrorCode::Groth16PreparingInputsG1MulFailed,
+            Groth16Error::PreparingInputsG1AdditionFailed => ErrorCode::Groth16PreparingInputsG1AdditionFailed,
+            Groth16Error::PairingFailed => ErrorCode::Groth16PairingFailed,
+        }
+    }
 } 
\ No newline at end of file
@@ -138,12 +138,11 @@ impl<const NR_INPUTS: usize> Groth16Verifier<'_, NR_INPUTS> {
         .concat();
 
         let pairing_res = alt_bn128_pairing(pairing_input.as_slice())
-            .map_err(|_| Groth16Error::ProofVerificationFailed)?;
+            .map_err(|_| Groth16Error::PairingFailed)?;
 
-        if pairing_res[31] != 1 {
-            return Err(Groth16Error::ProofVerificationFailed);
-        }
-        Ok(true)
+        // A well-formed proof that doesn't satisfy the pairing equation is a wrong proof,
+        // not an error: callers map Ok(false) to InvalidProof.
+        Ok(pairing_res[31] == 1)
     }
 }
 
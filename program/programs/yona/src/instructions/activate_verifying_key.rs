@@ -0,0 +1,41 @@
+use anchor_lang::prelude::*;
+use crate::state::StoredVerifyingKey;
+use crate::types::VerifyingKeyActivatedEvent;
+use crate::ErrorCode;
+
+/**
+ * Promotes the pending key once its timelock has passed and all of its bytes match the
+ * staged hash. The replaced key stays accepted for `migration_window_seconds` so
+ * in-flight proofs don't fail.
+ */
+pub fn handler(ctx: Context<crate::UpdateVerifyingKey>) -> Result<()> {
+    let verifying_key = &mut ctx.accounts.verifying_key.load_mut()?;
+    require!(verifying_key.pending.is_set(), ErrorCode::VerifyingKeyNotSet);
+
+    let now = Clock::get()?.unix_timestamp;
+    require!(now >= verifying_key.pending_activation_ts, ErrorCode::VerifyingKeyTimelocked);
+    // A chunk left unwritten or overwritten after staging changes the hash
+    require!(
+        verifying_key.pending.key_hash() == verifying_key.pending_key_hash,
+        ErrorCode::VerifyingKeyHashMismatch
+    );
+    require!(!verifying_key.pending.has_identity_point(), ErrorCode::InvalidVerifyingKey);
+
+    verifying_key.previous = verifying_key.current;
+    verifying_key.previous_valid_until = now
+        .checked_add(verifying_key.migration_window_seconds)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+    verifying_key.current = verifying_key.pending;
+    verifying_key.pending = StoredVerifyingKey::EMPTY;
+    verifying_key.pending_activation_ts = 0;
+    verifying_key.pending_key_hash = [0; 32];
+
+    emit!(VerifyingKeyActivatedEvent {
+        circuit: verifying_key.circuit,
+        version: verifying_key.current.version,
+        previous_version: verifying_key.previous.version,
+        previous_valid_until: verifying_key.previous_valid_until,
+    });
+
+    Ok(())
+}
@@ -8,10 +8,10 @@ use anchor_spl::token_interface::{
 };
 
 use crate::merkle_tree::MerkleTree;
-use crate::state::{MerkleTreeAccount, GlobalConfig};
+use crate::state::{MerkleTreeAccount, GlobalConfig, MintConfig, VerifyingKeyAccount, CIRCUIT_TRANSACTION2};
 use crate::types::{CompressedProof, ExtData, ExtDataMinified, CommitmentData, DepositEvent};
 use crate::ErrorCode;
-use crate::utils::{verify_compressed_proof, VERIFYING_KEY};
+use crate::utils::verify_compressed_proof;
 use crate::utils;
 use crate::light::create_light_nullifiers;
 use light_sdk::instruction::{PackedAddressTreeInfo, ValidityProof};
@@ -27,24 +27,42 @@ use light_sdk::instruction::{PackedAddressTreeInfo, ValidityProof};
     nullifier1_address_tree_info: PackedAddressTreeInfo,
     output_state_tree_index: u8
 )]
-
-
 pub struct Deposit<'info> {
     #[account(
         mut,
-        seeds = [b"merkle_tree"],
+        seeds = [
+            b"merkle_tree",
+            global_config.pool_id.to_le_bytes().as_ref(),
+            global_config.active_tree_index.to_le_bytes().as_ref()
+        ],
         bump = tree_account.load()?.bump
     )]
     pub tree_account: AccountLoader<'info, MerkleTreeAccount>,
 
+    /// Retired tree of the same pool, only needed when the proof root is from a rolled over tree
+    pub retired_tree_account: Option<AccountLoader<'info, MerkleTreeAccount>>,
+
     #[account(
-        seeds = [b"global_config"],
-        bump
+        seeds = [b"global_config", global_config.pool_id.to_le_bytes().as_ref()],
+        bump = global_config.bump
     )]
     pub global_config: Box<Account<'info, GlobalConfig>>,
+
+    #[account(
+        seeds = [b"verifying_key", CIRCUIT_TRANSACTION2.to_le_bytes().as_ref()],
+        bump = verifying_key.load()?.bump
+    )]
+    pub verifying_key: AccountLoader<'info, VerifyingKeyAccount>,
     
     pub input_mint: Box<InterfaceAccount<'info, Mint>>,
 
+    #[account(
+        seeds = [b"mint_config", global_config.key().as_ref(), input_mint.key().as_ref()],
+        bump = mint_config.bump,
+        constraint = mint_config.enabled @ ErrorCode::UnsupportedMintAddress
+    )]
+    pub mint_config: Box<Account<'info, MintConfig>>,
+
     #[account(mut,
         associated_token::mint = input_mint,  
         associated_token::authority = global_config,
@@ -73,6 +91,7 @@ pub struct Deposit<'info> {
     //  account_compression_program, system_program, address_tree, address_queue, output_state_tree, ...]
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn handler<'info>(
     ctx: Context<'_, '_, '_, 'info, Deposit<'info>>, 
     proof: CompressedProof, 
@@ -83,21 +102,90 @@ pub fn handler<'info>(
     nullifier1_address_tree_info: PackedAddressTreeInfo,
     output_state_tree_index: u8,
 ) -> Result<()> {
-    let tree_account = &mut ctx.accounts.tree_account.load_mut()?;
-    let global_config = &ctx.accounts.global_config;
+    // The user owns the associated token account and signs the transfers itself
+    process_deposit(
+        DepositAccounts {
+            tree_account: &ctx.accounts.tree_account,
+            retired_tree_account: ctx.accounts.retired_tree_account.as_ref(),
+            global_config: &ctx.accounts.global_config,
+            verifying_key: &ctx.accounts.verifying_key,
+            input_mint: &ctx.accounts.input_mint,
+            mint_config: &ctx.accounts.mint_config,
+            reserve_token_account: ctx.accounts.reserve_token_account.to_account_info(),
+            fee_recipient_account: ctx.accounts.fee_recipient_account.to_account_info(),
+            user_token_account: ctx.accounts.user_token_account.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+            payer: ctx.accounts.user.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            remaining_accounts: ctx.remaining_accounts,
+        },
+        proof,
+        ext_data_minified,
+        encrypted_output,
+        light_proof,
+        nullifier0_address_tree_info,
+        nullifier1_address_tree_info,
+        output_state_tree_index,
+        &[],
+        |_, _| Ok(()),
+    )
+}
+
+/// Accounts of a deposit, the deposit instructions only differ in who signs the transfers out
+/// of `user_token_account` and who pays the nullifiers
+pub(crate) struct DepositAccounts<'a, 'info> {
+    pub tree_account: &'a AccountLoader<'info, MerkleTreeAccount>,
+    pub retired_tree_account: Option<&'a AccountLoader<'info, MerkleTreeAccount>>,
+    pub global_config: &'a GlobalConfig,
+    pub verifying_key: &'a AccountLoader<'info, VerifyingKeyAccount>,
+    pub input_mint: &'a InterfaceAccount<'info, Mint>,
+    pub mint_config: &'a MintConfig,
+    pub reserve_token_account: AccountInfo<'info>,
+    pub fee_recipient_account: AccountInfo<'info>,
+    pub user_token_account: AccountInfo<'info>,
+    /// Signs the transfers out of `user_token_account`, with `signer_seeds` if it is a PDA
+    pub authority: AccountInfo<'info>,
+    /// Pays the Light Protocol nullifier accounts
+    pub payer: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub remaining_accounts: &'a [AccountInfo<'info>],
+}
+
+/// Checks the proof and the deposit limits, then moves `ext_amount` and the fee out of
+/// `user_token_account` and appends the output commitments.
+/// `authorize` gets the deposit amount and the fee before anything is transferred, it holds the
+/// checks specific to each deposit instruction.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn process_deposit<'info>(
+    accounts: DepositAccounts<'_, 'info>,
+    proof: CompressedProof, 
+    ext_data_minified: ExtDataMinified, 
+    encrypted_output: Vec<u8>,
+    light_proof: ValidityProof,
+    nullifier0_address_tree_info: PackedAddressTreeInfo,
+    nullifier1_address_tree_info: PackedAddressTreeInfo,
+    output_state_tree_index: u8,
+    signer_seeds: &[&[&[u8]]],
+    authorize: impl FnOnce(u64, u64) -> Result<()>,
+) -> Result<()> {
+    let tree_account = &mut accounts.tree_account.load_mut()?;
+    let global_config = accounts.global_config;
+    let mint_config = accounts.mint_config;
+    let input_mint = accounts.input_mint;
   
     // Reconstruct full ExtData from minified version and context accounts
     let ext_data = ExtData::from_minified(
-        &ctx.accounts.reserve_token_account.key(),
-        &ctx.accounts.fee_recipient_account.key(),
+        accounts.reserve_token_account.key,
+        accounts.fee_recipient_account.key,
         ext_data_minified,
     );
 
-    // Check if proof.root is in the tree_account's proof history
-    require!(
-        MerkleTree::is_known_root(&tree_account, proof.root),
-        ErrorCode::UnknownRoot
-    );
+    // Check if proof.root is in the proof history of the active tree (or of the retired tree)
+    utils::require_known_root(
+        tree_account,
+        accounts.retired_tree_account,
+        proof.root,
+    )?;
 
     // Check if the ext_data hashes to the same ext_data in the proof
     let calculated_ext_data_hash = utils::calculate_complete_ext_data_hash(
@@ -106,8 +194,8 @@ pub fn handler<'info>(
         &encrypted_output,
         ext_data.fee,
         ext_data.fee_recipient,
-        ctx.accounts.input_mint.key(),
-        ctx.accounts.input_mint.key(),
+        input_mint.key(),
+        input_mint.key(),
     )?;
  
     require!(
@@ -120,7 +208,7 @@ pub fn handler<'info>(
         utils::check_public_amount(ext_data.ext_amount, ext_data.fee, proof.public_amount0),
         ErrorCode::InvalidPublicAmountData
     );
-    require!(proof.public_amount1 == [0; 32], ErrorCode::InvalidPublicAmountData);
+    require!(proof.public_amount1 == [0; 32], ErrorCode::DualTokenNotSupported);
     
     let ext_amount = ext_data.ext_amount;
     let fee = ext_data.fee;
@@ -129,36 +217,36 @@ pub fn handler<'info>(
     utils::validate_fee(
         ext_amount,
         fee,
-        global_config.deposit_fee_rate,
-        global_config.withdrawal_fee_rate,
+        mint_config.deposit_fee_rate(global_config),
+        mint_config.withdrawal_fee_rate(global_config),
         global_config.fee_error_margin,
     )?;
 
     // Verify the ZK proof
     msg!("verifying proof");
-    require!(
-        verify_compressed_proof(
-            proof.clone(), 
-            VERIFYING_KEY, 
-            ctx.accounts.input_mint.key(), 
-            ctx.accounts.input_mint.key()
-        ), 
-        ErrorCode::InvalidProof
-    );
+    utils::require_valid_proof(&*accounts.verifying_key.load()?, |verifying_key| {
+        verify_compressed_proof(proof.clone(), verifying_key, input_mint.key(), input_mint.key())
+    })?;
     msg!("proof verified");
     
     require!(ext_amount > 0, ErrorCode::InvalidExtAmount);
     let deposit_amount = ext_amount as u64;
 
     require!(
-        deposit_amount <= tree_account.max_deposit_amount,
+        deposit_amount >= mint_config.min_deposit_amount,
+        ErrorCode::DepositBelowMinimum
+    );
+    require!(
+        deposit_amount <= mint_config.max_deposit_amount,
         ErrorCode::DepositLimitExceeded
     );
 
+    authorize(deposit_amount, fee)?;
+
     // Create Light Protocol nullifier compressed accounts
     create_light_nullifiers(
-        ctx.accounts.user.as_ref(),
-        ctx.remaining_accounts,
+        &accounts.payer,
+        accounts.remaining_accounts,
         &proof.input_nullifiers,
         light_proof,
         nullifier0_address_tree_info,
@@ -167,30 +255,32 @@ pub fn handler<'info>(
     )?;
 
     // Get decimals from mint for transfer_checked
-    let decimals = ctx.accounts.input_mint.decimals;
+    let decimals = input_mint.decimals;
 
     // Transfer tokens from user to reserve using transfer_checked (Token-2022 compatible)
-    let transfer_ctx = CpiContext::new(
-        ctx.accounts.token_program.to_account_info(),
+    let transfer_ctx = CpiContext::new_with_signer(
+        accounts.token_program.clone(),
         TransferChecked {
-            from: ctx.accounts.user_token_account.to_account_info(),
-            mint: ctx.accounts.input_mint.to_account_info(),
-            to: ctx.accounts.reserve_token_account.to_account_info(),
-            authority: ctx.accounts.user.to_account_info(),
+            from: accounts.user_token_account.clone(),
+            mint: input_mint.to_account_info(),
+            to: accounts.reserve_token_account.clone(),
+            authority: accounts.authority.clone(),
         },
+        signer_seeds,
     );
     transfer_checked(transfer_ctx, deposit_amount, decimals)?;
     
     // Transfer fee if applicable
     if fee > 0 {
-        let transfer_ctx = CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
+        let transfer_ctx = CpiContext::new_with_signer(
+            accounts.token_program.clone(),
             TransferChecked {
-                from: ctx.accounts.user_token_account.to_account_info(),
-                mint: ctx.accounts.input_mint.to_account_info(),
-                to: ctx.accounts.fee_recipient_account.to_account_info(),
-                authority: ctx.accounts.user.to_account_info(),
+                from: accounts.user_token_account.clone(),
+                mint: input_mint.to_account_info(),
+                to: accounts.fee_recipient_account.clone(),
+                authority: accounts.authority.clone(),
             },
+            signer_seeds,
         );
         transfer_checked(transfer_ctx, fee, decimals)?;
     }
@@ -201,6 +291,8 @@ pub fn handler<'info>(
     MerkleTree::append::<Poseidon>(proof.output_commitments[1], tree_account)?;
 
     emit!(CommitmentData {
+        pool_id: global_config.pool_id,
+        tree_index: tree_account.tree_index,
         index: next_index_to_insert,
         commitment0: proof.output_commitments[0],
         commitment1: proof.output_commitments[1],
@@ -208,7 +300,7 @@ pub fn handler<'info>(
     });
 
     emit!(DepositEvent {
-        input_mint: ctx.accounts.input_mint.key(),
+        input_mint: input_mint.key(),
         amount: deposit_amount,
     });
 
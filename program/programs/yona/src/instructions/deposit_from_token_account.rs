@@ -0,0 +1,143 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::state::{MerkleTreeAccount, GlobalConfig, MintConfig, VerifyingKeyAccount, CIRCUIT_TRANSACTION2};
+use crate::types::{CompressedProof, ExtDataMinified};
+use crate::ErrorCode;
+use crate::instructions::deposit::{process_deposit, DepositAccounts};
+use light_sdk::instruction::{PackedAddressTreeInfo, ValidityProof};
+
+
+#[derive(Accounts)]
+#[instruction(
+    proof: CompressedProof, 
+    ext_data_minified: ExtDataMinified, 
+    encrypted_output: Vec<u8>,
+    light_proof: ValidityProof,
+    nullifier0_address_tree_info: PackedAddressTreeInfo,
+    nullifier1_address_tree_info: PackedAddressTreeInfo,
+    output_state_tree_index: u8
+)]
+pub struct DepositFromTokenAccount<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"merkle_tree",
+            global_config.pool_id.to_le_bytes().as_ref(),
+            global_config.active_tree_index.to_le_bytes().as_ref()
+        ],
+        bump = tree_account.load()?.bump
+    )]
+    pub tree_account: AccountLoader<'info, MerkleTreeAccount>,
+
+    /// Retired tree of the same pool, only needed when the proof root is from a rolled over tree
+    pub retired_tree_account: Option<AccountLoader<'info, MerkleTreeAccount>>,
+
+    #[account(
+        seeds = [b"global_config", global_config.pool_id.to_le_bytes().as_ref()],
+        bump = global_config.bump
+    )]
+    pub global_config: Box<Account<'info, GlobalConfig>>,
+
+    #[account(
+        seeds = [b"verifying_key", CIRCUIT_TRANSACTION2.to_le_bytes().as_ref()],
+        bump = verifying_key.load()?.bump
+    )]
+    pub verifying_key: AccountLoader<'info, VerifyingKeyAccount>,
+    
+    pub input_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"mint_config", global_config.key().as_ref(), input_mint.key().as_ref()],
+        bump = mint_config.bump,
+        constraint = mint_config.enabled @ ErrorCode::UnsupportedMintAddress
+    )]
+    pub mint_config: Box<Account<'info, MintConfig>>,
+
+    #[account(mut,
+        associated_token::mint = input_mint,  
+        associated_token::authority = global_config,
+        associated_token::token_program = token_program,
+    )]
+    pub reserve_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: user should be able to send fees to any types of accounts
+    pub fee_recipient_account: UncheckedAccount<'info>,
+
+    /// Any token account of the mint (multisig vault, program-owned or non-associated account),
+    /// `authority` must be its owner or its delegate
+    #[account(mut,
+        token::mint = input_mint,
+        token::token_program = token_program,
+    )]
+    pub user_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Owner or delegate of `user_token_account`, may be a PDA signing through CPI
+    pub authority: Signer<'info>,
+
+    /// Pays the Light Protocol nullifier accounts, so `authority` doesn't need to hold lamports
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+    /// Token program - supports both Token and Token-2022 programs
+    pub token_program: Interface<'info, TokenInterface>,
+    // Remaining accounts for Light Protocol:
+    // [light_system_program, registered_program_pda, account_compression_authority, 
+    //  account_compression_program, system_program, address_tree, address_queue, output_state_tree, ...]
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, DepositFromTokenAccount<'info>>, 
+    proof: CompressedProof, 
+    ext_data_minified: ExtDataMinified, 
+    encrypted_output: Vec<u8>,
+    light_proof: ValidityProof,
+    nullifier0_address_tree_info: PackedAddressTreeInfo,
+    nullifier1_address_tree_info: PackedAddressTreeInfo,
+    output_state_tree_index: u8,
+) -> Result<()> {
+    let user_token_account = &ctx.accounts.user_token_account;
+    let authority = ctx.accounts.authority.key();
+
+    // The authority signs the transfers as owner or delegate of the source account, the payer
+    // pays the nullifiers
+    process_deposit(
+        DepositAccounts {
+            tree_account: &ctx.accounts.tree_account,
+            retired_tree_account: ctx.accounts.retired_tree_account.as_ref(),
+            global_config: &ctx.accounts.global_config,
+            verifying_key: &ctx.accounts.verifying_key,
+            input_mint: &ctx.accounts.input_mint,
+            mint_config: &ctx.accounts.mint_config,
+            reserve_token_account: ctx.accounts.reserve_token_account.to_account_info(),
+            fee_recipient_account: ctx.accounts.fee_recipient_account.to_account_info(),
+            user_token_account: user_token_account.to_account_info(),
+            authority: ctx.accounts.authority.to_account_info(),
+            payer: ctx.accounts.payer.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            remaining_accounts: ctx.remaining_accounts,
+        },
+        proof,
+        ext_data_minified,
+        encrypted_output,
+        light_proof,
+        nullifier0_address_tree_info,
+        nullifier1_address_tree_info,
+        output_state_tree_index,
+        &[],
+        |deposit_amount, fee| {
+            // The authority must own the source account or be delegated the deposit amount plus fee
+            let total_amount = deposit_amount.checked_add(fee).ok_or(ErrorCode::ArithmeticOverflow)?;
+            require!(
+                user_token_account.owner == authority
+                    || (user_token_account.delegate == COption::Some(authority)
+                        && user_token_account.delegated_amount >= total_amount),
+                ErrorCode::InvalidTokenAccountAuthority
+            );
+            Ok(())
+        },
+    )
+}
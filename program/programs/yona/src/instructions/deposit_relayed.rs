@@ -0,0 +1,181 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::state::{MerkleTreeAccount, GlobalConfig, MintConfig, RelayerAccount, VerifyingKeyAccount, CIRCUIT_TRANSACTION2};
+use crate::types::{CompressedProof, ExtDataMinified};
+use crate::ErrorCode;
+use crate::instructions::deposit::{process_deposit, DepositAccounts};
+use light_sdk::instruction::{PackedAddressTreeInfo, ValidityProof};
+
+
+#[derive(Accounts)]
+#[instruction(
+    proof: CompressedProof, 
+    ext_data_minified: ExtDataMinified, 
+    encrypted_output: Vec<u8>,
+    light_proof: ValidityProof,
+    nullifier0_address_tree_info: PackedAddressTreeInfo,
+    nullifier1_address_tree_info: PackedAddressTreeInfo,
+    output_state_tree_index: u8
+)]
+pub struct DepositRelayed<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"merkle_tree",
+            global_config.pool_id.to_le_bytes().as_ref(),
+            global_config.active_tree_index.to_le_bytes().as_ref()
+        ],
+        bump = tree_account.load()?.bump
+    )]
+    pub tree_account: AccountLoader<'info, MerkleTreeAccount>,
+
+    /// Retired tree of the same pool, only needed when the proof root is from a rolled over tree
+    pub retired_tree_account: Option<AccountLoader<'info, MerkleTreeAccount>>,
+
+    #[account(
+        seeds = [b"global_config", global_config.pool_id.to_le_bytes().as_ref()],
+        bump = global_config.bump
+    )]
+    pub global_config: Box<Account<'info, GlobalConfig>>,
+
+    #[account(
+        seeds = [b"verifying_key", CIRCUIT_TRANSACTION2.to_le_bytes().as_ref()],
+        bump = verifying_key.load()?.bump
+    )]
+    pub verifying_key: AccountLoader<'info, VerifyingKeyAccount>,
+    
+    pub input_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"mint_config", global_config.key().as_ref(), input_mint.key().as_ref()],
+        bump = mint_config.bump,
+        constraint = mint_config.enabled @ ErrorCode::UnsupportedMintAddress
+    )]
+    pub mint_config: Box<Account<'info, MintConfig>>,
+
+    #[account(mut,
+        associated_token::mint = input_mint,  
+        associated_token::authority = global_config,
+        associated_token::token_program = token_program,
+    )]
+    pub reserve_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Fee token account of the relayer, must be owned by its registered fee recipient
+    #[account(
+        mut,
+        token::mint = input_mint,
+        token::token_program = token_program,
+        constraint = fee_recipient_account.owner == relayer_account.fee_recipient @ ErrorCode::InvalidFeeRecipient
+    )]
+    pub fee_recipient_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Depositor's token account, the depositor approved `deposit_authority` for the deposit
+    /// amount plus fee and doesn't sign this instruction
+    #[account(mut,
+        token::mint = input_mint,
+        token::token_program = token_program,
+    )]
+    pub user_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: PDA delegate of the depositor's tokens, bound to the first output commitment so an
+    /// approval can only fund the deposit it was given for
+    #[account(
+        seeds = [b"deposit_authority", proof.output_commitments[0].as_ref()],
+        bump
+    )]
+    pub deposit_authority: UncheckedAccount<'info>,
+
+    /// Pays the transaction and the Light Protocol nullifier accounts
+    #[account(mut)]
+    pub relayer: Signer<'info>,
+
+    /// Registry entry of the relayer, deposits can only be relayed by bonded relayers
+    #[account(
+        seeds = [b"relayer", global_config.key().as_ref(), relayer.key().as_ref()],
+        bump = relayer_account.bump,
+        constraint = relayer_account.bond >= global_config.min_relayer_bond @ ErrorCode::RelayerBondTooLow,
+        constraint = !relayer_account.is_deregistering() @ ErrorCode::RelayerDeregistering
+    )]
+    pub relayer_account: Box<Account<'info, RelayerAccount>>,
+    
+    pub system_program: Program<'info, System>,
+    /// Token program - supports both Token and Token-2022 programs
+    pub token_program: Interface<'info, TokenInterface>,
+    // Remaining accounts for Light Protocol:
+    // [light_system_program, registered_program_pda, account_compression_authority, 
+    //  account_compression_program, system_program, address_tree, address_queue, output_state_tree, ...]
+}
+
+/**
+ * Deposit paid by a relayer. The depositor only approves `deposit_authority` for
+ * `ext_amount + fee` on its token account (`approve_checked`, which can be sent in the same
+ * transaction with the relayer as fee payer), so wallets without SOL can shield tokens.
+ */
+#[allow(clippy::too_many_arguments)]
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, DepositRelayed<'info>>, 
+    proof: CompressedProof, 
+    ext_data_minified: ExtDataMinified, 
+    encrypted_output: Vec<u8>,
+    light_proof: ValidityProof,
+    nullifier0_address_tree_info: PackedAddressTreeInfo,
+    nullifier1_address_tree_info: PackedAddressTreeInfo,
+    output_state_tree_index: u8,
+) -> Result<()> {
+    let output_commitment = proof.output_commitments[0];
+    let deposit_authority_seeds = &[
+        b"deposit_authority".as_ref(),
+        output_commitment.as_ref(),
+        &[ctx.bumps.deposit_authority],
+    ];
+    let signer_seeds = &[&deposit_authority_seeds[..]];
+    let relayer_account = &ctx.accounts.relayer_account;
+    let user_token_account = &ctx.accounts.user_token_account;
+    let deposit_authority = ctx.accounts.deposit_authority.key();
+
+    // The deposit authority transfers the depositor's tokens as its delegate, the relayer pays
+    // the nullifiers
+    process_deposit(
+        DepositAccounts {
+            tree_account: &ctx.accounts.tree_account,
+            retired_tree_account: ctx.accounts.retired_tree_account.as_ref(),
+            global_config: &ctx.accounts.global_config,
+            verifying_key: &ctx.accounts.verifying_key,
+            input_mint: &ctx.accounts.input_mint,
+            mint_config: &ctx.accounts.mint_config,
+            reserve_token_account: ctx.accounts.reserve_token_account.to_account_info(),
+            fee_recipient_account: ctx.accounts.fee_recipient_account.to_account_info(),
+            user_token_account: user_token_account.to_account_info(),
+            authority: ctx.accounts.deposit_authority.to_account_info(),
+            payer: ctx.accounts.relayer.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            remaining_accounts: ctx.remaining_accounts,
+        },
+        proof,
+        ext_data_minified,
+        encrypted_output,
+        light_proof,
+        nullifier0_address_tree_info,
+        nullifier1_address_tree_info,
+        output_state_tree_index,
+        signer_seeds,
+        |deposit_amount, fee| {
+            // The pool rates are a floor, the rate advertised by the relayer caps the fee
+            require!(
+                fee <= relayer_account.max_fee(deposit_amount)?,
+                ErrorCode::RelayerFeeTooHigh
+            );
+
+            // The depositor must have approved the deposit authority for the deposit amount plus fee
+            let total_amount = deposit_amount.checked_add(fee).ok_or(ErrorCode::ArithmeticOverflow)?;
+            require!(
+                user_token_account.delegate == COption::Some(deposit_authority)
+                    && user_token_account.delegated_amount >= total_amount,
+                ErrorCode::DepositNotApproved
+            );
+            Ok(())
+        },
+    )
+}
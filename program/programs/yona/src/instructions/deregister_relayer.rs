@@ -0,0 +1,22 @@
+use anchor_lang::prelude::*;
+use crate::ErrorCode;
+
+/**
+ * Remove a relayer from the registry. The account is closed to the relayer, which gets back
+ * its bond and rent, once the unbonding period started by `request_relayer_deregistration`
+ * has passed.
+ */
+pub fn handler(ctx: Context<crate::DeregisterRelayer>) -> Result<()> {
+    let relayer_account = &ctx.accounts.relayer_account;
+    require!(
+        relayer_account.is_deregistering()
+            && Clock::get()?.unix_timestamp >= relayer_account.unbonded_at,
+        ErrorCode::RelayerBondUnbonding
+    );
+
+    msg!(
+        "Relayer deregistered: {}, bond returned: {} lamports",
+        ctx.accounts.authority.key(), relayer_account.bond
+    );
+    Ok(())
+}
@@ -1,37 +1,88 @@
 use anchor_lang::prelude::*;
 use crate::merkle_tree::MerkleTree;
-use crate::state::MERKLE_TREE_HEIGHT;
+use crate::state::{
+    GlobalConfig, MerkleTreeAccount, TreeTokenAccount,
+    DEFAULT_POOL_ID, INITIAL_TREE_INDEX, MERKLE_TREE_HEIGHT, MAX_ROOT_HISTORY_SIZE,
+};
 use crate::ADMIN_PUBKEY;
 use crate::ErrorCode;
 use light_hasher::Poseidon;
 
-pub fn handler(ctx: Context<crate::Initialize>) -> Result<()> { 
+pub fn handler(ctx: Context<crate::Initialize>, height: u8, root_history_size: u16) -> Result<()> { 
+    initialize_pool_accounts(
+        DEFAULT_POOL_ID,
+        height,
+        root_history_size,
+        ctx.accounts.authority.key(),
+        &ctx.accounts.tree_account,
+        ctx.bumps.tree_account,
+        &mut ctx.accounts.tree_token_account,
+        ctx.bumps.tree_token_account,
+        &mut ctx.accounts.global_config,
+        ctx.bumps.global_config,
+    )
+}
+
+/**
+ * Initializes the merkle tree, tree token and global config accounts of a pool.
+ * Shared by `initialize` (default pool) and `initialize_pool`.
+ */
+#[allow(clippy::too_many_arguments)]
+pub fn initialize_pool_accounts<'info>(
+    pool_id: u16,
+    height: u8,
+    root_history_size: u16,
+    authority: Pubkey,
+    tree_account: &AccountLoader<'info, MerkleTreeAccount>,
+    tree_account_bump: u8,
+    tree_token_account: &mut TreeTokenAccount,
+    tree_token_account_bump: u8,
+    global_config: &mut GlobalConfig,
+    global_config_bump: u8,
+) -> Result<()> {
     if let Some(admin_key) = ADMIN_PUBKEY {
-        require!(ctx.accounts.authority.key().eq(&admin_key), ErrorCode::Unauthorized);
+        require!(authority.eq(&admin_key), ErrorCode::Unauthorized);
     } // TODO: remove admin key for testing
+
+    // Proofs are generated for the circuit's levels: a shorter tree only caps the number of
+    // leaves, its root is still hashed up to MERKLE_TREE_HEIGHT
+    require!(
+        height > 0 && height <= MERKLE_TREE_HEIGHT,
+        ErrorCode::InvalidTreeHeight
+    );
+    require!(
+        root_history_size > 0 && root_history_size as usize <= MAX_ROOT_HISTORY_SIZE,
+        ErrorCode::InvalidRootHistorySize
+    );
     
-    let tree_account = &mut ctx.accounts.tree_account.load_init()?;
-    tree_account.authority = ctx.accounts.authority.key();
+    let tree_account = &mut tree_account.load_init()?;
+    tree_account.authority = authority;
     tree_account.next_index = 0;
     tree_account.root_index = 0;
-    tree_account.bump = ctx.bumps.tree_account;
-    tree_account.max_deposit_amount = 1_000_000_000_000; // 1000 SOL default limit
-    tree_account.height = MERKLE_TREE_HEIGHT; // Hardcoded height
-    tree_account.root_history_size = 100; // Hardcoded root history size
+    tree_account.bump = tree_account_bump;
+    tree_account.pool_id = pool_id;
+    tree_account.tree_index = INITIAL_TREE_INDEX;
+    tree_account.height = height;
+    tree_account.root_history_size = root_history_size;
 
     MerkleTree::initialize::<Poseidon>(tree_account)?;
+
+    tree_token_account.authority = authority;
+    tree_token_account.pool_id = pool_id;
+    tree_token_account.bump = tree_token_account_bump;
     
     // Initialize global config
-    let global_config = &mut ctx.accounts.global_config;
-    global_config.authority = ctx.accounts.authority.key();
+    global_config.authority = authority;
+    global_config.pool_id = pool_id;
+    global_config.active_tree_index = INITIAL_TREE_INDEX;
     global_config.deposit_fee_rate = 0; // 0% - Free deposits
     global_config.withdrawal_fee_rate = 30; // 0.3% (30 basis points)
     global_config.fee_error_margin = 500; // 5% (500 basis points)
-    global_config.bump = ctx.bumps.global_config;
+    global_config.min_relayer_bond = 0; // Anyone can register as a relayer
+    global_config.bump = global_config_bump;
     
-    msg!("Sparse Merkle Tree initialized successfully with height: {}, root history size: {}, deposit limit: {} lamports, 
+    msg!("Sparse Merkle Tree initialized successfully for pool: {}, height: {}, root history size: {}, 
         deposit fee rate: {}, withdrawal fee rate: {}, fee error margin: {}",
-        MERKLE_TREE_HEIGHT, 100, tree_account.max_deposit_amount, global_config.deposit_fee_rate, global_config.withdrawal_fee_rate, global_config.fee_error_margin);
+        pool_id, height, root_history_size, global_config.deposit_fee_rate, global_config.withdrawal_fee_rate, global_config.fee_error_margin);
     Ok(())
 }
-
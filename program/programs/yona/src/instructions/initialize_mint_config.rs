@@ -0,0 +1,16 @@
+use anchor_lang::prelude::*;
+use crate::types::MintConfigParams;
+
+/**
+ * Allow a mint in the pool. Only the authority can call this.
+ */
+pub fn handler(ctx: Context<crate::InitializeMintConfig>, params: MintConfigParams) -> Result<()> {
+    let mint_config = &mut ctx.accounts.mint_config;
+    mint_config.global_config = ctx.accounts.global_config.key();
+    mint_config.mint = ctx.accounts.mint.key();
+    mint_config.bump = ctx.bumps.mint_config;
+    mint_config.apply(params)?;
+
+    msg!("Mint config initialized for mint: {}", mint_config.mint);
+    Ok(())
+}
@@ -0,0 +1,26 @@
+use anchor_lang::prelude::*;
+use crate::instructions::initialize::initialize_pool_accounts;
+
+/**
+ * Create an additional pool with its own merkle tree, global config and reserves.
+ * Pools are fully independent: notes, roots and fees of one pool are never accepted by another.
+ */
+pub fn handler(
+    ctx: Context<crate::InitializePool>,
+    pool_id: u16,
+    height: u8,
+    root_history_size: u16,
+) -> Result<()> {
+    initialize_pool_accounts(
+        pool_id,
+        height,
+        root_history_size,
+        ctx.accounts.authority.key(),
+        &ctx.accounts.tree_account,
+        ctx.bumps.tree_account,
+        &mut ctx.accounts.tree_token_account,
+        ctx.bumps.tree_token_account,
+        &mut ctx.accounts.global_config,
+        ctx.bumps.global_config,
+    )
+}
@@ -0,0 +1,36 @@
+use anchor_lang::prelude::*;
+use crate::state::circuit_public_inputs;
+use crate::ADMIN_PUBKEY;
+use crate::ErrorCode;
+
+/**
+ * Creates the verifying key account of a circuit with empty key slots.
+ * The first key goes through stage / write / activate like any upgrade.
+ * There is one account per circuit for the whole program, shared by every pool.
+ */
+pub fn handler(
+    ctx: Context<crate::InitializeVerifyingKey>,
+    circuit: u8,
+    timelock_seconds: i64,
+    migration_window_seconds: i64,
+) -> Result<()> {
+    if let Some(admin_key) = ADMIN_PUBKEY {
+        require!(ctx.accounts.authority.key().eq(&admin_key), ErrorCode::Unauthorized);
+    }
+    require!(circuit_public_inputs(circuit).is_some(), ErrorCode::InvalidVerifyingKey);
+    require!(
+        timelock_seconds >= 0 && migration_window_seconds >= 0,
+        ErrorCode::InvalidVerifyingKey
+    );
+
+    let verifying_key = &mut ctx.accounts.verifying_key.load_init()?;
+    verifying_key.authority = ctx.accounts.authority.key();
+    verifying_key.circuit = circuit;
+    verifying_key.timelock_seconds = timelock_seconds;
+    verifying_key.migration_window_seconds = migration_window_seconds;
+    verifying_key.bump = ctx.bumps.verifying_key;
+
+    msg!("Verifying key account initialized for circuit: {}, timelock: {}s, migration window: {}s",
+        circuit, timelock_seconds, migration_window_seconds);
+    Ok(())
+}
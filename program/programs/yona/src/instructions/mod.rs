@@ -1,13 +1,36 @@
 pub mod initialize;
+pub mod initialize_pool;
 pub mod deposit;
+pub mod deposit_relayed;
+pub mod deposit_from_token_account;
+pub mod initialize_mint_config;
+pub mod update_mint_config;
 pub mod update_deposit_limit;
 pub mod update_global_config;
+pub mod register_relayer;
+pub mod update_relayer;
+pub mod request_relayer_deregistration;
+pub mod deregister_relayer;
+pub mod slash_relayer;
+pub mod set_aggregator_program;
+pub mod initialize_verifying_key;
+pub mod stage_verifying_key;
+pub mod write_verifying_key;
+pub mod activate_verifying_key;
 pub mod swap;
+pub mod rollover_tree;
+pub mod transact;
+pub mod transact_dual;
+pub mod transact4;
 pub mod withdraw;   
 
-pub use initialize::*;
+// Every module exposes a `handler`, the globs are for the account structs
+#[allow(ambiguous_glob_reexports)]
 pub use deposit::*;
-pub use update_deposit_limit::*;
-pub use update_global_config::*;
+pub use deposit_relayed::*;
+pub use deposit_from_token_account::*;
 pub use swap::*;
+pub use transact::*;
+pub use transact_dual::*;
+pub use transact4::*;
 pub use withdraw::*;
\ No newline at end of file
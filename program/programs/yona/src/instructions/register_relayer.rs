@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use crate::ErrorCode;
+
+/**
+ * Register the signer as a relayer of the pool. Permissionless, the signer bonds at least
+ * `GlobalConfig.min_relayer_bond` lamports and advertises its fee rate and fee recipient.
+ */
+pub fn handler(
+    ctx: Context<crate::RegisterRelayer>,
+    fee_rate: u16,
+    fee_recipient: Pubkey,
+    bond: u64,
+) -> Result<()> {
+    require!(fee_rate <= 10000, ErrorCode::InvalidFeeRate);
+    require!(
+        bond >= ctx.accounts.global_config.min_relayer_bond,
+        ErrorCode::RelayerBondTooLow
+    );
+
+    if bond > 0 {
+        let bond_transfer_ctx = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: ctx.accounts.authority.to_account_info(),
+                to: ctx.accounts.relayer_account.to_account_info(),
+            },
+        );
+        system_program::transfer(bond_transfer_ctx, bond)?;
+    }
+
+    let relayer_account = &mut ctx.accounts.relayer_account;
+    relayer_account.global_config = ctx.accounts.global_config.key();
+    relayer_account.authority = ctx.accounts.authority.key();
+    relayer_account.fee_recipient = fee_recipient;
+    relayer_account.fee_rate = fee_rate;
+    relayer_account.bond = bond;
+    relayer_account.bump = ctx.bumps.relayer_account;
+    relayer_account.unbonded_at = 0;
+
+    msg!(
+        "Relayer registered: {}, fee rate: {} basis points, fee recipient: {}, bond: {} lamports",
+        relayer_account.authority, fee_rate, fee_recipient, bond
+    );
+    Ok(())
+}
@@ -0,0 +1,22 @@
+use anchor_lang::prelude::*;
+use crate::state::RELAYER_UNBONDING_SECONDS;
+use crate::ErrorCode;
+
+/**
+ * Stop relaying and start unbonding. The relayer can no longer relay transactions, its bond is
+ * returned by `deregister_relayer` once `RELAYER_UNBONDING_SECONDS` have passed.
+ */
+pub fn handler(ctx: Context<crate::RequestRelayerDeregistration>) -> Result<()> {
+    let relayer_account = &mut ctx.accounts.relayer_account;
+    require!(!relayer_account.is_deregistering(), ErrorCode::RelayerDeregistering);
+
+    relayer_account.unbonded_at = Clock::get()?.unix_timestamp
+        .checked_add(RELAYER_UNBONDING_SECONDS)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+    msg!(
+        "Relayer deregistration requested: {}, bond unlocked at: {}",
+        relayer_account.authority, relayer_account.unbonded_at
+    );
+    Ok(())
+}
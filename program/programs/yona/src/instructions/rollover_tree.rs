@@ -0,0 +1,53 @@
+use anchor_lang::prelude::*;
+use light_hasher::Poseidon;
+
+use crate::merkle_tree::MerkleTree;
+use crate::types::TreeRolloverEvent;
+use crate::ErrorCode;
+
+/**
+ * Create the successor of the active tree and route new commitments to it.
+ * 
+ * Permissionless once the active tree can't fit another pair of commitments, otherwise only
+ * the pool authority can call it. The retired tree is left untouched: its roots are still
+ * accepted through the `retired_tree_account` of deposit/withdraw/swap.
+ */
+pub fn handler(ctx: Context<crate::RolloverTree>) -> Result<()> {
+    let tree_account = ctx.accounts.tree_account.load()?;
+    let global_config = &mut ctx.accounts.global_config;
+
+    require!(
+        MerkleTree::is_full(&tree_account) || ctx.accounts.payer.key() == global_config.authority,
+        ErrorCode::TreeNotFull
+    );
+
+    let previous_tree_index = global_config.active_tree_index;
+    let tree_index = previous_tree_index
+        .checked_add(1)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+    let new_tree_account = &mut ctx.accounts.new_tree_account.load_init()?;
+    new_tree_account.authority = tree_account.authority;
+    new_tree_account.next_index = 0;
+    new_tree_account.root_index = 0;
+    new_tree_account.bump = ctx.bumps.new_tree_account;
+    new_tree_account.pool_id = tree_account.pool_id;
+    new_tree_account.tree_index = tree_index;
+    new_tree_account.height = tree_account.height;
+    new_tree_account.root_history_size = tree_account.root_history_size;
+
+    MerkleTree::initialize::<Poseidon>(new_tree_account)?;
+
+    global_config.active_tree_index = tree_index;
+
+    emit!(TreeRolloverEvent {
+        pool_id: global_config.pool_id,
+        previous_tree_index,
+        tree_index,
+        tree_account: ctx.accounts.new_tree_account.key(),
+    });
+
+    msg!("Tree {} of pool {} retired, new commitments go to tree {}",
+        previous_tree_index, global_config.pool_id, tree_index);
+    Ok(())
+}
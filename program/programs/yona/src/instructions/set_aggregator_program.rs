@@ -0,0 +1,20 @@
+use anchor_lang::prelude::*;
+
+/**
+ * Allow or disallow the Jupiter aggregator as the CPI target of `swap`. Only the authority can
+ * call this, the config is created the first time the program is set.
+ */
+pub fn handler(ctx: Context<crate::SetAggregatorProgram>, enabled: bool) -> Result<()> {
+    let aggregator_config = &mut ctx.accounts.aggregator_config;
+    aggregator_config.global_config = ctx.accounts.global_config.key();
+    aggregator_config.program_id = ctx.accounts.aggregator_program.key();
+    aggregator_config.enabled = enabled;
+    aggregator_config.bump = ctx.bumps.aggregator_config;
+
+    msg!(
+        "Aggregator program {} {}",
+        aggregator_config.program_id,
+        if enabled { "allowed" } else { "disallowed" }
+    );
+    Ok(())
+}
@@ -0,0 +1,25 @@
+use anchor_lang::prelude::*;
+use crate::ErrorCode;
+
+/**
+ * Slash `amount` lamports of a relayer's bond to `destination`, e.g. for censoring or
+ * front-running the transactions sent to it. Only the pool authority can call this, also while the relayer
+ * is unbonding. A relayer slashed below `GlobalConfig.min_relayer_bond` cannot relay until it
+ * tops its bond up.
+ */
+pub fn handler(ctx: Context<crate::SlashRelayer>, amount: u64) -> Result<()> {
+    let relayer_account = &mut ctx.accounts.relayer_account;
+    relayer_account.bond = relayer_account.bond
+        .checked_sub(amount)
+        .ok_or(ErrorCode::SlashExceedsRelayerBond)?;
+
+    // The bond is held as lamports of the relayer account, on top of its rent
+    relayer_account.sub_lamports(amount)?;
+    ctx.accounts.destination.add_lamports(amount)?;
+
+    msg!(
+        "Relayer slashed: {}, slashed: {} lamports, remaining bond: {} lamports",
+        relayer_account.authority, amount, relayer_account.bond
+    );
+    Ok(())
+}
@@ -0,0 +1,28 @@
+use anchor_lang::prelude::*;
+use crate::state::{circuit_public_inputs, StoredVerifyingKey};
+use crate::ErrorCode;
+
+/**
+ * Starts a key upgrade: clears the pending slot and sets its version.
+ * The key bytes are then uploaded with `write_verifying_key`, `key_hash` is the sha256
+ * of those bytes and is checked on activation.
+ */
+pub fn handler(ctx: Context<crate::UpdateVerifyingKey>, version: u32, key_hash: [u8; 32]) -> Result<()> {
+    let verifying_key = &mut ctx.accounts.verifying_key.load_mut()?;
+    require!(version > verifying_key.current.version, ErrorCode::InvalidVerifyingKeyVersion);
+
+    let nr_pubinputs = circuit_public_inputs(verifying_key.circuit)
+        .ok_or(ErrorCode::InvalidVerifyingKey)?;
+    verifying_key.pending = StoredVerifyingKey {
+        version,
+        nr_pubinputs,
+        ..StoredVerifyingKey::EMPTY
+    };
+    verifying_key.pending_key_hash = key_hash;
+    verifying_key.pending_activation_ts = Clock::get()?.unix_timestamp
+        .checked_add(verifying_key.timelock_seconds)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+    msg!("Verifying key version {} staged for circuit: {}", version, verifying_key.circuit);
+    Ok(())
+}
@@ -5,17 +5,20 @@ use light_hasher::Poseidon;
 use anchor_spl::token_interface::{
     Mint, TokenAccount, TokenInterface,
     transfer_checked, TransferChecked,
+    approve_checked, ApproveChecked,
+    revoke, Revoke,
 };
 use anchor_lang::solana_program::program::invoke_signed;
 use anchor_lang::solana_program::instruction::Instruction;
 
 use crate::merkle_tree::MerkleTree;
-use crate::state::{MerkleTreeAccount, GlobalConfig};
+use crate::state::{AggregatorConfig, MerkleTreeAccount, GlobalConfig, MintConfig, RelayerAccount, VerifyingKeyAccount, CIRCUIT_TRANSACTION2};
 use crate::types::{CompressedProof, SwapExtData, SwapExtDataMinified, CommitmentData, SwapEvent};
 use crate::ErrorCode;
-use crate::utils::{verify_compressed_proof, VERIFYING_KEY};
+use crate::utils::verify_compressed_proof;
 use crate::utils;
 use crate::light::create_light_nullifiers;
+use crate::jupiter::{require_reserves_not_decreased, reserve_balances, validate_route, ExpectedRoute};
 use light_sdk::instruction::{PackedAddressTreeInfo, ValidityProof};
 
 // Number of Light Protocol accounts in remaining_accounts:
@@ -41,20 +44,48 @@ const NUM_LIGHT_ACCOUNTS: usize = 12;
 pub struct Swap<'info> {
     #[account(
         mut,
-        seeds = [b"merkle_tree"],
+        seeds = [
+            b"merkle_tree",
+            global_config.pool_id.to_le_bytes().as_ref(),
+            global_config.active_tree_index.to_le_bytes().as_ref()
+        ],
         bump = tree_account.load()?.bump
     )]
     pub tree_account: AccountLoader<'info, MerkleTreeAccount>,
 
+    /// Retired tree of the same pool, only needed when the proof root is from a rolled over tree
+    pub retired_tree_account: Option<AccountLoader<'info, MerkleTreeAccount>>,
+
     #[account(
-        seeds = [b"global_config"],
-        bump
+        seeds = [b"global_config", global_config.pool_id.to_le_bytes().as_ref()],
+        bump = global_config.bump
     )]
     pub global_config: Box<Account<'info, GlobalConfig>>,
+
+    #[account(
+        seeds = [b"verifying_key", CIRCUIT_TRANSACTION2.to_le_bytes().as_ref()],
+        bump = verifying_key.load()?.bump
+    )]
+    pub verifying_key: AccountLoader<'info, VerifyingKeyAccount>,
     
     pub input_mint: Box<InterfaceAccount<'info, Mint>>,
     pub output_mint: Box<InterfaceAccount<'info, Mint>>,
 
+    /// Swapping out of a disabled mint is allowed, like withdrawing from it
+    #[account(
+        seeds = [b"mint_config", global_config.key().as_ref(), input_mint.key().as_ref()],
+        bump = input_mint_config.bump
+    )]
+    pub input_mint_config: Box<Account<'info, MintConfig>>,
+
+    /// New notes can only be created in enabled mints
+    #[account(
+        seeds = [b"mint_config", global_config.key().as_ref(), output_mint.key().as_ref()],
+        bump = output_mint_config.bump,
+        constraint = output_mint_config.enabled @ ErrorCode::UnsupportedMintAddress
+    )]
+    pub output_mint_config: Box<Account<'info, MintConfig>>,
+
     #[account(mut,
         associated_token::mint = input_mint,  
         associated_token::authority = global_config,
@@ -69,16 +100,48 @@ pub struct Swap<'info> {
     )]
     pub reserve_token_account_output: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    #[account(mut)]
-    /// CHECK: user should be able to send fees to any types of accounts
-    pub fee_recipient_account: UncheckedAccount<'info>,
+    /// Output mint token account of the relayer, must be owned by its registered fee recipient.
+    /// The relayer is paid the swap output above `ext_min_amount_out`, which must be within its fee rate
+    #[account(
+        mut,
+        token::mint = output_mint,
+        token::token_program = output_token_program,
+        constraint = fee_recipient_account.owner == relayer_account.fee_recipient @ ErrorCode::InvalidFeeRecipient
+    )]
+    pub fee_recipient_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
     /// Jupiter aggregator program
-    /// CHECK: Jupiter program ID
+    /// CHECK: must be allowed by `aggregator_config`
     pub jupiter_program: UncheckedAccount<'info>,
 
+    #[account(
+        seeds = [b"aggregator_config", global_config.key().as_ref(), jupiter_program.key().as_ref()],
+        bump = aggregator_config.bump,
+        constraint = aggregator_config.enabled @ ErrorCode::AggregatorNotAllowed
+    )]
+    pub aggregator_config: Box<Account<'info, AggregatorConfig>>,
+
+    /// CHECK: signs the Jupiter CPI, delegated exactly the swap input on the input reserve only.
+    /// The global config, authority of every reserve, never signs the route.
+    #[account(
+        seeds = [b"swap_authority", global_config.key().as_ref()],
+        bump
+    )]
+    pub swap_authority: UncheckedAccount<'info>,
+
+    /// Submits the swap and pays the Light Protocol nullifier accounts, so the user's wallet
+    /// never signs
     #[account(mut)]
-    pub user: Signer<'info>,
+    pub relayer: Signer<'info>,
+
+    /// Registry entry of the relayer, swaps can only be relayed by bonded relayers
+    #[account(
+        seeds = [b"relayer", global_config.key().as_ref(), relayer.key().as_ref()],
+        bump = relayer_account.bump,
+        constraint = relayer_account.bond >= global_config.min_relayer_bond @ ErrorCode::RelayerBondTooLow,
+        constraint = !relayer_account.is_deregistering() @ ErrorCode::RelayerDeregistering
+    )]
+    pub relayer_account: Box<Account<'info, RelayerAccount>>,
     
     pub system_program: Program<'info, System>,
     /// Token program for input mint - supports both Token and Token-2022 programs
@@ -103,6 +166,7 @@ pub struct Swap<'info> {
  * 
  * Reentrant attacks are not possible, because nullifier creation is checked by Light Protocol.
  */
+#[allow(clippy::too_many_arguments)]
 pub fn handler<'info>(
     ctx: Context<'_, '_, '_, 'info, Swap<'info>>, 
     proof: CompressedProof, 
@@ -117,17 +181,32 @@ pub fn handler<'info>(
     let tree_account = &mut ctx.accounts.tree_account.load_mut()?;
     let global_config = &ctx.accounts.global_config;
 
+    // Split remaining accounts: first NUM_LIGHT_ACCOUNTS are for Light Protocol, rest are for Jupiter
+    let (light_accounts, jupiter_accounts) = ctx
+        .remaining_accounts
+        .split_at_checked(NUM_LIGHT_ACCOUNTS)
+        .ok_or(ErrorCode::NotEnoughLightAccounts)?;
+
+    // The route is bound in the ext data hash, so the prover fixes the execution path
+    let route_accounts: Vec<(Pubkey, bool)> = jupiter_accounts
+        .iter()
+        .map(|account| (*account.key, account.is_writable))
+        .collect();
+    let route_hash = utils::calculate_route_hash(&jupiter_swap_data, &route_accounts)?;
+
     // Reconstruct full SwapExtData from minified version and context accounts
     let ext_data = SwapExtData::from_minified(
         &ctx.accounts.fee_recipient_account.key(),
+        route_hash,
         ext_data_minified,
     );
 
-    // Check if proof.root is in the tree_account's proof history
-    require!(
-        MerkleTree::is_known_root(&tree_account, proof.root),
-        ErrorCode::UnknownRoot
-    );
+    // Check if proof.root is in the proof history of the active tree (or of the retired tree)
+    utils::require_known_root(
+        tree_account,
+        ctx.accounts.retired_tree_account.as_ref(),
+        proof.root,
+    )?;
 
 
     // Check if the ext_data hashes to the same ext_data in the proof
@@ -139,6 +218,7 @@ pub fn handler<'info>(
         ext_data.fee_recipient,
         ctx.accounts.input_mint.key(),
         ctx.accounts.output_mint.key(),
+        ext_data.route_hash,
     )?;
     require!(
         Fr::from_le_bytes_mod_order(&calculated_ext_data_hash) == Fr::from_be_bytes_mod_order(&proof.ext_data_hash),
@@ -163,39 +243,21 @@ pub fn handler<'info>(
     );
 
     let ext_amount = ext_data.ext_amount;
-    let fee = ext_data.fee;
-
-    // Validate fee calculation
-    // https://docs.yona.cash/concepts/fees - we take fee from output amount. 
-    // When you execute a swap with a 0.3% slippage tolerance, here's what happens:
-    // You receive the minimum amount out - Your transaction is guaranteed to complete, and you'll receive at least the minimum output amount based on the slippage tolerance
-    // Relayer takes the remaining tokens - Any difference between the actual swap output and the minimum amount goes to the relayer
-    // Relayer absorbs the slippage risk - By taking the remaining tokens, the relayer assumes all slippage risks
-    // utils::validate_fee(
-    //     ext_amount,
-    //     fee,
-    //     global_config.deposit_fee_rate, 
-    //     global_config.deposit_fee_rate,
-    //     global_config.fee_error_margin,
-    // )?;
 
-    // Verify the proof with both mint addresses
-    require!(
-        verify_compressed_proof(
-            proof.clone(), 
-            VERIFYING_KEY, 
-            ctx.accounts.input_mint.key(), 
-            ctx.accounts.output_mint.key()
-        ), 
-        ErrorCode::InvalidProof
-    );
+    // https://docs.yona.cash/concepts/fees - we take fee from output amount.
+    // The user receives ext_min_amount_out, the relayer gets the swap output above it and
+    // absorbs the slippage. An input side fee would leave the notes without being swapped or
+    // paid out, so it must be zero.
+    require!(ext_data.fee == 0, ErrorCode::InvalidFeeAmount);
 
-    // Split remaining accounts: first NUM_LIGHT_ACCOUNTS are for Light Protocol, rest are for Jupiter
-    let (light_accounts, jupiter_accounts) = ctx.remaining_accounts.split_at(NUM_LIGHT_ACCOUNTS);
+    // Verify the proof with both mint addresses
+    utils::require_valid_proof(&*ctx.accounts.verifying_key.load()?, |verifying_key| {
+        verify_compressed_proof(proof.clone(), verifying_key, ctx.accounts.input_mint.key(), ctx.accounts.output_mint.key())
+    })?;
 
     // Create Light Protocol nullifier compressed accounts
     create_light_nullifiers(
-        ctx.accounts.user.as_ref(),
+        ctx.accounts.relayer.as_ref(),
         light_accounts,
         &proof.input_nullifiers,
         light_proof,
@@ -204,15 +266,35 @@ pub fn handler<'info>(
         output_state_tree_index,
     )?;
 
-    // Get balance before swap
+    // Get balances before swap
     let balance_before = ctx.accounts.reserve_token_account_output.amount;
+    let input_balance_before = ctx.accounts.reserve_token_account_input.amount;
+    let input_amount = ext_amount.checked_neg()
+        .ok_or(ErrorCode::ArithmeticOverflow)? as u64;
+
+    if !jupiter_swap_data.is_empty() {
+        // The route must swap exactly the input amount between the two reserves
+        let route_keys: Vec<Pubkey> = jupiter_accounts.iter().map(|account| *account.key).collect();
+        let route = validate_route(
+            &jupiter_swap_data,
+            &route_keys,
+            &ExpectedRoute {
+                program_id: ctx.accounts.jupiter_program.key(),
+                authority: ctx.accounts.swap_authority.key(),
+                source_token_account: ctx.accounts.reserve_token_account_input.key(),
+                destination_token_account: ctx.accounts.reserve_token_account_output.key(),
+                source_mint: ctx.accounts.input_mint.key(),
+                destination_mint: ctx.accounts.output_mint.key(),
+                in_amount: input_amount,
+            },
+        )?;
 
-    if jupiter_swap_data.len() > 0 {
         let mut account_metas = Vec::new();
         
-        // Add remaining accounts (these are the accounts needed by Jupiter)
-        for account in jupiter_accounts.iter() {
-            let is_signer = if *account.key == ctx.accounts.global_config.key() {
+        // Add remaining accounts (these are the accounts needed by Jupiter), the swap authority
+        // only signs as the transfer authority of the route
+        for (index, account) in jupiter_accounts.iter().enumerate() {
+            let is_signer = if index == route.kind.authority_index() {
                 true
             } else {
                 account.is_signer
@@ -231,24 +313,74 @@ pub fn handler<'info>(
             data: jupiter_swap_data,
         };
         
-        // Execute Jupiter CPI
-        let account_infos: Vec<AccountInfo> = jupiter_accounts.to_vec();
+        let pool_id_bytes = global_config.pool_id.to_le_bytes();
         let global_config_seeds = &[
             b"global_config".as_ref(),
+            pool_id_bytes.as_ref(),
             &[global_config.bump],
         ];
-        let signer_seeds = &[&global_config_seeds[..]];
+        let global_config_signer_seeds = &[&global_config_seeds[..]];
+        let global_config_key = global_config.key();
+        let swap_authority_seeds = &[
+            b"swap_authority".as_ref(),
+            global_config_key.as_ref(),
+            &[ctx.bumps.swap_authority],
+        ];
+        let swap_authority_signer_seeds = &[&swap_authority_seeds[..]];
+
+        // Delegate exactly the input amount of the input reserve to the swap authority
+        let approve_ctx = CpiContext::new_with_signer(
+            ctx.accounts.input_token_program.to_account_info(),
+            ApproveChecked {
+                to: ctx.accounts.reserve_token_account_input.to_account_info(),
+                mint: ctx.accounts.input_mint.to_account_info(),
+                delegate: ctx.accounts.swap_authority.to_account_info(),
+                authority: ctx.accounts.global_config.to_account_info(),
+            },
+            global_config_signer_seeds,
+        );
+        approve_checked(approve_ctx, input_amount, ctx.accounts.input_mint.decimals)?;
 
+        // Snapshot the other reserves passed to the route
+        let reserves_before = reserve_balances(
+            jupiter_accounts,
+            &global_config_key,
+            &ctx.accounts.reserve_token_account_input.key(),
+        );
+
+        // Execute Jupiter CPI
+        let account_infos: Vec<AccountInfo> = jupiter_accounts.to_vec();
         invoke_signed(
             &jupiter_instruction,
             &account_infos,
-            signer_seeds,
+            swap_authority_signer_seeds,
         )?;
+
+        // No reserve other than the input one may lose funds
+        require_reserves_not_decreased(jupiter_accounts, &reserves_before)?;
+
+        // Drop what the route did not use of the delegation
+        let revoke_ctx = CpiContext::new_with_signer(
+            ctx.accounts.input_token_program.to_account_info(),
+            Revoke {
+                source: ctx.accounts.reserve_token_account_input.to_account_info(),
+                authority: ctx.accounts.global_config.to_account_info(),
+            },
+            global_config_signer_seeds,
+        );
+        revoke(revoke_ctx)?;
         
     } else {
        return Err(ErrorCode::InvalidJupiterSwapData.into());
     }
 
+    // The route must have spent exactly the input amount bound in the proof
+    ctx.accounts.reserve_token_account_input.reload()?;
+    let input_amount_spent = input_balance_before
+        .checked_sub(ctx.accounts.reserve_token_account_input.amount)
+        .ok_or(ErrorCode::SwapInputMismatch)?;
+    require!(input_amount_spent == input_amount, ErrorCode::SwapInputMismatch);
+
     // Reload the output token account to get updated balance
     ctx.accounts.reserve_token_account_output.reload()?;
     let balance_after = ctx.accounts.reserve_token_account_output.amount;
@@ -259,13 +391,22 @@ pub fn handler<'info>(
     
     // Calculate fee as difference between received and min_amount_out
     let min_amount = ext_data.ext_min_amount_out as u64;
-    let calculated_fee = actual_amount_received.checked_sub(min_amount)
+    let surplus = actual_amount_received.checked_sub(min_amount)
         .ok_or(ErrorCode::InsufficientSwapOutput)?;
+    // The whole surplus pays the relayer, at most its advertised rate on the output. A larger
+    // surplus is rejected rather than left in the reserve where no note can claim it
+    require!(
+        surplus <= ctx.accounts.relayer_account.max_fee(actual_amount_received)?,
+        ErrorCode::RelayerFeeTooHigh
+    );
+    let calculated_fee = surplus;
 
     // Transfer the fee to fee recipient using transfer_checked (Token-2022 compatible)
     if calculated_fee > 0 {
+        let pool_id_bytes = global_config.pool_id.to_le_bytes();
         let global_config_seeds = &[
             b"global_config".as_ref(),
+            pool_id_bytes.as_ref(),
             &[global_config.bump],
         ];
         let signer_seeds = &[&global_config_seeds[..]];
@@ -290,15 +431,14 @@ pub fn handler<'info>(
     MerkleTree::append::<Poseidon>(proof.output_commitments[1], tree_account)?;
 
     emit!(CommitmentData {
+        pool_id: global_config.pool_id,
+        tree_index: tree_account.tree_index,
         index: next_index_to_insert,
         commitment0: proof.output_commitments[0],
         commitment1: proof.output_commitments[1],
         encrypted_output: encrypted_output.to_vec(),
     });
 
-    let input_amount = ext_amount.checked_neg()
-        .ok_or(ErrorCode::ArithmeticOverflow)? as u64;
-
     emit!(SwapEvent {
         input_mint: ctx.accounts.input_mint.key(),
         output_mint: ctx.accounts.output_mint.key(),
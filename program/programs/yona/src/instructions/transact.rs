@@ -0,0 +1,146 @@
+use anchor_lang::prelude::*;
+use ark_ff::PrimeField;
+use ark_bn254::Fr;
+use light_hasher::Poseidon;
+use anchor_spl::token_interface::Mint;
+
+use crate::merkle_tree::MerkleTree;
+use crate::state::{MerkleTreeAccount, GlobalConfig, MintConfig, VerifyingKeyAccount, CIRCUIT_TRANSACTION2};
+use crate::types::{CompressedProof, CommitmentData};
+use crate::ErrorCode;
+use crate::utils::verify_compressed_proof;
+use crate::utils;
+use crate::light::create_light_nullifiers;
+use light_sdk::instruction::{PackedAddressTreeInfo, ValidityProof};
+
+
+#[derive(Accounts)]
+#[instruction(
+    proof: CompressedProof, 
+    encrypted_output: Vec<u8>,
+    light_proof: ValidityProof,
+    nullifier0_address_tree_info: PackedAddressTreeInfo,
+    nullifier1_address_tree_info: PackedAddressTreeInfo,
+    output_state_tree_index: u8
+)]
+pub struct Transact<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"merkle_tree",
+            global_config.pool_id.to_le_bytes().as_ref(),
+            global_config.active_tree_index.to_le_bytes().as_ref()
+        ],
+        bump = tree_account.load()?.bump
+    )]
+    pub tree_account: AccountLoader<'info, MerkleTreeAccount>,
+
+    /// Retired tree of the same pool, only needed when the proof root is from a rolled over tree
+    pub retired_tree_account: Option<AccountLoader<'info, MerkleTreeAccount>>,
+
+    #[account(
+        seeds = [b"global_config", global_config.pool_id.to_le_bytes().as_ref()],
+        bump = global_config.bump
+    )]
+    pub global_config: Box<Account<'info, GlobalConfig>>,
+
+    #[account(
+        seeds = [b"verifying_key", CIRCUIT_TRANSACTION2.to_le_bytes().as_ref()],
+        bump = verifying_key.load()?.bump
+    )]
+    pub verifying_key: AccountLoader<'info, VerifyingKeyAccount>,
+    
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Notes of disabled mints can still be transferred, no value enters the pool
+    #[account(
+        seeds = [b"mint_config", global_config.key().as_ref(), mint.key().as_ref()],
+        bump = mint_config.bump
+    )]
+    pub mint_config: Box<Account<'info, MintConfig>>,
+
+    /// Pays for the transaction and the nullifier compressed accounts, can be a relayer
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+    // Remaining accounts for Light Protocol:
+    // [light_system_program, cpi_signer, registered_program_pda, noop_program,
+    //  account_compression_authority, account_compression_program, self_program,
+    //  system_program, address_tree, address_queue, output_state_tree, nullifier_queue]
+}
+
+/**
+ * Private transfer inside the pool: spends two notes and creates two notes of the same mint.
+ * 
+ * ext_amount and fee are 0, so both public amounts must be 0 and no tokens are moved.
+ * recipient and fee_recipient are hashed as the default pubkey.
+ */
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, Transact<'info>>, 
+    proof: CompressedProof, 
+    encrypted_output: Vec<u8>,
+    light_proof: ValidityProof,
+    nullifier0_address_tree_info: PackedAddressTreeInfo,
+    nullifier1_address_tree_info: PackedAddressTreeInfo,
+    output_state_tree_index: u8,
+) -> Result<()> {
+    let tree_account = &mut ctx.accounts.tree_account.load_mut()?;
+    let global_config = &ctx.accounts.global_config;
+
+    // Check if proof.root is in the proof history of the active tree (or of the retired tree)
+    utils::require_known_root(
+        tree_account,
+        ctx.accounts.retired_tree_account.as_ref(),
+        proof.root,
+    )?;
+
+    // Check if the ext_data hashes to the same ext_data in the proof
+    let calculated_ext_data_hash = utils::calculate_complete_ext_data_hash(
+        Pubkey::default(),
+        0,
+        &encrypted_output,
+        0,
+        Pubkey::default(),
+        ctx.accounts.mint.key(),
+        ctx.accounts.mint.key(),
+    )?;
+    require!(
+        Fr::from_le_bytes_mod_order(&calculated_ext_data_hash) == Fr::from_be_bytes_mod_order(&proof.ext_data_hash),
+        ErrorCode::ExtDataHashMismatch
+    );
+
+    // Nothing enters or leaves the pool
+    require!(proof.public_amount0 == [0; 32], ErrorCode::InvalidPublicAmountData);
+    require!(proof.public_amount1 == [0; 32], ErrorCode::InvalidPublicAmountData);
+
+    utils::require_valid_proof(&*ctx.accounts.verifying_key.load()?, |verifying_key| {
+        verify_compressed_proof(proof.clone(), verifying_key, ctx.accounts.mint.key(), ctx.accounts.mint.key())
+    })?;
+
+    // Create Light Protocol nullifier compressed accounts
+    create_light_nullifiers(
+        ctx.accounts.payer.as_ref(),
+        ctx.remaining_accounts,
+        &proof.input_nullifiers,
+        light_proof,
+        nullifier0_address_tree_info,
+        nullifier1_address_tree_info,
+        output_state_tree_index,
+    )?;
+
+    let next_index_to_insert = tree_account.next_index;
+    MerkleTree::append::<Poseidon>(proof.output_commitments[0], tree_account)?;
+    MerkleTree::append::<Poseidon>(proof.output_commitments[1], tree_account)?;
+
+    emit!(CommitmentData {
+        pool_id: global_config.pool_id,
+        tree_index: tree_account.tree_index,
+        index: next_index_to_insert,
+        commitment0: proof.output_commitments[0],
+        commitment1: proof.output_commitments[1],
+        encrypted_output: encrypted_output.to_vec(),
+    });
+
+    Ok(())
+}
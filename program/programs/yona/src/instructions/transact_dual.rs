@@ -0,0 +1,398 @@
+use anchor_lang::prelude::*;
+use ark_ff::PrimeField;
+use ark_bn254::Fr;
+use light_hasher::Poseidon;
+use anchor_spl::token_interface::{
+    Mint, TokenAccount, TokenInterface,
+    transfer_checked, TransferChecked,
+};
+
+use crate::merkle_tree::MerkleTree;
+use crate::state::{MerkleTreeAccount, GlobalConfig, MintConfig, RelayerAccount, VerifyingKeyAccount, CIRCUIT_TRANSACTION2};
+use crate::types::{CompressedProof, DualExtDataMinified, CommitmentData, DualTransactEvent};
+use crate::ErrorCode;
+use crate::utils::verify_compressed_proof;
+use crate::utils;
+use crate::light::create_light_nullifiers;
+use light_sdk::instruction::{PackedAddressTreeInfo, ValidityProof};
+
+
+#[derive(Accounts)]
+#[instruction(
+    proof: CompressedProof, 
+    ext_data_minified: DualExtDataMinified, 
+    encrypted_output: Vec<u8>,
+    light_proof: ValidityProof,
+    nullifier0_address_tree_info: PackedAddressTreeInfo,
+    nullifier1_address_tree_info: PackedAddressTreeInfo,
+    output_state_tree_index: u8
+)]
+pub struct TransactDual<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"merkle_tree",
+            global_config.pool_id.to_le_bytes().as_ref(),
+            global_config.active_tree_index.to_le_bytes().as_ref()
+        ],
+        bump = tree_account.load()?.bump
+    )]
+    pub tree_account: AccountLoader<'info, MerkleTreeAccount>,
+
+    /// Retired tree of the same pool, only needed when the proof root is from a rolled over tree
+    pub retired_tree_account: Option<AccountLoader<'info, MerkleTreeAccount>>,
+
+    #[account(
+        seeds = [b"global_config", global_config.pool_id.to_le_bytes().as_ref()],
+        bump = global_config.bump
+    )]
+    pub global_config: Box<Account<'info, GlobalConfig>>,
+
+    #[account(
+        seeds = [b"verifying_key", CIRCUIT_TRANSACTION2.to_le_bytes().as_ref()],
+        bump = verifying_key.load()?.bump
+    )]
+    pub verifying_key: AccountLoader<'info, VerifyingKeyAccount>,
+    
+    pub mint0: Box<InterfaceAccount<'info, Mint>>,
+    pub mint1: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"mint_config", global_config.key().as_ref(), mint0.key().as_ref()],
+        bump = mint_config0.bump
+    )]
+    pub mint_config0: Box<Account<'info, MintConfig>>,
+
+    #[account(
+        seeds = [b"mint_config", global_config.key().as_ref(), mint1.key().as_ref()],
+        bump = mint_config1.bump
+    )]
+    pub mint_config1: Box<Account<'info, MintConfig>>,
+
+    #[account(mut,
+        associated_token::mint = mint0,  
+        associated_token::authority = global_config,
+        associated_token::token_program = token_program0,
+    )]
+    pub reserve_token_account0: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut,
+        associated_token::mint = mint1,  
+        associated_token::authority = global_config,
+        associated_token::token_program = token_program1,
+    )]
+    pub reserve_token_account1: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Source of deposits and destination of withdrawals of mint0
+    #[account(mut,
+        token::mint = mint0,
+        token::authority = user,
+        token::token_program = token_program0,
+    )]
+    pub user_token_account0: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Source of deposits and destination of withdrawals of mint1
+    #[account(mut,
+        token::mint = mint1,
+        token::authority = user,
+        token::token_program = token_program1,
+    )]
+    pub user_token_account1: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Fee token account of mint0, owned by the relayer's registered fee recipient when fee0 is paid
+    #[account(mut,
+        token::mint = mint0,
+        token::token_program = token_program0,
+        constraint = ext_data_minified.fee0 == 0 || relayer_account.as_ref()
+            .is_some_and(|relayer| fee_recipient_account0.owner == relayer.fee_recipient)
+            @ ErrorCode::InvalidFeeRecipient
+    )]
+    pub fee_recipient_account0: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Fee token account of mint1, owned by the relayer's registered fee recipient when fee1 is paid
+    #[account(mut,
+        token::mint = mint1,
+        token::token_program = token_program1,
+        constraint = ext_data_minified.fee1 == 0 || relayer_account.as_ref()
+            .is_some_and(|relayer| fee_recipient_account1.owner == relayer.fee_recipient)
+            @ ErrorCode::InvalidFeeRecipient
+    )]
+    pub fee_recipient_account1: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// Registry entry of the relayer paid the fees, required as soon as one side pays a fee.
+    /// Fee token accounts must be owned by its registered fee recipient, as in `withdraw`
+    #[account(
+        seeds = [b"relayer", global_config.key().as_ref(), relayer_account.authority.as_ref()],
+        bump = relayer_account.bump,
+        constraint = relayer_account.bond >= global_config.min_relayer_bond @ ErrorCode::RelayerBondTooLow,
+        constraint = !relayer_account.is_deregistering() @ ErrorCode::RelayerDeregistering
+    )]
+    pub relayer_account: Option<Box<Account<'info, RelayerAccount>>>,
+    
+    pub system_program: Program<'info, System>,
+    /// Token program of mint0 - supports both Token and Token-2022 programs
+    pub token_program0: Interface<'info, TokenInterface>,
+    /// Token program of mint1 - supports both Token and Token-2022 programs
+    pub token_program1: Interface<'info, TokenInterface>,
+    // Remaining accounts for Light Protocol:
+    // [light_system_program, cpi_signer, registered_program_pda, noop_program,
+    //  account_compression_authority, account_compression_program, self_program,
+    //  system_program, address_tree, address_queue, output_state_tree, nullifier_queue]
+}
+
+/**
+ * Deposit and/or withdraw two different mints with one proof, e.g. deposit USDC while
+ * withdrawing SOL. publicAmount0 settles mint0 and publicAmount1 settles mint1.
+ * 
+ * Each side is a deposit (ext_amount > 0), a withdrawal (ext_amount < 0) or idle (0, no fee).
+ * Withdrawals go to the user's token account of that mint, fees are checked per mint and
+ * only paid to a bonded relayer, capped at its advertised rate.
+ */
+#[allow(clippy::too_many_arguments)]
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, TransactDual<'info>>, 
+    proof: CompressedProof, 
+    ext_data_minified: DualExtDataMinified, 
+    encrypted_output: Vec<u8>,
+    light_proof: ValidityProof,
+    nullifier0_address_tree_info: PackedAddressTreeInfo,
+    nullifier1_address_tree_info: PackedAddressTreeInfo,
+    output_state_tree_index: u8,
+) -> Result<()> {
+    let tree_account = &mut ctx.accounts.tree_account.load_mut()?;
+    let global_config = &ctx.accounts.global_config;
+    let mint0 = ctx.accounts.mint0.key();
+    let mint1 = ctx.accounts.mint1.key();
+
+    // Same-mint transactions go through deposit/withdraw
+    require!(mint0 != mint1, ErrorCode::UnsupportedMintAddress);
+    require!(
+        ext_data_minified.ext_amount0 != 0 || ext_data_minified.ext_amount1 != 0,
+        ErrorCode::InvalidExtAmount
+    );
+
+    // Check if proof.root is in the proof history of the active tree (or of the retired tree)
+    utils::require_known_root(
+        tree_account,
+        ctx.accounts.retired_tree_account.as_ref(),
+        proof.root,
+    )?;
+
+    // Check if the ext_data hashes to the same ext_data in the proof
+    let calculated_ext_data_hash = utils::calculate_dual_ext_data_hash(
+        ctx.accounts.user.key(),
+        ext_data_minified.ext_amount0,
+        ext_data_minified.ext_amount1,
+        &encrypted_output,
+        ext_data_minified.fee0,
+        ext_data_minified.fee1,
+        ctx.accounts.fee_recipient_account0.key(),
+        ctx.accounts.fee_recipient_account1.key(),
+        mint0,
+        mint1,
+    )?;
+    require!(
+        Fr::from_le_bytes_mod_order(&calculated_ext_data_hash) == Fr::from_be_bytes_mod_order(&proof.ext_data_hash),
+        ErrorCode::ExtDataHashMismatch
+    );
+
+    require!(
+        utils::check_dual_public_amount(ext_data_minified.ext_amount0, ext_data_minified.fee0, proof.public_amount0),
+        ErrorCode::InvalidPublicAmountData
+    );
+    require!(
+        utils::check_dual_public_amount(ext_data_minified.ext_amount1, ext_data_minified.fee1, proof.public_amount1),
+        ErrorCode::InvalidPublicAmountData
+    );
+
+    for (ext_amount, fee, mint_config) in [
+        (ext_data_minified.ext_amount0, ext_data_minified.fee0, &ctx.accounts.mint_config0),
+        (ext_data_minified.ext_amount1, ext_data_minified.fee1, &ctx.accounts.mint_config1),
+    ] {
+        utils::validate_fee(
+            ext_amount,
+            fee,
+            mint_config.deposit_fee_rate(global_config),
+            mint_config.withdrawal_fee_rate(global_config),
+            global_config.fee_error_margin,
+        )?;
+
+        // Same relayer binding as `withdraw`: the fee recipient is checked with the accounts, the
+        // pool rates are a floor and the rate advertised by the relayer caps the fee
+        if fee > 0 {
+            let relayer_account = ctx.accounts.relayer_account.as_ref()
+                .ok_or(ErrorCode::InvalidFeeRecipient)?;
+            require!(
+                fee <= relayer_account.max_fee(ext_amount.unsigned_abs())?,
+                ErrorCode::RelayerFeeTooHigh
+            );
+        }
+
+        // Disabled mints can still be withdrawn, deposits must be enabled and within bounds
+        if ext_amount > 0 {
+            require!(mint_config.enabled, ErrorCode::UnsupportedMintAddress);
+            require!(
+                ext_amount as u64 >= mint_config.min_deposit_amount,
+                ErrorCode::DepositBelowMinimum
+            );
+            require!(
+                ext_amount as u64 <= mint_config.max_deposit_amount,
+                ErrorCode::DepositLimitExceeded
+            );
+        }
+    }
+
+    utils::require_valid_proof(&*ctx.accounts.verifying_key.load()?, |verifying_key| {
+        verify_compressed_proof(proof.clone(), verifying_key, mint0, mint1)
+    })?;
+
+    // Create Light Protocol nullifier compressed accounts
+    create_light_nullifiers(
+        ctx.accounts.user.as_ref(),
+        ctx.remaining_accounts,
+        &proof.input_nullifiers,
+        light_proof,
+        nullifier0_address_tree_info,
+        nullifier1_address_tree_info,
+        output_state_tree_index,
+    )?;
+
+    let pool_id_bytes = global_config.pool_id.to_le_bytes();
+    let global_config_seeds = &[
+        b"global_config".as_ref(),
+        pool_id_bytes.as_ref(),
+        &[global_config.bump],
+    ];
+    let signer_seeds = &[&global_config_seeds[..]];
+
+    settle(
+        ext_data_minified.ext_amount0,
+        ext_data_minified.fee0,
+        &ctx.accounts.token_program0,
+        &ctx.accounts.mint0,
+        &ctx.accounts.reserve_token_account0,
+        &ctx.accounts.user_token_account0,
+        &ctx.accounts.fee_recipient_account0,
+        ctx.accounts.user.as_ref(),
+        &ctx.accounts.global_config.to_account_info(),
+        signer_seeds,
+    )?;
+    settle(
+        ext_data_minified.ext_amount1,
+        ext_data_minified.fee1,
+        &ctx.accounts.token_program1,
+        &ctx.accounts.mint1,
+        &ctx.accounts.reserve_token_account1,
+        &ctx.accounts.user_token_account1,
+        &ctx.accounts.fee_recipient_account1,
+        ctx.accounts.user.as_ref(),
+        &ctx.accounts.global_config.to_account_info(),
+        signer_seeds,
+    )?;
+
+    let next_index_to_insert = tree_account.next_index;
+    MerkleTree::append::<Poseidon>(proof.output_commitments[0], tree_account)?;
+    MerkleTree::append::<Poseidon>(proof.output_commitments[1], tree_account)?;
+
+    emit!(CommitmentData {
+        pool_id: global_config.pool_id,
+        tree_index: tree_account.tree_index,
+        index: next_index_to_insert,
+        commitment0: proof.output_commitments[0],
+        commitment1: proof.output_commitments[1],
+        encrypted_output: encrypted_output.to_vec(),
+    });
+
+    emit!(DualTransactEvent {
+        mint0,
+        ext_amount0: ext_data_minified.ext_amount0,
+        mint1,
+        ext_amount1: ext_data_minified.ext_amount1,
+    });
+
+    Ok(())
+}
+
+/// Moves the tokens of one side: user -> reserve (+ fee) for deposits,
+/// reserve -> user (+ fee) for withdrawals, nothing for an idle side.
+#[allow(clippy::too_many_arguments)]
+fn settle<'info>(
+    ext_amount: i64,
+    fee: u64,
+    token_program: &Interface<'info, TokenInterface>,
+    mint: &InterfaceAccount<'info, Mint>,
+    reserve_token_account: &InterfaceAccount<'info, TokenAccount>,
+    user_token_account: &InterfaceAccount<'info, TokenAccount>,
+    fee_recipient_account: &InterfaceAccount<'info, TokenAccount>,
+    user: &AccountInfo<'info>,
+    global_config: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let decimals = mint.decimals;
+
+    if ext_amount > 0 {
+        let transfer_ctx = CpiContext::new(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: user_token_account.to_account_info(),
+                mint: mint.to_account_info(),
+                to: reserve_token_account.to_account_info(),
+                authority: user.clone(),
+            },
+        );
+        transfer_checked(transfer_ctx, ext_amount as u64, decimals)?;
+
+        if fee > 0 {
+            let fee_transfer_ctx = CpiContext::new(
+                token_program.to_account_info(),
+                TransferChecked {
+                    from: user_token_account.to_account_info(),
+                    mint: mint.to_account_info(),
+                    to: fee_recipient_account.to_account_info(),
+                    authority: user.clone(),
+                },
+            );
+            transfer_checked(fee_transfer_ctx, fee, decimals)?;
+        }
+    } else if ext_amount < 0 {
+        let withdrawal_amount = ext_amount.checked_neg()
+            .ok_or(ErrorCode::ArithmeticOverflow)? as u64;
+        let total = withdrawal_amount.checked_add(fee)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        require!(
+            reserve_token_account.amount >= total,
+            ErrorCode::InsufficientFundsForWithdrawal
+        );
+
+        if fee > 0 {
+            let fee_transfer_ctx = CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                TransferChecked {
+                    from: reserve_token_account.to_account_info(),
+                    mint: mint.to_account_info(),
+                    to: fee_recipient_account.to_account_info(),
+                    authority: global_config.clone(),
+                },
+                signer_seeds,
+            );
+            transfer_checked(fee_transfer_ctx, fee, decimals)?;
+        }
+
+        let transfer_ctx = CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: reserve_token_account.to_account_info(),
+                mint: mint.to_account_info(),
+                to: user_token_account.to_account_info(),
+                authority: global_config.clone(),
+            },
+            signer_seeds,
+        );
+        transfer_checked(transfer_ctx, withdrawal_amount, decimals)?;
+    }
+
+    Ok(())
+}
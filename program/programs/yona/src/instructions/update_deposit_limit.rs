@@ -1,14 +1,20 @@
 use anchor_lang::prelude::*;
+use crate::ErrorCode;
 
 /**
- * Update the maximum deposit amount limit. Only the authority can call this.
+ * Deprecated, use `update_mint_config`. Only the authority can call this.
+ * The limit used to be in lamports for every mint, it now sets the max deposit of the
+ * wrapped SOL mint config of the default pool.
  */
 pub fn handler(ctx: Context<crate::UpdateDepositLimit>, new_limit: u64) -> Result<()> {
-    let tree_account = &mut ctx.accounts.tree_account.load_mut()?;
-    
-    tree_account.max_deposit_amount = new_limit;
-    
+    let mint_config = &mut ctx.accounts.mint_config;
+    require!(
+        mint_config.min_deposit_amount <= new_limit,
+        ErrorCode::InvalidMintConfig
+    );
+
+    mint_config.max_deposit_amount = new_limit;
+
     msg!("Deposit limit updated to: {} lamports", new_limit);
     Ok(())
 }
-
@@ -8,7 +8,8 @@ pub fn handler(
     ctx: Context<crate::UpdateGlobalConfig>, 
     deposit_fee_rate: Option<u16>,
     withdrawal_fee_rate: Option<u16>,
-    fee_error_margin: Option<u16>
+    fee_error_margin: Option<u16>,
+    min_relayer_bond: Option<u64>
 ) -> Result<()> {
     let global_config = &mut ctx.accounts.global_config;
     
@@ -29,6 +30,12 @@ pub fn handler(
         global_config.fee_error_margin = fee_error_margin_val;
         msg!("Fee error margin updated to: {} basis points", fee_error_margin_val);
     }
+
+    // Relayers below the new minimum can't relay until they top up their bond
+    if let Some(min_bond) = min_relayer_bond {
+        global_config.min_relayer_bond = min_bond;
+        msg!("Minimum relayer bond updated to: {} lamports", min_bond);
+    }
     
     Ok(())
 }
@@ -0,0 +1,14 @@
+use anchor_lang::prelude::*;
+use crate::types::MintConfigParams;
+
+/**
+ * Replace the settings of an allowed mint. Only the authority can call this.
+ * Disabling a mint blocks deposits into it, withdrawals stay possible.
+ */
+pub fn handler(ctx: Context<crate::UpdateMintConfig>, params: MintConfigParams) -> Result<()> {
+    let mint_config = &mut ctx.accounts.mint_config;
+    mint_config.apply(params)?;
+
+    msg!("Mint config updated for mint: {}", mint_config.mint);
+    Ok(())
+}
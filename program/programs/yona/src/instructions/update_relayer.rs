@@ -0,0 +1,47 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use crate::ErrorCode;
+
+/**
+ * Update the advertised fee rate and fee recipient of a relayer, or top up its bond.
+ * Only the relayer can call this.
+ */
+pub fn handler(
+    ctx: Context<crate::UpdateRelayer>,
+    fee_rate: Option<u16>,
+    fee_recipient: Option<Pubkey>,
+    bond_top_up: Option<u64>,
+) -> Result<()> {
+    if let Some(amount) = bond_top_up {
+        let bond_transfer_ctx = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: ctx.accounts.authority.to_account_info(),
+                to: ctx.accounts.relayer_account.to_account_info(),
+            },
+        );
+        system_program::transfer(bond_transfer_ctx, amount)?;
+    }
+
+    let relayer_account = &mut ctx.accounts.relayer_account;
+
+    if let Some(rate) = fee_rate {
+        require!(rate <= 10000, ErrorCode::InvalidFeeRate);
+        relayer_account.fee_rate = rate;
+        msg!("Relayer fee rate updated to: {} basis points", rate);
+    }
+
+    if let Some(recipient) = fee_recipient {
+        relayer_account.fee_recipient = recipient;
+        msg!("Relayer fee recipient updated to: {}", recipient);
+    }
+
+    if let Some(amount) = bond_top_up {
+        relayer_account.bond = relayer_account.bond
+            .checked_add(amount)
+            .ok_or(ErrorCode::ArithmeticOverflow)?;
+        msg!("Relayer bond topped up to: {} lamports", relayer_account.bond);
+    }
+
+    Ok(())
+}
@@ -10,10 +10,10 @@ use anchor_spl::token_interface::{
 use anchor_spl::token::spl_token::native_mint;
 
 use crate::merkle_tree::MerkleTree;
-use crate::state::{MerkleTreeAccount, GlobalConfig};
+use crate::state::{MerkleTreeAccount, GlobalConfig, MintConfig, RelayerAccount, VerifyingKeyAccount, CIRCUIT_TRANSACTION2};
 use crate::types::{CompressedProof, ExtData, ExtDataMinified, CommitmentData, WithdrawEvent};
 use crate::ErrorCode;
-use crate::utils::{verify_compressed_proof, VERIFYING_KEY};
+use crate::utils::verify_compressed_proof;
 use crate::utils;
 use crate::light::create_light_nullifiers;
 use light_sdk::instruction::{PackedAddressTreeInfo, ValidityProof};
@@ -33,19 +33,39 @@ use light_sdk::instruction::{PackedAddressTreeInfo, ValidityProof};
 pub struct Withdraw<'info> {
     #[account(
         mut,
-        seeds = [b"merkle_tree"],
+        seeds = [
+            b"merkle_tree",
+            global_config.pool_id.to_le_bytes().as_ref(),
+            global_config.active_tree_index.to_le_bytes().as_ref()
+        ],
         bump = tree_account.load()?.bump
     )]
     pub tree_account: AccountLoader<'info, MerkleTreeAccount>,
 
+    /// Retired tree of the same pool, only needed when the proof root is from a rolled over tree
+    pub retired_tree_account: Option<AccountLoader<'info, MerkleTreeAccount>>,
+
     #[account(
-        seeds = [b"global_config"],
-        bump
+        seeds = [b"global_config", global_config.pool_id.to_le_bytes().as_ref()],
+        bump = global_config.bump
     )]
     pub global_config: Box<Account<'info, GlobalConfig>>,
+
+    #[account(
+        seeds = [b"verifying_key", CIRCUIT_TRANSACTION2.to_le_bytes().as_ref()],
+        bump = verifying_key.load()?.bump
+    )]
+    pub verifying_key: AccountLoader<'info, VerifyingKeyAccount>,
     
     pub input_mint: Box<InterfaceAccount<'info, Mint>>,
 
+    /// Disabled mints can still be withdrawn
+    #[account(
+        seeds = [b"mint_config", global_config.key().as_ref(), input_mint.key().as_ref()],
+        bump = mint_config.bump
+    )]
+    pub mint_config: Box<Account<'info, MintConfig>>,
+
     #[account(mut,
         associated_token::mint = input_mint,  
         associated_token::authority = global_config,
@@ -61,12 +81,29 @@ pub struct Withdraw<'info> {
     #[account(mut)]
     pub recipient_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    /// CHECK: user should be able to send fees to any types of accounts
-    #[account(mut)]
-    pub fee_recipient_account: UncheckedAccount<'info>,
+    /// Fee token account of the relayer, must be owned by its registered fee recipient
+    #[account(
+        mut,
+        token::mint = input_mint,
+        token::token_program = token_program,
+        constraint = relayer_account.as_ref()
+            .is_none_or(|relayer_account| fee_recipient_account.owner == relayer_account.fee_recipient)
+            @ ErrorCode::InvalidFeeRecipient
+    )]
+    pub fee_recipient_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(mut)]
     pub relayer: Signer<'info>,
+
+    /// Registry entry of the relayer, required to take a fee. Without it the withdrawal is
+    /// self-relayed and pays no fee
+    #[account(
+        seeds = [b"relayer", global_config.key().as_ref(), relayer.key().as_ref()],
+        bump = relayer_account.bump,
+        constraint = relayer_account.bond >= global_config.min_relayer_bond @ ErrorCode::RelayerBondTooLow,
+        constraint = !relayer_account.is_deregistering() @ ErrorCode::RelayerDeregistering
+    )]
+    pub relayer_account: Option<Box<Account<'info, RelayerAccount>>>,
     
     pub system_program: Program<'info, System>,
     /// Token program - supports both Token and Token-2022 programs
@@ -77,6 +114,7 @@ pub struct Withdraw<'info> {
 }
 
 
+#[allow(clippy::too_many_arguments)]
 pub fn handler<'info>(
     ctx: Context<'_, '_, '_, 'info, Withdraw<'info>>, 
     proof: CompressedProof, 
@@ -89,6 +127,7 @@ pub fn handler<'info>(
 ) -> Result<()> {
     let tree_account = &mut ctx.accounts.tree_account.load_mut()?;
     let global_config = &ctx.accounts.global_config;
+    let mint_config = &ctx.accounts.mint_config;
 
     // Reconstruct full ExtData from minified version and context accounts
     let recipient_key = ctx.accounts.recipient.key();
@@ -99,11 +138,12 @@ pub fn handler<'info>(
         ext_data_minified,
     );
 
-    // Check if proof.root is in the tree_account's proof history
-    require!(
-        MerkleTree::is_known_root(&tree_account, proof.root),
-        ErrorCode::UnknownRoot
-    );
+    // Check if proof.root is in the proof history of the active tree (or of the retired tree)
+    utils::require_known_root(
+        tree_account,
+        ctx.accounts.retired_tree_account.as_ref(),
+        proof.root,
+    )?;
 
     // Check if the ext_data hashes to the same ext_data in the proof
     let calculated_ext_data_hash = utils::calculate_complete_ext_data_hash(
@@ -126,21 +166,35 @@ pub fn handler<'info>(
         utils::check_public_amount(ext_data.ext_amount, ext_data.fee, proof.public_amount0),
         ErrorCode::InvalidPublicAmountData
     );
-    require!(proof.public_amount1 == [0; 32], ErrorCode::InvalidPublicAmountData); // publicAmount1 must be zero in single-token SOL mode
+    require!(proof.public_amount1 == [0; 32], ErrorCode::DualTokenNotSupported); // publicAmount1 must be zero in single-token SOL mode
     
     let ext_amount = ext_data.ext_amount;
     let fee = ext_data.fee;
 
-    // Validate fee calculation using utility function
-    utils::validate_fee(
-        ext_amount,
-        fee,
-        global_config.deposit_fee_rate,
-        global_config.withdrawal_fee_rate,
-        global_config.fee_error_margin,
-    )?;
+    match ctx.accounts.relayer_account.as_ref() {
+        Some(relayer_account) => {
+            // Validate fee calculation using utility function
+            utils::validate_fee(
+                ext_amount,
+                fee,
+                mint_config.deposit_fee_rate(global_config),
+                mint_config.withdrawal_fee_rate(global_config),
+                global_config.fee_error_margin,
+            )?;
+
+            // The pool rates are a floor, the rate advertised by the relayer caps the fee
+            require!(
+                fee <= relayer_account.max_fee(ext_amount.unsigned_abs())?,
+                ErrorCode::RelayerFeeTooHigh
+            );
+        }
+        // Self-relayed, there is no relayer to pay
+        None => require!(fee == 0, ErrorCode::InvalidFeeRecipient),
+    }
 
-    require!(verify_compressed_proof(proof.clone(), VERIFYING_KEY, ctx.accounts.input_mint.key(), ctx.accounts.input_mint.key()), ErrorCode::InvalidProof);
+    utils::require_valid_proof(&*ctx.accounts.verifying_key.load()?, |verifying_key| {
+        verify_compressed_proof(proof.clone(), verifying_key, ctx.accounts.input_mint.key(), ctx.accounts.input_mint.key())
+    })?;
     require!(ext_amount < 0, ErrorCode::InvalidExtAmount);
 
     // Create Light Protocol nullifier compressed accounts
@@ -167,8 +221,10 @@ pub fn handler<'info>(
     );
 
     // Create PDA signer seeds for the global_config account
+    let pool_id_bytes = global_config.pool_id.to_le_bytes();
     let global_config_seeds = &[
         b"global_config".as_ref(),
+        pool_id_bytes.as_ref(),
         &[global_config.bump],
     ];
     let signer_seeds = &[&global_config_seeds[..]];
@@ -264,6 +320,8 @@ pub fn handler<'info>(
     MerkleTree::append::<Poseidon>(proof.output_commitments[1], tree_account)?;
 
     emit!(CommitmentData {
+        pool_id: global_config.pool_id,
+        tree_index: tree_account.tree_index,
         index: next_index_to_insert,
         commitment0: proof.output_commitments[0],
         commitment1: proof.output_commitments[1],
@@ -0,0 +1,18 @@
+use anchor_lang::prelude::*;
+use crate::ErrorCode;
+
+/**
+ * Writes a chunk of the pending key (alpha_g1 | beta_g2 | gamma_g2 | delta_g2 | ic) at `offset`.
+ * Every write restarts the timelock, so the final key is public for the whole delay.
+ */
+pub fn handler(ctx: Context<crate::UpdateVerifyingKey>, offset: u32, data: Vec<u8>) -> Result<()> {
+    let verifying_key = &mut ctx.accounts.verifying_key.load_mut()?;
+    require!(verifying_key.pending.is_set(), ErrorCode::VerifyingKeyNotSet);
+
+    verifying_key.pending.write(offset as usize, &data)?;
+    verifying_key.pending_activation_ts = Clock::get()?.unix_timestamp
+        .checked_add(verifying_key.timelock_seconds)
+        .ok_or(ErrorCode::ArithmeticOverflow)?;
+
+    Ok(())
+}
@@ -0,0 +1,186 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use anchor_spl::token_interface::TokenAccount;
+use crate::jupiter_aggregator::client::args::{Route, SharedAccountsRoute};
+use crate::ErrorCode;
+
+// Account positions of the `route` instruction:
+// [token_program, user_transfer_authority, user_source_token_account,
+//  user_destination_token_account, destination_token_account (optional), destination_mint,
+//  platform_fee_account (optional), event_authority, program, ...route plan accounts]
+const ROUTE_AUTHORITY: usize = 1;
+const ROUTE_SOURCE_TOKEN_ACCOUNT: usize = 2;
+const ROUTE_DESTINATION_TOKEN_ACCOUNT: usize = 3;
+const ROUTE_OPTIONAL_DESTINATION_TOKEN_ACCOUNT: usize = 4;
+const ROUTE_DESTINATION_MINT: usize = 5;
+
+// Account positions of the `shared_accounts_route` instruction:
+// [token_program, program_authority, user_transfer_authority, source_token_account,
+//  program_source_token_account, program_destination_token_account, destination_token_account,
+//  source_mint, destination_mint, platform_fee_account (optional), token2022_program (optional),
+//  event_authority, program, ...route plan accounts]
+const SHARED_ROUTE_AUTHORITY: usize = 2;
+const SHARED_ROUTE_SOURCE_TOKEN_ACCOUNT: usize = 3;
+const SHARED_ROUTE_DESTINATION_TOKEN_ACCOUNT: usize = 6;
+const SHARED_ROUTE_SOURCE_MINT: usize = 7;
+const SHARED_ROUTE_DESTINATION_MINT: usize = 8;
+
+/// Accounts a route forwarded by `swap` must use
+pub struct ExpectedRoute {
+    /// Aggregator program, stands for the optional accounts that are not set
+    pub program_id: Pubkey,
+    /// Signer transferring the input tokens out of the source reserve
+    pub authority: Pubkey,
+    pub source_token_account: Pubkey,
+    pub destination_token_account: Pubkey,
+    pub source_mint: Pubkey,
+    pub destination_mint: Pubkey,
+    pub in_amount: u64,
+}
+
+/// Jupiter route instructions `swap` accepts. Token ledger and exact out routes are rejected,
+/// their input amount is not fixed by the instruction data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RouteKind {
+    Route,
+    SharedAccountsRoute,
+}
+
+impl RouteKind {
+    /// Position of the account signing the input transfer, the only account promoted to signer
+    pub fn authority_index(self) -> usize {
+        match self {
+            RouteKind::Route => ROUTE_AUTHORITY,
+            RouteKind::SharedAccountsRoute => SHARED_ROUTE_AUTHORITY,
+        }
+    }
+}
+
+/// Decoded route arguments checked by `swap`
+pub struct DecodedRoute {
+    pub kind: RouteKind,
+    pub in_amount: u64,
+    /// Output quoted when the route was built, Jupiter enforces it less `slippage_bps`
+    pub quoted_out_amount: u64,
+    pub platform_fee_bps: u8,
+}
+
+/// Decodes `data` as a `route` or `shared_accounts_route` instruction of the Jupiter IDL.
+pub fn decode_route(data: &[u8]) -> Result<DecodedRoute> {
+    require!(data.len() >= 8, ErrorCode::UnsupportedJupiterRoute);
+    let (discriminator, args) = data.split_at(8);
+
+    if discriminator == Route::DISCRIMINATOR {
+        let route = Route::try_from_slice(args).map_err(|_| ErrorCode::UnsupportedJupiterRoute)?;
+        Ok(DecodedRoute {
+            kind: RouteKind::Route,
+            in_amount: route.in_amount,
+            quoted_out_amount: route.quoted_out_amount,
+            platform_fee_bps: route.platform_fee_bps,
+        })
+    } else if discriminator == SharedAccountsRoute::DISCRIMINATOR {
+        let route = SharedAccountsRoute::try_from_slice(args)
+            .map_err(|_| ErrorCode::UnsupportedJupiterRoute)?;
+        Ok(DecodedRoute {
+            kind: RouteKind::SharedAccountsRoute,
+            in_amount: route.in_amount,
+            quoted_out_amount: route.quoted_out_amount,
+            platform_fee_bps: route.platform_fee_bps,
+        })
+    } else {
+        err!(ErrorCode::UnsupportedJupiterRoute)
+    }
+}
+
+/// Decodes the route and checks it swaps `expected.in_amount` from the source reserve into the
+/// destination reserve, signed by `expected.authority`. `accounts` are the keys of the route
+/// accounts, in instruction order.
+pub fn validate_route(
+    data: &[u8],
+    accounts: &[Pubkey],
+    expected: &ExpectedRoute,
+) -> Result<DecodedRoute> {
+    let route = decode_route(data)?;
+
+    // A platform fee would be taken out of the output before it reaches the reserve
+    require!(route.platform_fee_bps == 0, ErrorCode::UnsupportedJupiterRoute);
+    require!(
+        route.in_amount == expected.in_amount,
+        ErrorCode::JupiterRouteAmountMismatch
+    );
+
+    let key_at = |index: usize| -> Result<Pubkey> {
+        accounts
+            .get(index)
+            .copied()
+            .ok_or_else(|| error!(ErrorCode::InvalidJupiterRouteAccount))
+    };
+    let require_key = |index: usize, key: Pubkey| -> Result<()> {
+        require_keys_eq!(key_at(index)?, key, ErrorCode::InvalidJupiterRouteAccount);
+        Ok(())
+    };
+
+    match route.kind {
+        RouteKind::Route => {
+            require_key(ROUTE_AUTHORITY, expected.authority)?;
+            require_key(ROUTE_SOURCE_TOKEN_ACCOUNT, expected.source_token_account)?;
+            require_key(ROUTE_DESTINATION_TOKEN_ACCOUNT, expected.destination_token_account)?;
+            // When set, the output is sent there instead of the user destination account
+            let destination = key_at(ROUTE_OPTIONAL_DESTINATION_TOKEN_ACCOUNT)?;
+            require!(
+                destination == expected.program_id
+                    || destination == expected.destination_token_account,
+                ErrorCode::InvalidJupiterRouteAccount
+            );
+            require_key(ROUTE_DESTINATION_MINT, expected.destination_mint)?;
+        }
+        RouteKind::SharedAccountsRoute => {
+            require_key(SHARED_ROUTE_AUTHORITY, expected.authority)?;
+            require_key(SHARED_ROUTE_SOURCE_TOKEN_ACCOUNT, expected.source_token_account)?;
+            require_key(SHARED_ROUTE_DESTINATION_TOKEN_ACCOUNT, expected.destination_token_account)?;
+            require_key(SHARED_ROUTE_SOURCE_MINT, expected.source_mint)?;
+            require_key(SHARED_ROUTE_DESTINATION_MINT, expected.destination_mint)?;
+        }
+    }
+
+    Ok(route)
+}
+
+/// Balances of the token accounts among `accounts` owned by `owner` (the pool reserves),
+/// except `excluded`.
+pub fn reserve_balances(accounts: &[AccountInfo], owner: &Pubkey, excluded: &Pubkey) -> Vec<(Pubkey, u64)> {
+    let mut balances: Vec<(Pubkey, u64)> = Vec::new();
+    for account in accounts {
+        if account.key == excluded || balances.iter().any(|(key, _)| key == account.key) {
+            continue;
+        }
+        if let Some(token_account) = token_account(account) {
+            if token_account.owner == *owner {
+                balances.push((*account.key, token_account.amount));
+            }
+        }
+    }
+    balances
+}
+
+/// Fails when one of the `before` reserves holds less than its snapshot.
+pub fn require_reserves_not_decreased(accounts: &[AccountInfo], before: &[(Pubkey, u64)]) -> Result<()> {
+    for (key, balance_before) in before {
+        let balance_after = accounts
+            .iter()
+            .find(|account| account.key == key)
+            .and_then(token_account)
+            .map(|token_account| token_account.amount)
+            .unwrap_or(0);
+        require!(balance_after >= *balance_before, ErrorCode::ReserveBalanceDecreased);
+    }
+    Ok(())
+}
+
+fn token_account(account: &AccountInfo) -> Option<TokenAccount> {
+    if *account.owner != anchor_spl::token::ID && *account.owner != anchor_spl::token_2022::ID {
+        return None;
+    }
+    let data = account.try_borrow_data().ok()?;
+    TokenAccount::try_deserialize(&mut data.as_ref()).ok()
+}
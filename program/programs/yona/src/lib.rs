@@ -1,4 +1,10 @@
+// `#[program]` emits its IDL instructions (which call the deprecated `AccountInfo::realloc`) at the
+// crate root, outside the reach of an attribute on `mod yona`
+#![allow(deprecated)]
+
 use anchor_lang::prelude::*;
+use anchor_spl::token::spl_token::native_mint;
+use anchor_spl::token_interface::Mint;
 
 declare_id!("yonaMBw7KLYvQSspboB2GGAt5EsQqV28dZZasKhKGqC");
 declare_program!(jupiter_aggregator);
@@ -13,6 +19,7 @@ pub mod state;
 pub mod types;
 pub mod instructions;
 pub mod light;
+pub mod jupiter;
 
 pub use state::*;
 pub use types::*;
@@ -26,10 +33,38 @@ pub const ADMIN_PUBKEY: Option<Pubkey> = Some(pubkey!("qwqwHSpTkXF3zKF3eGfeMesnq
 pub mod yona {
     use super::*;
 
-    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
-        instructions::initialize::handler(ctx)
+    pub fn initialize(ctx: Context<Initialize>, height: u8, root_history_size: u16) -> Result<()> {
+        instructions::initialize::handler(ctx, height, root_history_size)
+    }
+
+    pub fn initialize_pool(
+        ctx: Context<InitializePool>,
+        pool_id: u16,
+        height: u8,
+        root_history_size: u16,
+    ) -> Result<()> {
+        instructions::initialize_pool::handler(ctx, pool_id, height, root_history_size)
+    }
+
+    pub fn rollover_tree(ctx: Context<RolloverTree>) -> Result<()> {
+        instructions::rollover_tree::handler(ctx)
+    }
+
+    pub fn initialize_mint_config(
+        ctx: Context<InitializeMintConfig>,
+        params: MintConfigParams,
+    ) -> Result<()> {
+        instructions::initialize_mint_config::handler(ctx, params)
+    }
+
+    pub fn update_mint_config(
+        ctx: Context<UpdateMintConfig>,
+        params: MintConfigParams,
+    ) -> Result<()> {
+        instructions::update_mint_config::handler(ctx, params)
     }
 
+    /// Deprecated, use `update_mint_config`
     pub fn update_deposit_limit(ctx: Context<UpdateDepositLimit>, new_limit: u64) -> Result<()> {
         instructions::update_deposit_limit::handler(ctx, new_limit)
     }
@@ -38,16 +73,87 @@ pub mod yona {
         ctx: Context<UpdateGlobalConfig>, 
         deposit_fee_rate: Option<u16>,
         withdrawal_fee_rate: Option<u16>,
-        fee_error_margin: Option<u16>
+        fee_error_margin: Option<u16>,
+        min_relayer_bond: Option<u64>
     ) -> Result<()> {
         instructions::update_global_config::handler(
             ctx, 
             deposit_fee_rate, 
             withdrawal_fee_rate, 
-            fee_error_margin
+            fee_error_margin,
+            min_relayer_bond
         )
     }
 
+    pub fn register_relayer(
+        ctx: Context<RegisterRelayer>,
+        fee_rate: u16,
+        fee_recipient: Pubkey,
+        bond: u64,
+    ) -> Result<()> {
+        instructions::register_relayer::handler(ctx, fee_rate, fee_recipient, bond)
+    }
+
+    pub fn set_aggregator_program(ctx: Context<SetAggregatorProgram>, enabled: bool) -> Result<()> {
+        instructions::set_aggregator_program::handler(ctx, enabled)
+    }
+
+    pub fn update_relayer(
+        ctx: Context<UpdateRelayer>,
+        fee_rate: Option<u16>,
+        fee_recipient: Option<Pubkey>,
+        bond_top_up: Option<u64>,
+    ) -> Result<()> {
+        instructions::update_relayer::handler(ctx, fee_rate, fee_recipient, bond_top_up)
+    }
+
+    pub fn request_relayer_deregistration(ctx: Context<RequestRelayerDeregistration>) -> Result<()> {
+        instructions::request_relayer_deregistration::handler(ctx)
+    }
+
+    pub fn deregister_relayer(ctx: Context<DeregisterRelayer>) -> Result<()> {
+        instructions::deregister_relayer::handler(ctx)
+    }
+
+    pub fn slash_relayer(ctx: Context<SlashRelayer>, amount: u64) -> Result<()> {
+        instructions::slash_relayer::handler(ctx, amount)
+    }
+
+    pub fn initialize_verifying_key(
+        ctx: Context<InitializeVerifyingKey>,
+        circuit: u8,
+        timelock_seconds: i64,
+        migration_window_seconds: i64,
+    ) -> Result<()> {
+        instructions::initialize_verifying_key::handler(
+            ctx,
+            circuit,
+            timelock_seconds,
+            migration_window_seconds,
+        )
+    }
+
+    pub fn stage_verifying_key(
+        ctx: Context<UpdateVerifyingKey>,
+        version: u32,
+        key_hash: [u8; 32],
+    ) -> Result<()> {
+        instructions::stage_verifying_key::handler(ctx, version, key_hash)
+    }
+
+    pub fn write_verifying_key(
+        ctx: Context<UpdateVerifyingKey>,
+        offset: u32,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        instructions::write_verifying_key::handler(ctx, offset, data)
+    }
+
+    pub fn activate_verifying_key(ctx: Context<UpdateVerifyingKey>) -> Result<()> {
+        instructions::activate_verifying_key::handler(ctx)
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub fn deposit<'info>(
         ctx: Context<'_, '_, '_, 'info, Deposit<'info>>, 
         proof: CompressedProof, 
@@ -70,6 +176,53 @@ pub mod yona {
         )
     }
 
+    #[allow(clippy::too_many_arguments)]
+    pub fn deposit_relayed<'info>(
+        ctx: Context<'_, '_, '_, 'info, DepositRelayed<'info>>,
+        proof: CompressedProof,
+        ext_data_minified: ExtDataMinified,
+        encrypted_output: Vec<u8>,
+        light_proof: ValidityProof,
+        nullifier0_address_tree_info: PackedAddressTreeInfo,
+        nullifier1_address_tree_info: PackedAddressTreeInfo,
+        output_state_tree_index: u8,
+    ) -> Result<()> {
+        instructions::deposit_relayed::handler(
+            ctx,
+            proof,
+            ext_data_minified,
+            encrypted_output,
+            light_proof,
+            nullifier0_address_tree_info,
+            nullifier1_address_tree_info,
+            output_state_tree_index,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn deposit_from_token_account<'info>(
+        ctx: Context<'_, '_, '_, 'info, DepositFromTokenAccount<'info>>,
+        proof: CompressedProof,
+        ext_data_minified: ExtDataMinified,
+        encrypted_output: Vec<u8>,
+        light_proof: ValidityProof,
+        nullifier0_address_tree_info: PackedAddressTreeInfo,
+        nullifier1_address_tree_info: PackedAddressTreeInfo,
+        output_state_tree_index: u8,
+    ) -> Result<()> {
+        instructions::deposit_from_token_account::handler(
+            ctx,
+            proof,
+            ext_data_minified,
+            encrypted_output,
+            light_proof,
+            nullifier0_address_tree_info,
+            nullifier1_address_tree_info,
+            output_state_tree_index,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub fn withdraw<'info>(
         ctx: Context<'_, '_, '_, 'info, Withdraw<'info>>,
         proof: CompressedProof,
@@ -92,6 +245,68 @@ pub mod yona {
         )    
     }
 
+    pub fn transact<'info>(
+        ctx: Context<'_, '_, '_, 'info, Transact<'info>>,
+        proof: CompressedProof,
+        encrypted_output: Vec<u8>,
+        light_proof: ValidityProof,
+        nullifier0_address_tree_info: PackedAddressTreeInfo,
+        nullifier1_address_tree_info: PackedAddressTreeInfo,
+        output_state_tree_index: u8,
+    ) -> Result<()> {
+        instructions::transact::handler(
+            ctx,
+            proof,
+            encrypted_output,
+            light_proof,
+            nullifier0_address_tree_info,
+            nullifier1_address_tree_info,
+            output_state_tree_index,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn transact_dual<'info>(
+        ctx: Context<'_, '_, '_, 'info, TransactDual<'info>>,
+        proof: CompressedProof,
+        ext_data_minified: DualExtDataMinified,
+        encrypted_output: Vec<u8>,
+        light_proof: ValidityProof,
+        nullifier0_address_tree_info: PackedAddressTreeInfo,
+        nullifier1_address_tree_info: PackedAddressTreeInfo,
+        output_state_tree_index: u8,
+    ) -> Result<()> {
+        instructions::transact_dual::handler(
+            ctx,
+            proof,
+            ext_data_minified,
+            encrypted_output,
+            light_proof,
+            nullifier0_address_tree_info,
+            nullifier1_address_tree_info,
+            output_state_tree_index,
+        )
+    }
+
+    pub fn transact4<'info>(
+        ctx: Context<'_, '_, '_, 'info, Transact4<'info>>,
+        proof: CompressedProof4,
+        encrypted_output: Vec<u8>,
+        light_proof: ValidityProof,
+        nullifier_address_tree_infos: [PackedAddressTreeInfo; 4],
+        output_state_tree_index: u8,
+    ) -> Result<()> {
+        instructions::transact4::handler(
+            ctx,
+            proof,
+            encrypted_output,
+            light_proof,
+            nullifier_address_tree_infos,
+            output_state_tree_index,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub fn swap<'info>(
         ctx: Context<'_, '_, '_, 'info, Swap<'info>>,
         proof: CompressedProof,
@@ -123,7 +338,7 @@ pub struct Initialize<'info> {
         init,
         payer = authority,
         space = 8 + std::mem::size_of::<MerkleTreeAccount>(),
-        seeds = [b"merkle_tree"],
+        seeds = [b"merkle_tree", DEFAULT_POOL_ID.to_le_bytes().as_ref(), INITIAL_TREE_INDEX.to_le_bytes().as_ref()],
         bump
     )]
     pub tree_account: AccountLoader<'info, MerkleTreeAccount>,
@@ -132,7 +347,7 @@ pub struct Initialize<'info> {
         init,
         payer = authority,
         space = 8 + std::mem::size_of::<TreeTokenAccount>(),
-        seeds = [b"tree_token"],
+        seeds = [b"tree_token", DEFAULT_POOL_ID.to_le_bytes().as_ref()],
         bump
     )]
     pub tree_token_account: Account<'info, TreeTokenAccount>,
@@ -141,7 +356,7 @@ pub struct Initialize<'info> {
         init,
         payer = authority,
         space = 8 + std::mem::size_of::<GlobalConfig>(),
-        seeds = [b"global_config"],
+        seeds = [b"global_config", DEFAULT_POOL_ID.to_le_bytes().as_ref()],
         bump
     )]
     pub global_config: Account<'info, GlobalConfig>,
@@ -153,15 +368,145 @@ pub struct Initialize<'info> {
 }
 
 #[derive(Accounts)]
-pub struct UpdateDepositLimit<'info> {
+#[instruction(pool_id: u16)]
+pub struct InitializePool<'info> {
     #[account(
-        mut,
-        seeds = [b"merkle_tree"],
-        bump = tree_account.load()?.bump,
-        has_one = authority @ ErrorCode::Unauthorized
+        init,
+        payer = authority,
+        space = 8 + std::mem::size_of::<MerkleTreeAccount>(),
+        seeds = [b"merkle_tree", pool_id.to_le_bytes().as_ref(), INITIAL_TREE_INDEX.to_le_bytes().as_ref()],
+        bump
     )]
     pub tree_account: AccountLoader<'info, MerkleTreeAccount>,
     
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + std::mem::size_of::<TreeTokenAccount>(),
+        seeds = [b"tree_token", pool_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub tree_token_account: Account<'info, TreeTokenAccount>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + std::mem::size_of::<GlobalConfig>(),
+        seeds = [b"global_config", pool_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RolloverTree<'info> {
+    #[account(
+        mut,
+        seeds = [b"global_config", global_config.pool_id.to_le_bytes().as_ref()],
+        bump = global_config.bump
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+
+    /// The active tree, retired by this instruction
+    #[account(
+        seeds = [
+            b"merkle_tree",
+            global_config.pool_id.to_le_bytes().as_ref(),
+            global_config.active_tree_index.to_le_bytes().as_ref()
+        ],
+        bump = tree_account.load()?.bump
+    )]
+    pub tree_account: AccountLoader<'info, MerkleTreeAccount>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + std::mem::size_of::<MerkleTreeAccount>(),
+        seeds = [
+            b"merkle_tree",
+            global_config.pool_id.to_le_bytes().as_ref(),
+            global_config.active_tree_index.wrapping_add(1).to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub new_tree_account: AccountLoader<'info, MerkleTreeAccount>,
+
+    /// Anyone can roll over a full tree, the pool authority can do it at any time
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeMintConfig<'info> {
+    #[account(
+        seeds = [b"global_config", global_config.pool_id.to_le_bytes().as_ref()],
+        bump = global_config.bump,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + std::mem::size_of::<MintConfig>(),
+        seeds = [b"mint_config", global_config.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+
+    /// The authority account that can configure mints
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateMintConfig<'info> {
+    #[account(
+        seeds = [b"global_config", global_config.pool_id.to_le_bytes().as_ref()],
+        bump = global_config.bump,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"mint_config", global_config.key().as_ref(), mint_config.mint.as_ref()],
+        bump = mint_config.bump
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+
+    /// The authority account that can configure mints
+    pub authority: Signer<'info>,
+}
+
+/// Deprecated: the lamport deposit limit maps to the wrapped SOL mint config of the default pool
+#[derive(Accounts)]
+pub struct UpdateDepositLimit<'info> {
+    #[account(
+        seeds = [b"global_config", DEFAULT_POOL_ID.to_le_bytes().as_ref()],
+        bump = global_config.bump,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"mint_config", global_config.key().as_ref(), native_mint::ID.as_ref()],
+        bump = mint_config.bump
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+
     /// The authority account that can update the deposit limit
     pub authority: Signer<'info>,
 }
@@ -170,7 +515,7 @@ pub struct UpdateDepositLimit<'info> {
 pub struct UpdateGlobalConfig<'info> {
     #[account(
         mut,
-        seeds = [b"global_config"],
+        seeds = [b"global_config", global_config.pool_id.to_le_bytes().as_ref()],
         bump = global_config.bump,
         has_one = authority @ ErrorCode::Unauthorized
     )]
@@ -179,3 +524,158 @@ pub struct UpdateGlobalConfig<'info> {
     /// The authority account that can update the global config
     pub authority: Signer<'info>,
 }
+
+#[derive(Accounts)]
+pub struct SetAggregatorProgram<'info> {
+    #[account(
+        seeds = [b"global_config", global_config.pool_id.to_le_bytes().as_ref()],
+        bump = global_config.bump,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+
+    /// CHECK: aggregator program allowed as `swap` CPI target. Only Jupiter, `swap` decodes the
+    /// route with its IDL
+    #[account(executable, address = jupiter_aggregator::ID @ ErrorCode::AggregatorNotAllowed)]
+    pub aggregator_program: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + std::mem::size_of::<AggregatorConfig>(),
+        seeds = [b"aggregator_config", global_config.key().as_ref(), aggregator_program.key().as_ref()],
+        bump
+    )]
+    pub aggregator_config: Account<'info, AggregatorConfig>,
+
+    /// The authority account that can configure aggregators
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterRelayer<'info> {
+    #[account(
+        seeds = [b"global_config", global_config.pool_id.to_le_bytes().as_ref()],
+        bump = global_config.bump
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + std::mem::size_of::<RelayerAccount>(),
+        seeds = [b"relayer", global_config.key().as_ref(), authority.key().as_ref()],
+        bump
+    )]
+    pub relayer_account: Account<'info, RelayerAccount>,
+
+    /// Signer of the relayed transactions, pays the rent and the bond
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateRelayer<'info> {
+    #[account(
+        mut,
+        seeds = [b"relayer", relayer_account.global_config.as_ref(), authority.key().as_ref()],
+        bump = relayer_account.bump,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
+    pub relayer_account: Account<'info, RelayerAccount>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RequestRelayerDeregistration<'info> {
+    #[account(
+        mut,
+        seeds = [b"relayer", relayer_account.global_config.as_ref(), authority.key().as_ref()],
+        bump = relayer_account.bump,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
+    pub relayer_account: Account<'info, RelayerAccount>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DeregisterRelayer<'info> {
+    /// Closed to the authority, which gets back the rent and the bond
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"relayer", relayer_account.global_config.as_ref(), authority.key().as_ref()],
+        bump = relayer_account.bump,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
+    pub relayer_account: Account<'info, RelayerAccount>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SlashRelayer<'info> {
+    #[account(
+        seeds = [b"global_config", global_config.pool_id.to_le_bytes().as_ref()],
+        bump = global_config.bump,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"relayer", global_config.key().as_ref(), relayer_account.authority.as_ref()],
+        bump = relayer_account.bump
+    )]
+    pub relayer_account: Account<'info, RelayerAccount>,
+
+    /// CHECK: receives the slashed lamports, chosen by the pool authority
+    #[account(mut)]
+    pub destination: UncheckedAccount<'info>,
+
+    /// The pool authority
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(circuit: u8)]
+pub struct InitializeVerifyingKey<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + std::mem::size_of::<VerifyingKeyAccount>(),
+        seeds = [b"verifying_key", circuit.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub verifying_key: AccountLoader<'info, VerifyingKeyAccount>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateVerifyingKey<'info> {
+    #[account(
+        mut,
+        seeds = [b"verifying_key", verifying_key.load()?.circuit.to_le_bytes().as_ref()],
+        bump = verifying_key.load()?.bump,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
+    pub verifying_key: AccountLoader<'info, VerifyingKeyAccount>,
+
+    /// The authority account that can upgrade the verifying key
+    pub authority: Signer<'info>,
+}
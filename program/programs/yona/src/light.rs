@@ -47,81 +47,88 @@ pub fn create_light_nullifiers<'info>(
     nullifier1_address_tree_info: PackedAddressTreeInfo,
     output_state_tree_index: u8,
 ) -> Result<()> {
-    
-    let light_cpi_accounts = CpiAccounts::new(
+    create_light_nullifier_batch(
         payer,
         remaining_accounts,
-        LIGHT_CPI_SIGNER,
-    );
+        input_nullifiers,
+        light_proof,
+        &[nullifier0_address_tree_info, nullifier1_address_tree_info],
+        output_state_tree_index,
+    )
+}
 
-    // Get and validate address tree pubkey for nullifier 0
-    let nullifier0_tree_pubkey = nullifier0_address_tree_info
-        .get_tree_pubkey(&light_cpi_accounts)
-        .map_err(|_| ErrorCode::InvalidNullifierAddress)?;
-    
-    // Validate address tree is the canonical one (same tree = same address space)
+/// Creates one nullifier compressed account per input nullifier, for circuits of any arity.
+/// All addresses are created in a single Light System Program CPI, so `light_proof` must be
+/// a validity proof over all of them.
+/// 
+/// The address space is shared by every circuit: a note spent through a 4-input proof
+/// can't be spent again through a 2-input proof.
+/// 
+/// # Arguments
+/// * `payer` - The account that pays for the transaction
+/// * `remaining_accounts` - Remaining accounts containing Light Protocol state trees
+/// * `input_nullifiers` - Nullifier hashes from the ZK proof
+/// * `light_proof` - Light Protocol validity proof
+/// * `address_tree_infos` - Packed address tree info, one per nullifier
+/// * `output_state_tree_index` - Index of the output state tree
+pub fn create_light_nullifier_batch<'info>(
+    payer: &AccountInfo<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+    input_nullifiers: &[[u8; 32]],
+    light_proof: ValidityProof,
+    address_tree_infos: &[PackedAddressTreeInfo],
+    output_state_tree_index: u8,
+) -> Result<()> {
     require!(
-        nullifier0_tree_pubkey == CANONICAL_ADDRESS_TREE,
-        ErrorCode::InvalidAddressTree
+        input_nullifiers.len() == address_tree_infos.len(),
+        ErrorCode::InvalidNullifierAddress
     );
 
-    // Get and validate address tree pubkey for nullifier 1
-    let nullifier1_tree_pubkey = nullifier1_address_tree_info
-        .get_tree_pubkey(&light_cpi_accounts)
-        .map_err(|_| ErrorCode::InvalidNullifierAddress)?;
-    
-    // Validate address tree is the canonical one
-    require!(
-        nullifier1_tree_pubkey == CANONICAL_ADDRESS_TREE,
-        ErrorCode::InvalidAddressTree
+    let light_cpi_accounts = CpiAccounts::new(
+        payer,
+        remaining_accounts,
+        LIGHT_CPI_SIGNER,
     );
 
-    // Derive address for nullifier 0
-    let (nullifier0_address, nullifier0_seed) = derive_address(
-        &[b"nullifier", input_nullifiers[0].as_ref()],
-        &nullifier0_tree_pubkey,
-        &crate::ID,
-    );
+    let mut cpi = LightSystemProgramCpi::new_cpi(LIGHT_CPI_SIGNER, light_proof);
+    let mut new_address_params = Vec::with_capacity(input_nullifiers.len());
 
-    // Derive address for nullifier 1
-    let (nullifier1_address, nullifier1_seed) = derive_address(
-        &[b"nullifier", input_nullifiers[1].as_ref()],
-        &nullifier1_tree_pubkey,
-        &crate::ID,
-    );
+    for (nullifier, address_tree_info) in input_nullifiers.iter().zip(address_tree_infos) {
+        // Get and validate address tree pubkey for this nullifier
+        let tree_pubkey = address_tree_info
+            .get_tree_pubkey(&light_cpi_accounts)
+            .map_err(|_| ErrorCode::InvalidNullifierAddress)?;
 
-    // Create compressed account for nullifier 0
-    let mut nullifier0_account = LightAccount::<NullifierCompressedAccount>::new_init(
-        &crate::ID,
-        Some(nullifier0_address),
-        output_state_tree_index,
-    );
-    nullifier0_account.nullifier = input_nullifiers[0];
+        // Validate address tree is the canonical one (same tree = same address space)
+        require!(
+            tree_pubkey == CANONICAL_ADDRESS_TREE,
+            ErrorCode::InvalidAddressTree
+        );
 
-    // Create compressed account for nullifier 1
-    let mut nullifier1_account = LightAccount::<NullifierCompressedAccount>::new_init(
-        &crate::ID,
-        Some(nullifier1_address),
-        output_state_tree_index,
-    );
-    nullifier1_account.nullifier = input_nullifiers[1];
+        let (address, seed) = derive_address(
+            &[b"nullifier", nullifier.as_ref()],
+            &tree_pubkey,
+            &crate::ID,
+        );
 
-    // CPI to Light System Program to create both nullifier compressed accounts
-    // If these addresses already exist, the proof verification will fail
-    let new_address_params0 = nullifier0_address_tree_info.into_new_address_params_packed(nullifier0_seed);
-    let new_address_params1 = nullifier1_address_tree_info.into_new_address_params_packed(nullifier1_seed);
+        let mut nullifier_account = LightAccount::<NullifierCompressedAccount>::new_init(
+            &crate::ID,
+            Some(address),
+            output_state_tree_index,
+        );
+        nullifier_account.nullifier = *nullifier;
 
-    LightSystemProgramCpi::new_cpi(LIGHT_CPI_SIGNER, light_proof)
-        .with_light_account(nullifier0_account)
-        .map_err(|_| ErrorCode::LightProtocolError)?
-        .with_light_account(nullifier1_account)
-        .map_err(|_| ErrorCode::LightProtocolError)?
-        .with_new_addresses(&[new_address_params0, new_address_params1])
+        cpi = cpi
+            .with_light_account(nullifier_account)
+            .map_err(|_| ErrorCode::LightProtocolError)?;
+        new_address_params.push(address_tree_info.into_new_address_params_packed(seed));
+    }
+
+    // CPI to Light System Program to create all nullifier compressed accounts
+    // If these addresses already exist, the proof verification will fail
+    cpi.with_new_addresses(&new_address_params)
         .invoke(light_cpi_accounts)
         .map_err(|_| ErrorCode::LightProtocolError)?;
 
-    
     Ok(())
 }
-
-
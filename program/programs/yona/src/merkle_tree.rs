@@ -1,6 +1,6 @@
 // Adapted from https://github.com/Lightprotocol/light-protocol/blob/b2a236409bb7797615d217fbf4fff498c852d25e/sparse-merkle-tree/src/merkle_tree.rs
 use light_hasher::Hasher;
-use crate::{MerkleTreeAccount, ErrorCode};
+use crate::{MerkleTreeAccount, ErrorCode, MERKLE_TREE_HEIGHT};
 use anchor_lang::prelude::*;
 
 pub struct MerkleTree;
@@ -11,12 +11,10 @@ impl MerkleTree {
         
         // Initialize empty subtrees
         let zero_bytes = H::zero_bytes();
-        for i in 0..height {
-            tree_account.subtrees[i] = zero_bytes[i];
-        }
+        tree_account.subtrees[..height].copy_from_slice(&zero_bytes[..height]);
 
-        // Set initial root
-        let initial_root = H::zero_bytes()[height];
+        // Set initial root, roots always span the circuit's levels
+        let initial_root = H::zero_bytes()[MERKLE_TREE_HEIGHT as usize];
         tree_account.root = initial_root;
         tree_account.root_history[0] = initial_root;
         
@@ -44,24 +42,32 @@ impl MerkleTree {
         let mut right;
         let mut proof: Vec<[u8; 32]> = vec![[0u8; 32]; height];
 
-        for i in 0..height {
+        for (i, sibling) in proof.iter_mut().enumerate() {
             let subtree = &mut tree_account.subtrees[i];
             let zero_byte = H::zero_bytes()[i];
             
-            if current_index % 2 == 0 {
+            if current_index.is_multiple_of(2) {
                 left = current_level_hash;
                 right = zero_byte;
                 *subtree = current_level_hash;
-                proof[i] = right;
+                *sibling = right;
             } else {
                 left = *subtree;
                 right = current_level_hash;
-                proof[i] = left;
+                *sibling = left;
             }
             current_level_hash = H::hashv(&[&left, &right])
-                .ok_or(ErrorCode::ArithmeticOverflow)?;
+                .map_err(|_| ErrorCode::ArithmeticOverflow)?;
             current_index /= 2;
         }
+
+        // A shorter tree is the leftmost subtree of a MERKLE_TREE_HEIGHT tree, the levels above
+        // it only hold zero bytes
+        for zero_byte in &H::zero_bytes()[height..MERKLE_TREE_HEIGHT as usize] {
+            current_level_hash = H::hashv(&[&current_level_hash, zero_byte])
+                .map_err(|_| ErrorCode::ArithmeticOverflow)?;
+            proof.push(*zero_byte);
+        }
         
         tree_account.root = current_level_hash;
         tree_account.next_index = tree_account.next_index
@@ -77,6 +83,12 @@ impl MerkleTree {
         Ok(proof)
     }
 
+    /// True when the next `append` pair would not fit, i.e. the tree has to be rolled over
+    pub fn is_full(tree_account: &MerkleTreeAccount) -> bool {
+        let max_capacity = 1u64 << tree_account.height;
+        tree_account.next_index.saturating_add(2) > max_capacity
+    }
+
     pub fn is_known_root(tree_account: &MerkleTreeAccount, root: [u8; 32]) -> bool {
         if root == [0u8; 32] {
             return false;
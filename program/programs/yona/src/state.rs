@@ -1,21 +1,140 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use crate::types::MintConfigParams;
+use crate::groth16::Groth16Verifyingkey;
+use crate::ErrorCode;
 
 // Constants
+// Maximum tree height, the transaction circuit is compiled for 26 levels
 pub const MERKLE_TREE_HEIGHT: u8 = 26;
-pub const ROOT_HISTORY_SIZE: usize = 100;
+// Maximum root history size, MerkleTreeAccount arrays are sized for it
+// (the account must stay below the 10KB CPI allocation limit)
+pub const MAX_ROOT_HISTORY_SIZE: usize = 256;
+pub const DEFAULT_ROOT_HISTORY_SIZE: u16 = 100;
+// Pool created by `initialize`, additional pools are created with `initialize_pool`
+pub const DEFAULT_POOL_ID: u16 = 0;
+// Index of the first tree of a pool, successors are created by `rollover_tree`
+pub const INITIAL_TREE_INDEX: u32 = 0;
+// Circuits with a verifying key account, PDA `[b"verifying_key", circuit]`
+pub const CIRCUIT_TRANSACTION2: u8 = 0; // 2 inputs / 2 outputs, 10 public inputs
+pub const CIRCUIT_TRANSACTION4: u8 = 1; // 4 inputs / 2 outputs, 12 public inputs
+// Largest number of public inputs of a supported circuit, vk_ic holds one more point
+pub const MAX_VK_PUBLIC_INPUTS: usize = 12;
+// Size of a key as written by `write_verifying_key`: alpha_g1 | beta_g2 | gamma_g2 | delta_g2 | ic
+pub const VK_DATA_LEN: usize = 64 + 3 * 128 + (MAX_VK_PUBLIC_INPUTS + 1) * 64;
+// Delay between `request_relayer_deregistration` and `deregister_relayer`, a relayer cannot
+// pull its bond right after relaying and can still be slashed
+pub const RELAYER_UNBONDING_SECONDS: i64 = 2 * 24 * 60 * 60;
+
+/// Number of public inputs of a circuit, None for unknown circuits
+pub fn circuit_public_inputs(circuit: u8) -> Option<u32> {
+    match circuit {
+        CIRCUIT_TRANSACTION2 => Some(10),
+        CIRCUIT_TRANSACTION4 => Some(12),
+        _ => None,
+    }
+}
 
 #[account]
 pub struct TreeTokenAccount {
     pub authority: Pubkey,
+    pub pool_id: u16,
     pub bump: u8,
 }
 
 #[account]
 pub struct GlobalConfig {
     pub authority: Pubkey,
+    pub pool_id: u16,
+    // Tree that receives new commitments, older trees only serve roots for spending
+    pub active_tree_index: u32,
     pub deposit_fee_rate: u16,    // basis points (0-10000, where 10000 = 100%)
     pub withdrawal_fee_rate: u16, // basis points (0-10000, where 10000 = 100%)
     pub fee_error_margin: u16,    // basis points (0-10000, where 10000 = 100%)
+    pub min_relayer_bond: u64,    // lamports a relayer must bond to relay transactions
+    pub bump: u8,
+}
+
+/// Per-mint settings of a pool, PDA `[b"mint_config", global_config, mint]`.
+/// Mints without a config can't be deposited, withdrawn or swapped.
+#[account]
+pub struct MintConfig {
+    pub global_config: Pubkey,
+    pub mint: Pubkey,
+    // Disabled mints can still be withdrawn (and swapped from) so existing notes stay redeemable
+    pub enabled: bool,
+    pub min_deposit_amount: u64, // in base units of the mint
+    pub max_deposit_amount: u64, // in base units of the mint
+    pub deposit_fee_rate: Option<u16>,    // overrides GlobalConfig.deposit_fee_rate when set
+    pub withdrawal_fee_rate: Option<u16>, // overrides GlobalConfig.withdrawal_fee_rate when set
+    pub bump: u8,
+}
+
+impl MintConfig {
+    pub fn apply(&mut self, params: MintConfigParams) -> Result<()> {
+        require!(
+            params.min_deposit_amount <= params.max_deposit_amount,
+            ErrorCode::InvalidMintConfig
+        );
+        for rate in [params.deposit_fee_rate, params.withdrawal_fee_rate].into_iter().flatten() {
+            require!(rate <= 10000, ErrorCode::InvalidFeeRate);
+        }
+
+        self.enabled = params.enabled;
+        self.min_deposit_amount = params.min_deposit_amount;
+        self.max_deposit_amount = params.max_deposit_amount;
+        self.deposit_fee_rate = params.deposit_fee_rate;
+        self.withdrawal_fee_rate = params.withdrawal_fee_rate;
+        Ok(())
+    }
+
+    pub fn deposit_fee_rate(&self, global_config: &GlobalConfig) -> u16 {
+        self.deposit_fee_rate.unwrap_or(global_config.deposit_fee_rate)
+    }
+
+    pub fn withdrawal_fee_rate(&self, global_config: &GlobalConfig) -> u16 {
+        self.withdrawal_fee_rate.unwrap_or(global_config.withdrawal_fee_rate)
+    }
+}
+
+/// Registered relayer of a pool, PDA `[b"relayer", global_config, authority]`.
+/// The bond is held as lamports of this account on top of its rent, the pool authority can
+/// slash it and what is left is returned when the relayer deregisters.
+#[account]
+pub struct RelayerAccount {
+    pub global_config: Pubkey,
+    // Signer of the transactions relayed by this relayer
+    pub authority: Pubkey,
+    // Owner of the token accounts receiving the fees of relayed transactions
+    pub fee_recipient: Pubkey,
+    pub fee_rate: u16, // advertised fee, basis points (0-10000, where 10000 = 100%)
+    pub bond: u64,     // lamports
+    pub bump: u8,
+    pub unbonded_at: i64, // bond can be withdrawn from then on, 0 while the relayer is active
+}
+
+impl RelayerAccount {
+    /// Highest fee the relayer may take on `amount`, at its advertised fee rate
+    pub fn max_fee(&self, amount: u64) -> Result<u64> {
+        Ok((amount as u128)
+            .checked_mul(self.fee_rate as u128)
+            .ok_or(ErrorCode::ArithmeticOverflow)?
+            .checked_div(10000)
+            .ok_or(ErrorCode::ArithmeticOverflow)? as u64)
+    }
+
+    pub fn is_deregistering(&self) -> bool {
+        self.unbonded_at != 0
+    }
+}
+
+/// Aggregator program `swap` may CPI into, PDA `[b"aggregator_config", global_config, program_id]`.
+/// Only the Jupiter aggregator can be set, its route instructions are decoded before the CPI.
+#[account]
+pub struct AggregatorConfig {
+    pub global_config: Pubkey,
+    pub program_id: Pubkey,
+    pub enabled: bool,
     pub bump: u8,
 }
 
@@ -32,13 +151,148 @@ pub struct MerkleTreeAccount {
     pub next_index: u64,
     pub subtrees: [[u8; 32]; MERKLE_TREE_HEIGHT as usize],
     pub root: [u8; 32],
-    pub root_history: [[u8; 32]; ROOT_HISTORY_SIZE],
+    pub root_history: [[u8; 32]; MAX_ROOT_HISTORY_SIZE],
     pub root_index: u64,
-    pub max_deposit_amount: u64,
+    pub tree_index: u32,
+    pub root_history_size: u16,
+    pub pool_id: u16,
     pub height: u8,
-    pub root_history_size: u8,
     pub bump: u8,
-    // The pub _padding: [u8; 5] is needed because of the #[account(zero_copy)] attribute.
-    pub _padding: [u8; 5],
+    // The pub _padding: [u8; 6] is needed because of the #[account(zero_copy)] attribute.
+    pub _padding: [u8; 6],
+}
+
+/// Groth16 verifying key stored in a `VerifyingKeyAccount` slot.
+/// `version == 0` marks an empty slot.
+#[zero_copy]
+#[derive(Debug)]
+pub struct StoredVerifyingKey {
+    pub version: u32,
+    pub nr_pubinputs: u32,
+    pub vk_alpha_g1: [u8; 64],
+    pub vk_beta_g2: [u8; 128],
+    pub vk_gamma_g2: [u8; 128],
+    pub vk_delta_g2: [u8; 128],
+    pub vk_ic: [[u8; 64]; MAX_VK_PUBLIC_INPUTS + 1],
+}
+
+impl StoredVerifyingKey {
+    pub const EMPTY: Self = Self {
+        version: 0,
+        nr_pubinputs: 0,
+        vk_alpha_g1: [0; 64],
+        vk_beta_g2: [0; 128],
+        vk_gamma_g2: [0; 128],
+        vk_delta_g2: [0; 128],
+        vk_ic: [[0; 64]; MAX_VK_PUBLIC_INPUTS + 1],
+    };
+
+    pub fn is_set(&self) -> bool {
+        self.version != 0
+    }
+
+    /// Length of the key bytes for `nr_pubinputs` public inputs
+    pub fn data_len(&self) -> usize {
+        64 + 3 * 128 + (self.nr_pubinputs as usize + 1) * 64
+    }
+
+    /// sha256 of the key bytes (alpha_g1 | beta_g2 | gamma_g2 | delta_g2 | ic)
+    pub fn key_hash(&self) -> [u8; 32] {
+        let mut chunks = vec![
+            self.vk_alpha_g1.as_slice(),
+            self.vk_beta_g2.as_slice(),
+            self.vk_gamma_g2.as_slice(),
+            self.vk_delta_g2.as_slice(),
+        ];
+        chunks.extend(
+            self.vk_ic[..self.nr_pubinputs as usize + 1]
+                .iter()
+                .map(|point| point.as_slice()),
+        );
+        hashv(&chunks).to_bytes()
+    }
+
+    /// Whether one of the points is the identity (all zero bytes), e.g. a chunk was never written.
+    /// A key with an identity point accepts forged proofs.
+    pub fn has_identity_point(&self) -> bool {
+        let is_zero = |point: &[u8]| point.iter().all(|byte| *byte == 0);
+        is_zero(&self.vk_alpha_g1)
+            || is_zero(&self.vk_beta_g2)
+            || is_zero(&self.vk_gamma_g2)
+            || is_zero(&self.vk_delta_g2)
+            || self.vk_ic[..self.nr_pubinputs as usize + 1]
+                .iter()
+                .any(|point| is_zero(point))
+    }
+
+    pub fn as_groth16(&self) -> Groth16Verifyingkey<'_> {
+        Groth16Verifyingkey {
+            nr_pubinputs: self.nr_pubinputs as usize,
+            vk_alpha_g1: self.vk_alpha_g1,
+            vk_beta_g2: self.vk_beta_g2,
+            vk_gamma_g2: self.vk_gamma_g2,
+            vk_delta_g2: self.vk_delta_g2,
+            vk_ic: &self.vk_ic[..self.nr_pubinputs as usize + 1],
+        }
+    }
+
+    /// Writes `data` at `offset` of the key bytes (alpha_g1 | beta_g2 | gamma_g2 | delta_g2 | ic)
+    pub fn write(&mut self, offset: usize, data: &[u8]) -> Result<()> {
+        let end = offset.checked_add(data.len()).ok_or(ErrorCode::ArithmeticOverflow)?;
+        require!(end <= self.data_len(), ErrorCode::InvalidVerifyingKey);
+
+        for (i, byte) in data.iter().enumerate() {
+            let pos = offset + i;
+            let slot = match pos {
+                0..=63 => &mut self.vk_alpha_g1[pos],
+                64..=191 => &mut self.vk_beta_g2[pos - 64],
+                192..=319 => &mut self.vk_gamma_g2[pos - 192],
+                320..=447 => &mut self.vk_delta_g2[pos - 320],
+                _ => &mut self.vk_ic[(pos - 448) / 64][(pos - 448) % 64],
+            };
+            *slot = *byte;
+        }
+        Ok(())
+    }
+}
+
+/// Versioned verifying key of a circuit, PDA `[b"verifying_key", circuit]`.
+///
+/// Keys are program-wide: the PDA is not scoped by pool, every pool verifies its proofs with
+/// the same account, so an activation switches all pools at once.
+///
+/// New keys are staged in `pending` with the hash of their bytes, written in chunks and
+/// activated by the authority once `pending_activation_ts` is reached (every write restarts
+/// the timelock) and the written bytes match `pending_key_hash`.
+/// On activation the current key becomes `previous` and keeps verifying proofs until
+/// `previous_valid_until`, so proofs generated before the upgrade can still land.
+#[account(zero_copy)]
+pub struct VerifyingKeyAccount {
+    pub authority: Pubkey,
+    pub current: StoredVerifyingKey,
+    pub previous: StoredVerifyingKey,
+    pub pending: StoredVerifyingKey,
+    pub pending_activation_ts: i64,
+    pub previous_valid_until: i64,
+    pub timelock_seconds: i64,         // 0 = pending key can be activated right away
+    pub migration_window_seconds: i64, // 0 = previous key is rejected right after activation
+    pub pending_key_hash: [u8; 32],    // sha256 of the pending key bytes, set by `stage_verifying_key`
+    pub circuit: u8,
+    pub bump: u8,
+    // The pub _padding: [u8; 6] is needed because of the #[account(zero_copy)] attribute.
+    pub _padding: [u8; 6],
 }
 
+impl VerifyingKeyAccount {
+    /// Keys a proof can be verified against at `now`: the current key, then the previous
+    /// key while the migration window is open.
+    pub fn accepted_keys(&self, now: i64) -> impl Iterator<Item = Groth16Verifyingkey<'_>> {
+        let previous = (self.previous.is_set() && now <= self.previous_valid_until)
+            .then(|| self.previous.as_groth16());
+        self.current
+            .is_set()
+            .then(|| self.current.as_groth16())
+            .into_iter()
+            .chain(previous)
+    }
+}
@@ -2,12 +2,30 @@ use anchor_lang::prelude::*;
 
 #[event]
 pub struct CommitmentData {
+    pub pool_id: u16,
+    pub tree_index: u32,
     pub index: u64,
     pub commitment0: [u8; 32],
     pub commitment1: [u8; 32],
     pub encrypted_output: Vec<u8>,
 }
 
+#[event]
+pub struct TreeRolloverEvent {
+    pub pool_id: u16,
+    pub previous_tree_index: u32,
+    pub tree_index: u32,
+    pub tree_account: Pubkey,
+}
+
+#[event]
+pub struct VerifyingKeyActivatedEvent {
+    pub circuit: u8,
+    pub version: u32,
+    pub previous_version: u32,
+    pub previous_valid_until: i64,
+}
+
 #[event]
 pub struct DepositEvent {
     pub input_mint: Pubkey,
@@ -20,6 +38,14 @@ pub struct WithdrawEvent {
     pub amount: u64,
 }
 
+#[event]
+pub struct DualTransactEvent {
+    pub mint0: Pubkey,
+    pub ext_amount0: i64,
+    pub mint1: Pubkey,
+    pub ext_amount1: i64,
+}
+
 #[event]
 pub struct SwapEvent {
     pub input_mint: Pubkey,
@@ -29,6 +55,16 @@ pub struct SwapEvent {
 }
 
 
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct MintConfigParams {
+    pub enabled: bool,
+    pub min_deposit_amount: u64,
+    pub max_deposit_amount: u64,
+    pub deposit_fee_rate: Option<u16>,
+    pub withdrawal_fee_rate: Option<u16>,
+}
+
+
 // all public inputs needs to be in big endian format
 // Compressed proof format - saves transaction size
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
@@ -44,6 +80,21 @@ pub struct CompressedProof {
     pub output_commitments: [[u8; 32]; 2],
 }
 
+// Proof of the 4-input / 2-output circuit (transaction4), same layout as CompressedProof
+// with 4 input nullifiers. Used to consolidate small notes in a single transaction.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CompressedProof4 {
+    pub proof_a: [u8; 32],
+    pub proof_b: [u8; 64],
+    pub proof_c: [u8; 32],
+    pub root: [u8; 32],
+    pub public_amount0: [u8; 32],
+    pub public_amount1: [u8; 32],
+    pub ext_data_hash: [u8; 32],
+    pub input_nullifiers: [[u8; 32]; 4],
+    pub output_commitments: [[u8; 32]; 2],
+}
+
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
 pub struct ExtData {
@@ -60,6 +111,15 @@ pub struct ExtDataMinified {
     pub fee: u64,
 }
 
+/// Ext data of `transact_dual`: amount and fee per mint (index 0 and 1 follow mint_address0/1)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct DualExtDataMinified {
+    pub ext_amount0: i64,
+    pub fee0: u64,
+    pub ext_amount1: i64,
+    pub fee1: u64,
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
 pub struct SwapExtDataMinified {
     pub ext_amount: i64,
@@ -68,15 +128,17 @@ pub struct SwapExtDataMinified {
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
-pub struct SwapExtData { // TODO ADD JUP DATA
+pub struct SwapExtData {
     pub ext_amount: i64,
     pub ext_min_amount_out: i64,
     pub fee: u64,
     pub fee_recipient: Pubkey,
+    /// Hash of the Jupiter route, see `utils::calculate_route_hash`
+    pub route_hash: [u8; 32],
 }
 
 impl ExtData {
-    pub fn from_minified<'info>(
+    pub fn from_minified(
         recipient: &Pubkey,
         fee_recipient: &Pubkey,
         minified: ExtDataMinified,
@@ -91,8 +153,9 @@ impl ExtData {
 }
 
 impl SwapExtData {
-    pub fn from_minified<'info>(
+    pub fn from_minified(
         fee_recipient: &Pubkey, 
+        route_hash: [u8; 32],
         minified: SwapExtDataMinified,
     ) -> Self {
         Self {
@@ -100,6 +163,7 @@ impl SwapExtData {
             ext_min_amount_out: minified.ext_min_amount_out,
             fee: minified.fee,
             fee_recipient: *fee_recipient,  
+            route_hash,
         }
     }
 }
@@ -0,0 +1,135 @@
+//! Fetching and decoding the program accounts the preflight runs against.
+use anchor_lang::{AccountDeserialize, Discriminator};
+use solana_sdk::account::Account;
+use solana_sdk::clock::Clock;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::sysvar;
+use yona::{GlobalConfig, MerkleTreeAccount, RelayerAccount, VerifyingKeyAccount};
+use yona_client::pda::{
+    find_global_config_address, find_merkle_tree_address, find_relayer_address,
+    find_verifying_key_address,
+};
+use yona_client::preflight::PreflightState;
+
+use crate::cluster::Cluster;
+use crate::error::RelayerError;
+
+pub async fn fetch_account<C: Cluster>(cluster: &C, address: &Pubkey) -> Result<Account, RelayerError> {
+    cluster
+        .get_account(address)
+        .await?
+        .ok_or(RelayerError::AccountNotFound(*address))
+}
+
+/// Decodes a borsh `#[account]` (discriminator checked by `try_deserialize`).
+pub fn decode_account<T: AccountDeserialize>(address: &Pubkey, account: &Account) -> Result<T, RelayerError> {
+    T::try_deserialize(&mut account.data.as_slice()).map_err(|_| RelayerError::InvalidAccount(*address))
+}
+
+/// Decodes a `#[account(zero_copy)]`: discriminator followed by the `Pod` struct.
+pub fn decode_zero_copy<T: bytemuck::Pod + Discriminator>(
+    address: &Pubkey,
+    account: &Account,
+) -> Result<T, RelayerError> {
+    let data = account.data.as_slice();
+    let body = data
+        .strip_prefix(T::DISCRIMINATOR)
+        .and_then(|body| body.get(..std::mem::size_of::<T>()))
+        .ok_or(RelayerError::InvalidAccount(*address))?;
+    bytemuck::try_pod_read_unaligned(body).map_err(|_| RelayerError::InvalidAccount(*address))
+}
+
+pub async fn fetch_anchor_account<C: Cluster, T: AccountDeserialize>(
+    cluster: &C,
+    address: &Pubkey,
+) -> Result<T, RelayerError> {
+    decode_account(address, &fetch_account(cluster, address).await?)
+}
+
+pub async fn fetch_zero_copy_account<C: Cluster, T: bytemuck::Pod + Discriminator>(
+    cluster: &C,
+    address: &Pubkey,
+) -> Result<T, RelayerError> {
+    decode_zero_copy(address, &fetch_account(cluster, address).await?)
+}
+
+/// Registry entry of `authority` in the pool, fails when it is not registered or deregistering.
+pub async fn fetch_relayer_account<C: Cluster>(
+    cluster: &C,
+    pool_id: u16,
+    authority: &Pubkey,
+) -> Result<RelayerAccount, RelayerError> {
+    let program_id = yona::ID;
+    let (global_config, _) = find_global_config_address(&program_id, pool_id);
+    let (address, _) = find_relayer_address(&program_id, &global_config, authority);
+    let relayer_account: RelayerAccount = match cluster.get_account(&address).await? {
+        Some(account) => decode_account(&address, &account)?,
+        None => return Err(RelayerError::NotRegistered(*authority)),
+    };
+    // The program rejects transactions relayed after the deregistration request
+    if relayer_account.is_deregistering() {
+        return Err(RelayerError::Deregistering(*authority));
+    }
+    Ok(relayer_account)
+}
+
+/// Pool accounts shared by the `withdraw` and `swap` checks.
+pub struct PoolAccounts {
+    pub global_config_address: Pubkey,
+    pub global_config: GlobalConfig,
+    pub tree_account: MerkleTreeAccount,
+    pub retired_tree_account: Option<MerkleTreeAccount>,
+    pub verifying_key: VerifyingKeyAccount,
+    /// Cluster unix timestamp
+    pub now: i64,
+}
+
+impl PoolAccounts {
+    pub async fn fetch<C: Cluster>(
+        cluster: &C,
+        pool_id: u16,
+        circuit: u8,
+        retired_tree_index: Option<u32>,
+    ) -> Result<Self, RelayerError> {
+        let program_id = yona::ID;
+        let (global_config_address, _) = find_global_config_address(&program_id, pool_id);
+        let global_config: GlobalConfig = fetch_anchor_account(cluster, &global_config_address).await?;
+
+        let (tree_address, _) =
+            find_merkle_tree_address(&program_id, pool_id, global_config.active_tree_index);
+        let tree_account = fetch_zero_copy_account(cluster, &tree_address).await?;
+        let retired_tree_account = match retired_tree_index {
+            Some(tree_index) => {
+                let (address, _) = find_merkle_tree_address(&program_id, pool_id, tree_index);
+                Some(fetch_zero_copy_account(cluster, &address).await?)
+            }
+            None => None,
+        };
+
+        let (verifying_key_address, _) = find_verifying_key_address(&program_id, circuit);
+        let verifying_key = fetch_zero_copy_account(cluster, &verifying_key_address).await?;
+
+        let clock_account = fetch_account(cluster, &sysvar::clock::ID).await?;
+        let clock: Clock = solana_sdk::account::from_account(&clock_account)
+            .ok_or(RelayerError::InvalidAccount(sysvar::clock::ID))?;
+
+        Ok(Self {
+            global_config_address,
+            global_config,
+            tree_account,
+            retired_tree_account,
+            verifying_key,
+            now: clock.unix_timestamp,
+        })
+    }
+
+    pub fn preflight_state(&self) -> PreflightState<'_> {
+        PreflightState {
+            tree_account: &self.tree_account,
+            retired_tree_account: self.retired_tree_account.as_ref(),
+            verifying_key: &self.verifying_key,
+            global_config: &self.global_config,
+            now: self.now,
+        }
+    }
+}
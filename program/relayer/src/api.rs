@@ -0,0 +1,69 @@
+//! HTTP API of the relayer.
+//!
+//! - `GET /health`
+//! - `GET /relayer`: registration of the relayer (fee recipient and fee rate to use in ext data)
+//! - `POST /withdraw`, `POST /swap`: preflight, submit, returns the transaction signature.
+//!   Transactions failing the preflight are rejected with `422` and the failed checks.
+//! - `GET /status/:signature`: status of a submitted transaction
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use solana_sdk::signature::Signature;
+
+use crate::cluster::Cluster;
+use crate::error::RelayerError;
+use crate::relayer::Relayer;
+use crate::request::{RelayerInfo, SubmitResponse, SwapRequest, WithdrawRequest};
+
+pub fn router<C: Cluster>(relayer: Arc<Relayer<C>>) -> Router {
+    Router::new()
+        .route("/health", get(health))
+        .route("/relayer", get(relayer_info::<C>))
+        .route("/withdraw", post(withdraw::<C>))
+        .route("/swap", post(swap::<C>))
+        .route("/status/:signature", get(status::<C>))
+        .with_state(relayer)
+}
+
+async fn health() -> &'static str {
+    "ok"
+}
+
+async fn relayer_info<C: Cluster>(
+    State(relayer): State<Arc<Relayer<C>>>,
+) -> Result<Json<RelayerInfo>, RelayerError> {
+    Ok(Json(relayer.info().await?))
+}
+
+async fn withdraw<C: Cluster>(
+    State(relayer): State<Arc<Relayer<C>>>,
+    Json(request): Json<WithdrawRequest>,
+) -> Result<Json<SubmitResponse>, RelayerError> {
+    let signature = relayer.withdraw(request).await?;
+    Ok(Json(SubmitResponse { signature }))
+}
+
+async fn swap<C: Cluster>(
+    State(relayer): State<Arc<Relayer<C>>>,
+    Json(request): Json<SwapRequest>,
+) -> Result<Json<SubmitResponse>, RelayerError> {
+    let signature = relayer.swap(request).await?;
+    Ok(Json(SubmitResponse { signature }))
+}
+
+async fn status<C: Cluster>(
+    State(relayer): State<Arc<Relayer<C>>>,
+    Path(signature): Path<String>,
+) -> Result<Response, RelayerError> {
+    let signature: Signature = signature
+        .parse()
+        .map_err(|_| RelayerError::InvalidRequest(format!("invalid signature {signature}")))?;
+    Ok(match relayer.status(&signature).await? {
+        Some(status) => Json(status).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    })
+}
@@ -0,0 +1,118 @@
+//! Access to the cluster the relayer submits to.
+//!
+//! The relayer only needs a handful of RPC calls, they are behind [`Cluster`] so the service
+//! can run against a local validator, a LiteSVM instance or an in-memory fake in tests.
+use async_trait::async_trait;
+use serde::Serialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::account::Account;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
+
+use crate::error::RelayerError;
+
+/// Status of a submitted transaction.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum TransactionStatus {
+    Processed,
+    Confirmed,
+    Finalized,
+    Failed { error: String },
+}
+
+#[async_trait]
+pub trait Cluster: Send + Sync + 'static {
+    async fn get_account(&self, address: &Pubkey) -> Result<Option<Account>, RelayerError>;
+
+    async fn get_latest_blockhash(&self) -> Result<Hash, RelayerError>;
+
+    async fn send_transaction(
+        &self,
+        transaction: &VersionedTransaction,
+    ) -> Result<Signature, RelayerError>;
+
+    /// None when the cluster doesn't know the signature (not landed yet, or expired)
+    async fn get_transaction_status(
+        &self,
+        signature: &Signature,
+    ) -> Result<Option<TransactionStatus>, RelayerError>;
+}
+
+/// [`Cluster`] backed by a JSON RPC node.
+pub struct RpcCluster {
+    client: RpcClient,
+}
+
+impl RpcCluster {
+    pub fn new(url: String, commitment: CommitmentConfig) -> Self {
+        Self {
+            client: RpcClient::new_with_commitment(url, commitment),
+        }
+    }
+
+    pub fn client(&self) -> &RpcClient {
+        &self.client
+    }
+}
+
+fn rpc_error(error: impl std::fmt::Display) -> RelayerError {
+    RelayerError::Rpc(error.to_string())
+}
+
+#[async_trait]
+impl Cluster for RpcCluster {
+    async fn get_account(&self, address: &Pubkey) -> Result<Option<Account>, RelayerError> {
+        let response = self
+            .client
+            .get_account_with_commitment(address, self.client.commitment())
+            .await
+            .map_err(rpc_error)?;
+        Ok(response.value)
+    }
+
+    async fn get_latest_blockhash(&self) -> Result<Hash, RelayerError> {
+        self.client.get_latest_blockhash().await.map_err(rpc_error)
+    }
+
+    async fn send_transaction(
+        &self,
+        transaction: &VersionedTransaction,
+    ) -> Result<Signature, RelayerError> {
+        self.client
+            .send_transaction(transaction)
+            .await
+            .map_err(rpc_error)
+    }
+
+    async fn get_transaction_status(
+        &self,
+        signature: &Signature,
+    ) -> Result<Option<TransactionStatus>, RelayerError> {
+        let mut statuses = self
+            .client
+            .get_signature_statuses(&[*signature])
+            .await
+            .map_err(rpc_error)?
+            .value;
+        let Some(status) = statuses.pop().flatten() else {
+            return Ok(None);
+        };
+
+        Ok(Some(match &status.err {
+            Some(error) => TransactionStatus::Failed {
+                error: error.to_string(),
+            },
+            None if status.satisfies_commitment(CommitmentConfig::finalized()) => {
+                TransactionStatus::Finalized
+            }
+            None if status.satisfies_commitment(CommitmentConfig::confirmed()) => {
+                TransactionStatus::Confirmed
+            }
+            None => TransactionStatus::Processed,
+        }))
+    }
+}
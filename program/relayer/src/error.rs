@@ -0,0 +1,85 @@
+use anchor_lang::prelude::Pubkey;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Serialize;
+use thiserror::Error;
+use yona_client::preflight::PreflightReport;
+
+#[derive(Debug, Error)]
+pub enum RelayerError {
+    #[error("RPC error: {0}")]
+    Rpc(String),
+    #[error("Account not found: {0}")]
+    AccountNotFound(Pubkey),
+    #[error("Failed to decode account {0}")]
+    InvalidAccount(Pubkey),
+    #[error("Relayer {0} is not registered in the pool")]
+    NotRegistered(Pubkey),
+    #[error("Relayer {0} has requested its deregistration")]
+    Deregistering(Pubkey),
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+    #[error("Transaction would fail on-chain")]
+    Preflight(Vec<PreflightFailureData>),
+    #[error("Failed to build transaction: {0}")]
+    Transaction(String),
+}
+
+/// A failed preflight check, as returned by the API.
+#[derive(Clone, Debug, Serialize)]
+pub struct PreflightFailureData {
+    /// `PreflightCheck` variant name
+    pub check: String,
+    /// Custom program error code the transaction would fail with
+    pub error_code: Option<u32>,
+    pub message: String,
+}
+
+impl From<PreflightReport> for RelayerError {
+    fn from(report: PreflightReport) -> Self {
+        RelayerError::Preflight(
+            report
+                .failures
+                .iter()
+                .map(|failure| PreflightFailureData {
+                    check: format!("{:?}", failure.check),
+                    error_code: failure.error_code(),
+                    message: failure.error.to_string(),
+                })
+                .collect(),
+        )
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorResponse {
+    error: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    failures: Vec<PreflightFailureData>,
+}
+
+impl IntoResponse for RelayerError {
+    fn into_response(self) -> Response {
+        let status = match &self {
+            RelayerError::InvalidRequest(_) | RelayerError::AccountNotFound(_) => {
+                StatusCode::BAD_REQUEST
+            }
+            RelayerError::Preflight(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            RelayerError::Rpc(_) => StatusCode::BAD_GATEWAY,
+            RelayerError::InvalidAccount(_)
+            | RelayerError::NotRegistered(_)
+            | RelayerError::Deregistering(_)
+            | RelayerError::Transaction(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        let failures = match &self {
+            RelayerError::Preflight(failures) => failures.clone(),
+            _ => Vec::new(),
+        };
+        let body = ErrorResponse {
+            error: self.to_string(),
+            failures,
+        };
+        (status, Json(body)).into_response()
+    }
+}
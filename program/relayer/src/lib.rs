@@ -0,0 +1,17 @@
+//! Reference relayer for the yona program.
+//!
+//! Users send their proof and ext data over HTTP, the relayer runs the program checks
+//! off-chain (`yona_client::preflight`), builds the `withdraw` / `swap` transaction with the
+//! relayer as signer and fee recipient, and submits it as a v0 transaction using address
+//! lookup tables. The relayer must be registered in the pool (`register_relayer`).
+pub mod accounts;
+pub mod api;
+pub mod cluster;
+pub mod error;
+pub mod lookup_table;
+pub mod relayer;
+pub mod request;
+
+pub use cluster::{Cluster, RpcCluster, TransactionStatus};
+pub use error::RelayerError;
+pub use relayer::{Relayer, RelayerConfig, DEFAULT_COMPUTE_UNIT_LIMIT};
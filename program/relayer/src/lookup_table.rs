@@ -0,0 +1,102 @@
+//! Address lookup tables of relayed transactions.
+//!
+//! `withdraw` and `swap` don't fit in a legacy transaction once the Light Protocol accounts
+//! are appended, the relayer compiles v0 messages against a table holding the accounts shared
+//! by every transaction of the pool (same idea as `tests/lib/test_alt.ts`).
+use solana_sdk::address_lookup_table::instruction::{create_lookup_table, extend_lookup_table};
+use solana_sdk::address_lookup_table::state::AddressLookupTable;
+use solana_sdk::address_lookup_table::AddressLookupTableAccount;
+use solana_sdk::account::Account;
+use solana_compute_budget_interface as compute_budget;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use yona_client::light::{light_system_account_metas, LightTrees};
+use yona_client::pda::{
+    find_aggregator_config_address, find_global_config_address, find_merkle_tree_address,
+    find_relayer_address, find_swap_authority_address, find_tree_token_address,
+    find_verifying_key_address,
+};
+
+use crate::error::RelayerError;
+
+// Addresses per `extend_lookup_table` instruction, keeps the transaction below the size limit
+const EXTEND_CHUNK_SIZE: usize = 20;
+
+/// Accounts shared by the relayed transactions of a pool: program accounts, Light Protocol
+/// accounts, the relayer and its fee token accounts, and the programs.
+pub fn protocol_addresses(
+    pool_id: u16,
+    active_tree_index: u32,
+    relayer: &Pubkey,
+    fee_recipient_accounts: &[Pubkey],
+    light_trees: &LightTrees,
+) -> Vec<Pubkey> {
+    let program_id = yona::ID;
+    let (global_config, _) = find_global_config_address(&program_id, pool_id);
+
+    let mut addresses = vec![
+        program_id,
+        find_merkle_tree_address(&program_id, pool_id, active_tree_index).0,
+        find_tree_token_address(&program_id, pool_id).0,
+        global_config,
+        find_verifying_key_address(&program_id, yona::CIRCUIT_TRANSACTION2).0,
+        find_relayer_address(&program_id, &global_config, relayer).0,
+        find_aggregator_config_address(&program_id, &global_config, &yona::jupiter_aggregator::ID).0,
+        find_swap_authority_address(&program_id, &global_config).0,
+        *relayer,
+    ];
+    addresses.extend_from_slice(fee_recipient_accounts);
+    addresses.extend(
+        light_system_account_metas(&program_id)
+            .into_iter()
+            .map(|meta| meta.pubkey),
+    );
+    addresses.extend([
+        light_trees.address_tree,
+        light_trees.address_queue,
+        light_trees.output_state_tree,
+        light_trees.nullifier_queue,
+        yona::jupiter_aggregator::ID,
+        compute_budget::ID,
+        anchor_spl::token::ID,
+        anchor_spl::token_2022::ID,
+        anchor_spl::associated_token::ID,
+    ]);
+    addresses.sort();
+    addresses.dedup();
+    addresses
+}
+
+/// Instructions creating a lookup table owned by `authority` and filling it with `addresses`.
+/// The first instruction creates the table, each following one extends it and should be sent
+/// in its own transaction.
+pub fn create_lookup_table_instructions(
+    authority: Pubkey,
+    payer: Pubkey,
+    recent_slot: u64,
+    addresses: &[Pubkey],
+) -> (Pubkey, Vec<Instruction>) {
+    let (create_instruction, lookup_table) = create_lookup_table(authority, payer, recent_slot);
+    let mut instructions = vec![create_instruction];
+    for chunk in addresses.chunks(EXTEND_CHUNK_SIZE) {
+        instructions.push(extend_lookup_table(
+            lookup_table,
+            authority,
+            Some(payer),
+            chunk.to_vec(),
+        ));
+    }
+    (lookup_table, instructions)
+}
+
+pub fn decode_lookup_table(
+    address: &Pubkey,
+    account: &Account,
+) -> Result<AddressLookupTableAccount, RelayerError> {
+    let table = AddressLookupTable::deserialize(&account.data)
+        .map_err(|_| RelayerError::InvalidAccount(*address))?;
+    Ok(AddressLookupTableAccount {
+        key: *address,
+        addresses: table.addresses.to_vec(),
+    })
+}
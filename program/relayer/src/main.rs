@@ -0,0 +1,179 @@
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use clap::{Args, Parser, Subcommand};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+use yona_client::light::LightTrees;
+use yona_client::pda::find_global_config_address;
+use yona_relayer::accounts::{fetch_anchor_account, fetch_relayer_account};
+use yona_relayer::lookup_table::{create_lookup_table_instructions, protocol_addresses};
+use yona_relayer::{api, Relayer, RelayerConfig, RpcCluster, DEFAULT_COMPUTE_UNIT_LIMIT};
+
+#[derive(Parser)]
+#[command(about = "Relayer for the yona program")]
+struct Cli {
+    #[command(flatten)]
+    common: CommonArgs,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Args)]
+struct CommonArgs {
+    #[arg(long, env = "YONA_RPC_URL", default_value = "http://127.0.0.1:8899")]
+    rpc_url: String,
+    /// Keypair of the relayer, must be registered with `register_relayer`
+    #[arg(long, env = "YONA_RELAYER_KEYPAIR")]
+    keypair: PathBuf,
+    #[arg(long, env = "YONA_POOL_ID", default_value_t = yona::DEFAULT_POOL_ID)]
+    pool_id: u16,
+    /// Light Protocol trees, default to the trees of `light test-validator`
+    #[arg(long, env = "YONA_ADDRESS_TREE")]
+    address_tree: Option<Pubkey>,
+    #[arg(long, env = "YONA_ADDRESS_QUEUE")]
+    address_queue: Option<Pubkey>,
+    #[arg(long, env = "YONA_OUTPUT_STATE_TREE")]
+    output_state_tree: Option<Pubkey>,
+    #[arg(long, env = "YONA_NULLIFIER_QUEUE")]
+    nullifier_queue: Option<Pubkey>,
+}
+
+impl CommonArgs {
+    fn light_trees(&self) -> LightTrees {
+        let localnet = LightTrees::localnet();
+        LightTrees {
+            address_tree: self.address_tree.unwrap_or(localnet.address_tree),
+            address_queue: self.address_queue.unwrap_or(localnet.address_queue),
+            output_state_tree: self.output_state_tree.unwrap_or(localnet.output_state_tree),
+            nullifier_queue: self.nullifier_queue.unwrap_or(localnet.nullifier_queue),
+        }
+    }
+
+    fn keypair(&self) -> Result<Keypair, Box<dyn std::error::Error>> {
+        read_keypair_file(&self.keypair)
+            .map_err(|error| format!("failed to read {}: {error}", self.keypair.display()).into())
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run the HTTP service
+    Serve {
+        #[arg(long, env = "YONA_LISTEN", default_value = "127.0.0.1:8080")]
+        listen: SocketAddr,
+        /// Lookup table created by `create-lookup-table`
+        #[arg(long, env = "YONA_LOOKUP_TABLE")]
+        lookup_table: Option<Pubkey>,
+        #[arg(long, env = "YONA_COMPUTE_UNIT_LIMIT", default_value_t = DEFAULT_COMPUTE_UNIT_LIMIT)]
+        compute_unit_limit: u32,
+    },
+    /// Create the lookup table of the accounts shared by relayed transactions
+    CreateLookupTable {
+        /// Mints relayed for, their fee token accounts are added to the table
+        #[arg(long = "mint")]
+        mints: Vec<Pubkey>,
+    },
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .init();
+
+    let cli = Cli::parse();
+    let keypair = cli.common.keypair()?;
+    let cluster = RpcCluster::new(cli.common.rpc_url.clone(), CommitmentConfig::confirmed());
+
+    match cli.command {
+        Command::Serve {
+            listen,
+            lookup_table,
+            compute_unit_limit,
+        } => {
+            let relayer = Relayer::new(
+                cluster,
+                keypair,
+                RelayerConfig {
+                    pool_id: cli.common.pool_id,
+                    light_trees: cli.common.light_trees(),
+                    lookup_table,
+                    compute_unit_limit,
+                },
+            );
+            // Fail fast when the relayer is not registered in the pool
+            let info = relayer.info().await?;
+            tracing::info!(
+                authority = %info.authority,
+                fee_recipient = %info.fee_recipient,
+                fee_rate = info.fee_rate,
+                "relayer registered"
+            );
+
+            let listener = tokio::net::TcpListener::bind(listen).await?;
+            tracing::info!(%listen, "listening");
+            axum::serve(listener, api::router(Arc::new(relayer)))
+                .with_graceful_shutdown(async {
+                    let _ = tokio::signal::ctrl_c().await;
+                })
+                .await?;
+        }
+        Command::CreateLookupTable { mints } => {
+            create_lookup_table(&cluster, &keypair, &cli.common, &mints).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn create_lookup_table(
+    cluster: &RpcCluster,
+    keypair: &Keypair,
+    common: &CommonArgs,
+    mints: &[Pubkey],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let client = cluster.client();
+    let (global_config_address, _) = find_global_config_address(&yona::ID, common.pool_id);
+    let global_config: yona::GlobalConfig =
+        fetch_anchor_account(cluster, &global_config_address).await?;
+    let relayer_account = fetch_relayer_account(cluster, common.pool_id, &keypair.pubkey()).await?;
+
+    let mut fee_recipient_accounts = Vec::new();
+    for mint in mints {
+        let token_program = client.get_account(mint).await?.owner;
+        fee_recipient_accounts.push(
+            anchor_spl::associated_token::get_associated_token_address_with_program_id(
+                &relayer_account.fee_recipient,
+                mint,
+                &token_program,
+            ),
+        );
+    }
+    let addresses = protocol_addresses(
+        common.pool_id,
+        global_config.active_tree_index,
+        &keypair.pubkey(),
+        &fee_recipient_accounts,
+        &common.light_trees(),
+    );
+
+    let recent_slot = client.get_slot().await?;
+    let (lookup_table, instructions) =
+        create_lookup_table_instructions(keypair.pubkey(), keypair.pubkey(), recent_slot, &addresses);
+    for instruction in instructions {
+        let blockhash = client.get_latest_blockhash().await?;
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&keypair.pubkey()),
+            &[keypair],
+            blockhash,
+        );
+        client.send_and_confirm_transaction(&transaction).await?;
+    }
+
+    println!("{lookup_table}");
+    Ok(())
+}
@@ -0,0 +1,332 @@
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use anchor_spl::associated_token::spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+use anchor_spl::token::spl_token::native_mint;
+use anchor_spl::token_interface::TokenAccount;
+use solana_compute_budget_interface::ComputeBudgetInstruction;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::message::{v0, VersionedMessage};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::transaction::VersionedTransaction;
+use yona::{AggregatorConfig, CompressedProof, ExtDataMinified, MintConfig, RelayerAccount, SwapExtDataMinified, CIRCUIT_TRANSACTION2};
+use yona_client::light::LightTrees;
+use yona_client::pda::{find_aggregator_config_address, find_mint_config_address};
+use yona_client::preflight::{preflight_swap, preflight_withdraw};
+use yona_client::{SwapAccounts, WithdrawAccounts};
+
+use crate::accounts::{decode_account, fetch_account, fetch_anchor_account, fetch_relayer_account, PoolAccounts};
+use crate::cluster::{Cluster, TransactionStatus};
+use crate::error::RelayerError;
+use crate::lookup_table::decode_lookup_table;
+use crate::request::{RelayerInfo, SwapRequest, WithdrawRequest};
+
+// Same limit as the test transactions, the Groth16 verification and the Light CPI need most of it
+pub const DEFAULT_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+pub struct RelayerConfig {
+    pub pool_id: u16,
+    pub light_trees: LightTrees,
+    /// Lookup table holding `lookup_table::protocol_addresses`
+    pub lookup_table: Option<Pubkey>,
+    pub compute_unit_limit: u32,
+}
+
+/// Preflights, builds and submits the transactions relayed for users.
+pub struct Relayer<C> {
+    cluster: C,
+    keypair: Keypair,
+    config: RelayerConfig,
+}
+
+impl<C: Cluster> Relayer<C> {
+    pub fn new(cluster: C, keypair: Keypair, config: RelayerConfig) -> Self {
+        Self {
+            cluster,
+            keypair,
+            config,
+        }
+    }
+
+    pub fn authority(&self) -> Pubkey {
+        self.keypair.pubkey()
+    }
+
+    pub fn cluster(&self) -> &C {
+        &self.cluster
+    }
+
+    /// Registry entry of the relayer, fails when the relayer is not registered in the pool or is
+    /// deregistering.
+    pub async fn relayer_account(&self) -> Result<RelayerAccount, RelayerError> {
+        fetch_relayer_account(&self.cluster, self.config.pool_id, &self.authority()).await
+    }
+
+    pub async fn info(&self) -> Result<RelayerInfo, RelayerError> {
+        let relayer_account = self.relayer_account().await?;
+        Ok(RelayerInfo {
+            pool_id: self.config.pool_id,
+            authority: relayer_account.authority,
+            fee_recipient: relayer_account.fee_recipient,
+            fee_rate: relayer_account.fee_rate,
+            bond: relayer_account.bond,
+        })
+    }
+
+    pub async fn withdraw(&self, request: WithdrawRequest) -> Result<Signature, RelayerError> {
+        let relayer_account = self.relayer_account().await?;
+        let token_program = self.token_program(&request.input_mint).await?;
+        let fee_recipient_account = get_associated_token_address_with_program_id(
+            &relayer_account.fee_recipient,
+            &request.input_mint,
+            &token_program,
+        );
+
+        let pool = PoolAccounts::fetch(
+            &self.cluster,
+            self.config.pool_id,
+            CIRCUIT_TRANSACTION2,
+            request.retired_tree_index,
+        )
+        .await?;
+        let mint_config = self
+            .mint_config(&pool.global_config_address, &request.input_mint)
+            .await?;
+
+        let proof: CompressedProof = request.proof.clone().into();
+        let ext_data_minified = ExtDataMinified {
+            ext_amount: request.ext_amount,
+            fee: request.fee,
+        };
+
+        let mut instructions = vec![create_associated_token_account_idempotent(
+            &self.authority(),
+            &relayer_account.fee_recipient,
+            &request.input_mint,
+            &token_program,
+        )];
+        // WSOL is unwrapped through a token account of the relayer, closed by the program
+        let (recipient_token_account, recipient_token_account_owner) = if request.input_mint == native_mint::ID {
+            instructions.push(create_associated_token_account_idempotent(
+                &self.authority(),
+                &self.authority(),
+                &request.input_mint,
+                &token_program,
+            ));
+            (
+                get_associated_token_address_with_program_id(
+                    &self.authority(),
+                    &request.input_mint,
+                    &token_program,
+                ),
+                self.authority(),
+            )
+        } else if let Some(recipient_token_account) = request.recipient_token_account {
+            let token_account: TokenAccount =
+                fetch_anchor_account(&self.cluster, &recipient_token_account).await?;
+            (recipient_token_account, token_account.owner)
+        } else {
+            instructions.push(create_associated_token_account_idempotent(
+                &self.authority(),
+                &request.recipient,
+                &request.input_mint,
+                &token_program,
+            ));
+            (
+                get_associated_token_address_with_program_id(
+                    &request.recipient,
+                    &request.input_mint,
+                    &token_program,
+                ),
+                request.recipient,
+            )
+        };
+
+        let report = preflight_withdraw(
+            &pool.preflight_state(),
+            &mint_config,
+            Some(&relayer_account),
+            request.input_mint,
+            self.authority(),
+            request.recipient,
+            recipient_token_account_owner,
+            fee_recipient_account,
+            // Associated account of the fee recipient, created by the transaction when missing
+            relayer_account.fee_recipient,
+            &proof,
+            &ext_data_minified,
+            &request.encrypted_output,
+        );
+        if !report.is_ok() {
+            return Err(report.into());
+        }
+
+        instructions.push(yona_client::withdraw(
+            WithdrawAccounts {
+                pool_id: self.config.pool_id,
+                active_tree_index: pool.global_config.active_tree_index,
+                retired_tree_index: request.retired_tree_index,
+                relayer: self.authority(),
+                registered_relayer: true,
+                input_mint: request.input_mint,
+                recipient: request.recipient,
+                recipient_token_account,
+                fee_recipient_account,
+                token_program,
+            },
+            proof,
+            ext_data_minified,
+            request.encrypted_output,
+            &request.light_proof.nullifier_params(self.config.light_trees),
+        ));
+
+        self.submit(instructions, &[]).await
+    }
+
+    pub async fn swap(&self, request: SwapRequest) -> Result<Signature, RelayerError> {
+        let relayer_account = self.relayer_account().await?;
+        let input_token_program = self.token_program(&request.input_mint).await?;
+        let output_token_program = self.token_program(&request.output_mint).await?;
+        // The swap fee is paid in the output mint
+        let fee_recipient_account = get_associated_token_address_with_program_id(
+            &relayer_account.fee_recipient,
+            &request.output_mint,
+            &output_token_program,
+        );
+
+        let pool = PoolAccounts::fetch(
+            &self.cluster,
+            self.config.pool_id,
+            CIRCUIT_TRANSACTION2,
+            request.retired_tree_index,
+        )
+        .await?;
+        self.mint_config(&pool.global_config_address, &request.input_mint).await?;
+        let output_mint_config = self
+            .mint_config(&pool.global_config_address, &request.output_mint)
+            .await?;
+        let aggregator_config = self.aggregator_config(&pool.global_config_address).await?;
+
+        let proof: CompressedProof = request.proof.clone().into();
+        let accounts = SwapAccounts {
+            pool_id: self.config.pool_id,
+            active_tree_index: pool.global_config.active_tree_index,
+            retired_tree_index: request.retired_tree_index,
+            relayer: self.authority(),
+            input_mint: request.input_mint,
+            output_mint: request.output_mint,
+            fee_recipient_account,
+            input_token_program,
+            output_token_program,
+            jupiter_accounts: request.jupiter_accounts.iter().map(Into::into).collect(),
+        };
+        let ext_data_minified = SwapExtDataMinified {
+            ext_amount: request.ext_amount,
+            ext_min_amount_out: request.ext_min_amount_out,
+            fee: request.fee,
+        };
+        let report = preflight_swap(
+            &pool.preflight_state(),
+            &output_mint_config,
+            &relayer_account,
+            // Associated account of the fee recipient, created below when missing
+            relayer_account.fee_recipient,
+            aggregator_config.as_ref(),
+            &accounts,
+            &proof,
+            &ext_data_minified,
+            &request.encrypted_output,
+            &request.jupiter_swap_data,
+        );
+        if !report.is_ok() {
+            return Err(report.into());
+        }
+
+        let instructions = vec![
+            create_associated_token_account_idempotent(
+                &self.authority(),
+                &relayer_account.fee_recipient,
+                &request.output_mint,
+                &output_token_program,
+            ),
+            yona_client::swap(
+                accounts,
+                proof,
+                ext_data_minified,
+                request.encrypted_output,
+                request.jupiter_swap_data,
+                &request.light_proof.nullifier_params(self.config.light_trees),
+            ),
+        ];
+
+        self.submit(instructions, &request.address_lookup_tables).await
+    }
+
+    pub async fn status(&self, signature: &Signature) -> Result<Option<TransactionStatus>, RelayerError> {
+        self.cluster.get_transaction_status(signature).await
+    }
+
+    /// Signs `instructions` as a v0 transaction using the relayer's lookup table and
+    /// `extra_lookup_tables`, and sends it.
+    async fn submit(
+        &self,
+        instructions: Vec<Instruction>,
+        extra_lookup_tables: &[Pubkey],
+    ) -> Result<Signature, RelayerError> {
+        let mut all_instructions = vec![ComputeBudgetInstruction::set_compute_unit_limit(
+            self.config.compute_unit_limit,
+        )];
+        all_instructions.extend(instructions);
+
+        let mut lookup_tables = Vec::new();
+        for address in self.config.lookup_table.iter().chain(extra_lookup_tables) {
+            let account = fetch_account(&self.cluster, address).await?;
+            lookup_tables.push(decode_lookup_table(address, &account)?);
+        }
+
+        let blockhash = self.cluster.get_latest_blockhash().await?;
+        let message = v0::Message::try_compile(
+            &self.authority(),
+            &all_instructions,
+            &lookup_tables,
+            blockhash,
+        )
+        .map_err(|error| RelayerError::Transaction(error.to_string()))?;
+        let transaction = VersionedTransaction::try_new(VersionedMessage::V0(message), &[&self.keypair])
+            .map_err(|error| RelayerError::Transaction(error.to_string()))?;
+
+        let signature = self.cluster.send_transaction(&transaction).await?;
+        tracing::info!(%signature, "submitted transaction");
+        Ok(signature)
+    }
+
+    /// Token program owning `mint`
+    async fn token_program(&self, mint: &Pubkey) -> Result<Pubkey, RelayerError> {
+        let account = fetch_account(&self.cluster, mint).await?;
+        if account.owner != anchor_spl::token::ID && account.owner != anchor_spl::token_2022::ID {
+            return Err(RelayerError::InvalidRequest(format!("{mint} is not a token mint")));
+        }
+        Ok(account.owner)
+    }
+
+    /// Jupiter entry of the pool's aggregator allowlist, `None` when it was never set
+    async fn aggregator_config(&self, global_config: &Pubkey) -> Result<Option<AggregatorConfig>, RelayerError> {
+        let (address, _) =
+            find_aggregator_config_address(&yona::ID, global_config, &yona::jupiter_aggregator::ID);
+        match self.cluster.get_account(&address).await? {
+            Some(account) => Ok(Some(decode_account(&address, &account)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn mint_config(&self, global_config: &Pubkey, mint: &Pubkey) -> Result<MintConfig, RelayerError> {
+        let (address, _) = find_mint_config_address(&yona::ID, global_config, mint);
+        fetch_anchor_account(&self.cluster, &address)
+            .await
+            .map_err(|error| match error {
+                RelayerError::AccountNotFound(_) => {
+                    RelayerError::InvalidRequest(format!("mint {mint} is not supported by the pool"))
+                }
+                error => error,
+            })
+    }
+}
@@ -0,0 +1,175 @@
+//! JSON bodies of the relayer API.
+//!
+//! Byte arrays are hex, `encrypted_output` and `jupiter_swap_data` are base64 and public keys
+//! and signatures are base58, like the rest of the Solana tooling.
+use anchor_lang::solana_program::instruction::AccountMeta;
+use light_sdk::instruction::{CompressedProof as LightCompressedProof, ValidityProof};
+use serde::{Deserialize, Serialize};
+use serde_with::base64::Base64;
+use serde_with::hex::Hex;
+use serde_with::{serde_as, DisplayFromStr};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use yona::CompressedProof;
+use yona_client::light::{LightNullifierParams, LightTrees};
+
+/// Proof of the transaction circuit, public inputs in big endian like `CompressedProof`.
+#[serde_as]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ProofData {
+    #[serde_as(as = "Hex")]
+    pub proof_a: [u8; 32],
+    #[serde_as(as = "Hex")]
+    pub proof_b: [u8; 64],
+    #[serde_as(as = "Hex")]
+    pub proof_c: [u8; 32],
+    #[serde_as(as = "Hex")]
+    pub root: [u8; 32],
+    #[serde_as(as = "Hex")]
+    pub public_amount0: [u8; 32],
+    #[serde_as(as = "Hex")]
+    pub public_amount1: [u8; 32],
+    #[serde_as(as = "Hex")]
+    pub ext_data_hash: [u8; 32],
+    #[serde_as(as = "[Hex; 2]")]
+    pub input_nullifiers: [[u8; 32]; 2],
+    #[serde_as(as = "[Hex; 2]")]
+    pub output_commitments: [[u8; 32]; 2],
+}
+
+impl From<ProofData> for CompressedProof {
+    fn from(proof: ProofData) -> Self {
+        CompressedProof {
+            proof_a: proof.proof_a,
+            proof_b: proof.proof_b,
+            proof_c: proof.proof_c,
+            root: proof.root,
+            public_amount0: proof.public_amount0,
+            public_amount1: proof.public_amount1,
+            ext_data_hash: proof.ext_data_hash,
+            input_nullifiers: proof.input_nullifiers,
+            output_commitments: proof.output_commitments,
+        }
+    }
+}
+
+/// Light validity proof of the nullifier addresses, as returned by `getValidityProof`.
+#[serde_as]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LightProofData {
+    #[serde_as(as = "Hex")]
+    pub a: [u8; 32],
+    #[serde_as(as = "Hex")]
+    pub b: [u8; 64],
+    #[serde_as(as = "Hex")]
+    pub c: [u8; 32],
+    /// Root index of each nullifier address in the address tree
+    pub address_root_indices: [u16; 2],
+}
+
+impl LightProofData {
+    pub fn nullifier_params(&self, trees: LightTrees) -> LightNullifierParams {
+        LightNullifierParams {
+            light_proof: ValidityProof(Some(LightCompressedProof {
+                a: self.a,
+                b: self.b,
+                c: self.c,
+            })),
+            address_root_indices: self.address_root_indices,
+            trees,
+        }
+    }
+}
+
+#[serde_as]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct WithdrawRequest {
+    pub proof: ProofData,
+    pub ext_amount: i64,
+    pub fee: u64,
+    #[serde_as(as = "Base64")]
+    #[serde(default)]
+    pub encrypted_output: Vec<u8>,
+    #[serde_as(as = "DisplayFromStr")]
+    pub input_mint: Pubkey,
+    #[serde_as(as = "DisplayFromStr")]
+    pub recipient: Pubkey,
+    /// Defaults to the recipient's associated token account, created if needed. Ignored for wrapped
+    /// SOL, which is unwrapped through a token account of the relayer and paid out in lamports
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    #[serde(default)]
+    pub recipient_token_account: Option<Pubkey>,
+    /// Tree index of the proof root when it comes from a retired tree
+    #[serde(default)]
+    pub retired_tree_index: Option<u32>,
+    pub light_proof: LightProofData,
+}
+
+#[serde_as]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct JupiterAccountData {
+    #[serde_as(as = "DisplayFromStr")]
+    pub pubkey: Pubkey,
+    pub is_writable: bool,
+}
+
+impl From<&JupiterAccountData> for AccountMeta {
+    fn from(account: &JupiterAccountData) -> Self {
+        // Jupiter's signer is the pool's swap authority, signed by the program
+        AccountMeta {
+            pubkey: account.pubkey,
+            is_signer: false,
+            is_writable: account.is_writable,
+        }
+    }
+}
+
+#[serde_as]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SwapRequest {
+    pub proof: ProofData,
+    pub ext_amount: i64,
+    pub ext_min_amount_out: i64,
+    pub fee: u64,
+    #[serde_as(as = "Base64")]
+    #[serde(default)]
+    pub encrypted_output: Vec<u8>,
+    #[serde_as(as = "DisplayFromStr")]
+    pub input_mint: Pubkey,
+    #[serde_as(as = "DisplayFromStr")]
+    pub output_mint: Pubkey,
+    #[serde_as(as = "Base64")]
+    pub jupiter_swap_data: Vec<u8>,
+    /// Accounts of the Jupiter instruction, in the order expected by `jupiter_swap_data`
+    pub jupiter_accounts: Vec<JupiterAccountData>,
+    /// Lookup tables of the Jupiter route, used next to the relayer's own table
+    #[serde_as(as = "Vec<DisplayFromStr>")]
+    #[serde(default)]
+    pub address_lookup_tables: Vec<Pubkey>,
+    /// Tree index of the proof root when it comes from a retired tree
+    #[serde(default)]
+    pub retired_tree_index: Option<u32>,
+    pub light_proof: LightProofData,
+}
+
+#[serde_as]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SubmitResponse {
+    #[serde_as(as = "DisplayFromStr")]
+    pub signature: Signature,
+}
+
+/// Registration the relayer advertises, users bind `fee_recipient` in their ext data.
+#[serde_as]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RelayerInfo {
+    pub pool_id: u16,
+    #[serde_as(as = "DisplayFromStr")]
+    pub authority: Pubkey,
+    /// Owner of the fee token accounts, fees go to its associated token account of the mint
+    #[serde_as(as = "DisplayFromStr")]
+    pub fee_recipient: Pubkey,
+    /// Basis points (0-10000, where 10000 = 100%)
+    pub fee_rate: u16,
+    pub bond: u64,
+}
@@ -0,0 +1,606 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use anchor_lang::{AccountSerialize, Discriminator, InstructionData};
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use anchor_spl::token::spl_token::native_mint;
+use ark_bn254::Fr;
+use ark_ff::PrimeField;
+use async_trait::async_trait;
+use axum::body::{to_bytes, Body};
+use axum::http::{Request, StatusCode};
+use axum::Router;
+use light_hasher::Poseidon;
+use serde_json::{json, Value};
+use solana_bn254::compression::prelude::{alt_bn128_g1_compress, alt_bn128_g2_compress};
+use solana_bn254::prelude::alt_bn128_multiplication;
+use solana_sdk::account::Account;
+use solana_sdk::address_lookup_table::state::{AddressLookupTable, LookupTableMeta};
+use solana_sdk::clock::Clock;
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::sysvar;
+use solana_sdk::transaction::VersionedTransaction;
+use tower::ServiceExt;
+use yona::jupiter_aggregator;
+use yona::jupiter_aggregator::client::args::Route;
+use yona::merkle_tree::MerkleTree;
+use yona::state::{DEFAULT_ROOT_HISTORY_SIZE, MERKLE_TREE_HEIGHT};
+use yona::utils::calculate_complete_ext_data_hash;
+use yona::{
+    AggregatorConfig, GlobalConfig, MerkleTreeAccount, MintConfig, RelayerAccount,
+    VerifyingKeyAccount,
+};
+use yona_client::field::fr_to_be_bytes;
+use yona_client::light::LightTrees;
+use yona_client::pda::{
+    find_aggregator_config_address, find_global_config_address, find_merkle_tree_address,
+    find_mint_config_address, find_relayer_address, find_swap_authority_address,
+    find_verifying_key_address, reserve_token_account,
+};
+use yona_client::verifying_key::TRANSACTION2_VERIFYING_KEY;
+use yona_relayer::lookup_table::protocol_addresses;
+use yona_relayer::request::{JupiterAccountData, LightProofData, ProofData, SwapRequest};
+use yona_relayer::{
+    api, Cluster, Relayer, RelayerConfig, RelayerError, TransactionStatus,
+    DEFAULT_COMPUTE_UNIT_LIMIT,
+};
+
+const EXT_AMOUNT: i64 = -1_000_000;
+const FEE: u64 = 3_000; // 0.3%
+
+/// In-memory cluster recording the submitted transactions.
+struct FakeCluster {
+    accounts: HashMap<Pubkey, Account>,
+    sent: Arc<Mutex<Vec<VersionedTransaction>>>,
+}
+
+#[async_trait]
+impl Cluster for FakeCluster {
+    async fn get_account(&self, address: &Pubkey) -> Result<Option<Account>, RelayerError> {
+        Ok(self.accounts.get(address).cloned())
+    }
+
+    async fn get_latest_blockhash(&self) -> Result<Hash, RelayerError> {
+        Ok(Hash::new_unique())
+    }
+
+    async fn send_transaction(
+        &self,
+        transaction: &VersionedTransaction,
+    ) -> Result<Signature, RelayerError> {
+        self.sent.lock().unwrap().push(transaction.clone());
+        Ok(transaction.signatures[0])
+    }
+
+    async fn get_transaction_status(
+        &self,
+        _signature: &Signature,
+    ) -> Result<Option<TransactionStatus>, RelayerError> {
+        Ok(None)
+    }
+}
+
+fn program_account(data: Vec<u8>) -> Account {
+    Account {
+        lamports: 1,
+        data,
+        owner: yona::ID,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+fn anchor_account<T: AccountSerialize>(account: &T) -> Account {
+    let mut data = Vec::new();
+    account.try_serialize(&mut data).unwrap();
+    program_account(data)
+}
+
+fn zero_copy_account<T: bytemuck::Pod + Discriminator>(account: &T) -> Account {
+    program_account([T::DISCRIMINATOR, bytemuck::bytes_of(account)].concat())
+}
+
+fn token_account(owner: Pubkey) -> Account {
+    Account {
+        lamports: 1,
+        data: Vec::new(),
+        owner,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// Proof points accepted by [`accepting_verifying_key`].
+struct AcceptedProof {
+    proof_a: [u8; 32],
+    proof_b: [u8; 64],
+    proof_c: [u8; 32],
+}
+
+/// Verifying key accepting a fixed proof whatever its public inputs, so only the other
+/// preflight checks decide. With zero IC points and beta = gamma = delta = B, the pairing
+/// check e(A, B) e(alpha, beta) e(0, gamma) e(C, delta) = 1 holds when A + alpha + C = 0.
+fn accepting_verifying_key() -> (VerifyingKeyAccount, AcceptedProof) {
+    let alpha = TRANSACTION2_VERIFYING_KEY.vk_alpha_g1;
+    let beta = TRANSACTION2_VERIFYING_KEY.vk_beta_g2;
+    let minus_two_alpha =
+        alt_bn128_multiplication(&[&alpha[..], &fr_to_be_bytes(&-Fr::from(2u64))].concat()).unwrap();
+
+    let mut verifying_key: VerifyingKeyAccount = bytemuck::Zeroable::zeroed();
+    verifying_key.current.version = 1;
+    verifying_key.current.nr_pubinputs = TRANSACTION2_VERIFYING_KEY.nr_pubinputs as u32;
+    verifying_key.current.vk_alpha_g1 = alpha;
+    verifying_key.current.vk_beta_g2 = beta;
+    verifying_key.current.vk_gamma_g2 = beta;
+    verifying_key.current.vk_delta_g2 = beta;
+
+    let proof = AcceptedProof {
+        proof_a: alt_bn128_g1_compress(&alpha).unwrap(),
+        proof_b: alt_bn128_g2_compress(&beta).unwrap(),
+        proof_c: alt_bn128_g1_compress(&minus_two_alpha).unwrap(),
+    };
+    (verifying_key, proof)
+}
+
+struct Setup {
+    accounts: HashMap<Pubkey, Account>,
+    sent: Arc<Mutex<Vec<VersionedTransaction>>>,
+    relayer: Keypair,
+    fee_recipient: Pubkey,
+    mint: Pubkey,
+    /// Second mint of the pool, output of the swaps
+    output_mint: Pubkey,
+    root: [u8; 32],
+    proof: AcceptedProof,
+    lookup_table: Option<Pubkey>,
+}
+
+/// Pool 0 with two mints and wrapped SOL, an empty tree and a verifying key accepting
+/// `Setup::proof`, `relayer` registered when `registered` is set.
+fn setup(registered: bool) -> Setup {
+    let program_id = yona::ID;
+    let relayer = Keypair::new();
+    let fee_recipient = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let output_mint = Pubkey::new_unique();
+    let (global_config_address, bump) = find_global_config_address(&program_id, 0);
+    let mut accounts = HashMap::new();
+
+    accounts.insert(
+        global_config_address,
+        anchor_account(&GlobalConfig {
+            authority: Pubkey::new_unique(),
+            pool_id: 0,
+            active_tree_index: 0,
+            deposit_fee_rate: 0,
+            withdrawal_fee_rate: 30,
+            fee_error_margin: 500,
+            min_relayer_bond: 0,
+            bump,
+        }),
+    );
+
+    let mut tree_account: MerkleTreeAccount = bytemuck::Zeroable::zeroed();
+    tree_account.height = MERKLE_TREE_HEIGHT;
+    tree_account.root_history_size = DEFAULT_ROOT_HISTORY_SIZE;
+    MerkleTree::initialize::<Poseidon>(&mut tree_account).unwrap();
+    accounts.insert(
+        find_merkle_tree_address(&program_id, 0, 0).0,
+        zero_copy_account(&tree_account),
+    );
+
+    let (verifying_key, proof) = accepting_verifying_key();
+    accounts.insert(
+        find_verifying_key_address(&program_id, yona::CIRCUIT_TRANSACTION2).0,
+        zero_copy_account(&verifying_key),
+    );
+
+    for mint in [mint, output_mint, native_mint::ID] {
+        let (mint_config_address, mint_config_bump) =
+            find_mint_config_address(&program_id, &global_config_address, &mint);
+        accounts.insert(
+            mint_config_address,
+            anchor_account(&MintConfig {
+                global_config: global_config_address,
+                mint,
+                enabled: true,
+                min_deposit_amount: 0,
+                max_deposit_amount: u64::MAX,
+                deposit_fee_rate: None,
+                withdrawal_fee_rate: None,
+                bump: mint_config_bump,
+            }),
+        );
+        accounts.insert(mint, token_account(anchor_spl::token::ID));
+    }
+    accounts.insert(
+        sysvar::clock::ID,
+        solana_sdk::account::create_account_for_test(&Clock::default()),
+    );
+
+    if registered {
+        let (relayer_address, relayer_bump) =
+            find_relayer_address(&program_id, &global_config_address, &relayer.pubkey());
+        accounts.insert(
+            relayer_address,
+            anchor_account(&RelayerAccount {
+                global_config: global_config_address,
+                authority: relayer.pubkey(),
+                fee_recipient,
+                fee_rate: 30,
+                bond: 0,
+                bump: relayer_bump,
+                unbonded_at: 0,
+            }),
+        );
+    }
+
+    Setup {
+        accounts,
+        sent: Arc::default(),
+        relayer,
+        fee_recipient,
+        mint,
+        output_mint,
+        root: tree_account.root,
+        proof,
+        lookup_table: None,
+    }
+}
+
+fn router(setup: &Setup) -> Router {
+    let cluster = FakeCluster {
+        accounts: setup.accounts.clone(),
+        sent: setup.sent.clone(),
+    };
+    let relayer = Relayer::new(
+        cluster,
+        setup.relayer.insecure_clone(),
+        RelayerConfig {
+            pool_id: 0,
+            light_trees: LightTrees::localnet(),
+            lookup_table: setup.lookup_table,
+            compute_unit_limit: DEFAULT_COMPUTE_UNIT_LIMIT,
+        },
+    );
+    api::router(Arc::new(relayer))
+}
+
+async fn send(router: Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = router.oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+fn hex_bytes(byte: u8, len: usize) -> String {
+    format!("{byte:02x}").repeat(len)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Withdrawal of `mint` to `recipient` passing the preflight.
+fn withdraw_request(setup: &Setup, mint: Pubkey, recipient: Pubkey) -> Value {
+    let fee_recipient_account = get_associated_token_address_with_program_id(
+        &setup.fee_recipient,
+        &mint,
+        &anchor_spl::token::ID,
+    );
+    let ext_data_hash = calculate_complete_ext_data_hash(
+        recipient,
+        EXT_AMOUNT,
+        &[],
+        FEE,
+        fee_recipient_account,
+        mint,
+        mint,
+    )
+    .unwrap();
+    let public_amount = -Fr::from(EXT_AMOUNT.unsigned_abs() + FEE);
+
+    json!({
+        "proof": {
+            "proof_a": hex(&setup.proof.proof_a),
+            "proof_b": hex(&setup.proof.proof_b),
+            "proof_c": hex(&setup.proof.proof_c),
+            "root": hex(&setup.root),
+            "public_amount0": hex(&fr_to_be_bytes(&public_amount)),
+            "public_amount1": hex_bytes(0, 32),
+            "ext_data_hash": hex(&fr_to_be_bytes(&Fr::from_le_bytes_mod_order(&ext_data_hash))),
+            "input_nullifiers": [hex_bytes(1, 32), hex_bytes(2, 32)],
+            "output_commitments": [hex_bytes(3, 32), hex_bytes(4, 32)],
+        },
+        "ext_amount": EXT_AMOUNT,
+        "fee": FEE,
+        "input_mint": mint.to_string(),
+        "recipient": recipient.to_string(),
+        "light_proof": {
+            "a": hex_bytes(0, 32),
+            "b": hex_bytes(0, 64),
+            "c": hex_bytes(0, 32),
+            "address_root_indices": [0, 0],
+        },
+    })
+}
+
+fn post_withdraw(withdraw: &Value) -> Request<Body> {
+    Request::post("/withdraw")
+        .header("content-type", "application/json")
+        .body(Body::from(withdraw.to_string()))
+        .unwrap()
+}
+
+fn failed_checks(body: &Value) -> Vec<String> {
+    body["failures"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|failure| failure["check"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn relayer_info_returns_the_registration() {
+    let setup = setup(true);
+    let request = Request::get("/relayer").body(Body::empty()).unwrap();
+
+    let (status, body) = send(router(&setup), request).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["authority"], setup.relayer.pubkey().to_string());
+    assert_eq!(body["fee_recipient"], setup.fee_recipient.to_string());
+    assert_eq!(body["fee_rate"], 30);
+}
+
+#[tokio::test]
+async fn unregistered_relayer_is_rejected() {
+    let setup = setup(false);
+    let request = Request::get("/relayer").body(Body::empty()).unwrap();
+
+    let (status, body) = send(router(&setup), request).await;
+
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert!(body["error"].as_str().unwrap().contains("not registered"));
+}
+
+#[tokio::test]
+async fn withdraw_failing_preflight_is_not_submitted() {
+    let setup = setup(true);
+    let withdraw = json!({
+        "proof": {
+            "proof_a": hex_bytes(0xff, 32),
+            "proof_b": hex_bytes(0xff, 64),
+            "proof_c": hex_bytes(0xff, 32),
+            // Not in the root history of the tree
+            "root": hex_bytes(9, 32),
+            "public_amount0": hex_bytes(0, 32),
+            "public_amount1": hex_bytes(0, 32),
+            "ext_data_hash": hex_bytes(0, 32),
+            "input_nullifiers": [hex_bytes(1, 32), hex_bytes(2, 32)],
+            "output_commitments": [hex_bytes(3, 32), hex_bytes(4, 32)],
+        },
+        "ext_amount": -1_000_000,
+        "fee": 3_000,
+        "input_mint": setup.mint.to_string(),
+        "recipient": Pubkey::new_unique().to_string(),
+        "light_proof": {
+            "a": hex_bytes(0, 32),
+            "b": hex_bytes(0, 64),
+            "c": hex_bytes(0, 32),
+            "address_root_indices": [0, 0],
+        },
+    });
+
+    let (status, body) = send(router(&setup), post_withdraw(&withdraw)).await;
+
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let checks = failed_checks(&body);
+    for check in ["KnownRoot", "ExtDataHash", "Proof"] {
+        assert!(checks.iter().any(|failed| failed == check), "{checks:?}");
+    }
+    assert!(setup.sent.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn unknown_signature_is_not_found() {
+    let setup = setup(true);
+    let signature = Signature::from([7u8; 64]);
+    let request = Request::get(format!("/status/{signature}"))
+        .body(Body::empty())
+        .unwrap();
+
+    let (status, _) = send(router(&setup), request).await;
+
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn swap_failing_preflight_is_not_submitted() {
+    let mut setup = setup(true);
+    let (global_config, _) = find_global_config_address(&yona::ID, 0);
+    // The route pays another account than the output reserve and quotes 1% above the user's
+    // minimum, more than the relayer's 0.3%
+    let jupiter_accounts = [
+        anchor_spl::token::ID,
+        find_swap_authority_address(&yona::ID, &global_config).0,
+        reserve_token_account(&yona::ID, 0, &setup.mint, &anchor_spl::token::ID),
+        Pubkey::new_unique(),
+        jupiter_aggregator::ID,
+        setup.output_mint,
+        jupiter_aggregator::ID,
+        Pubkey::new_unique(),
+        jupiter_aggregator::ID,
+    ]
+    .into_iter()
+    .map(|pubkey| JupiterAccountData {
+        pubkey,
+        is_writable: true,
+    })
+    .collect();
+    let swap = SwapRequest {
+        proof: ProofData {
+            proof_a: [0xff; 32],
+            proof_b: [0xff; 64],
+            proof_c: [0xff; 32],
+            root: [9; 32],
+            public_amount0: [0; 32],
+            public_amount1: [0; 32],
+            ext_data_hash: [0; 32],
+            input_nullifiers: [[1; 32], [2; 32]],
+            output_commitments: [[3; 32], [4; 32]],
+        },
+        ext_amount: -1_000_000,
+        ext_min_amount_out: 990_000,
+        fee: 0,
+        encrypted_output: Vec::new(),
+        input_mint: setup.mint,
+        output_mint: setup.output_mint,
+        jupiter_swap_data: Route {
+            route_plan: vec![],
+            in_amount: 1_000_000,
+            quoted_out_amount: 1_000_000,
+            slippage_bps: 50,
+            platform_fee_bps: 0,
+        }
+        .data(),
+        jupiter_accounts,
+        address_lookup_tables: Vec::new(),
+        retired_tree_index: None,
+        light_proof: LightProofData {
+            a: [0; 32],
+            b: [0; 64],
+            c: [0; 32],
+            address_root_indices: [0, 0],
+        },
+    };
+    let request = |setup: &Setup| {
+        let request = Request::post("/swap")
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::to_string(&swap).unwrap()))
+            .unwrap();
+        send(router(setup), request)
+    };
+
+    // The pool never allowed the aggregator
+    let (status, body) = request(&setup).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let checks = failed_checks(&body);
+    for check in ["Aggregator", "Route", "SwapSurplus"] {
+        assert!(checks.iter().any(|failed| failed == check), "{checks:?}");
+    }
+
+    let (aggregator_config, bump) =
+        find_aggregator_config_address(&yona::ID, &global_config, &jupiter_aggregator::ID);
+    setup.accounts.insert(
+        aggregator_config,
+        anchor_account(&AggregatorConfig {
+            global_config,
+            program_id: jupiter_aggregator::ID,
+            enabled: true,
+            bump,
+        }),
+    );
+    let (status, body) = request(&setup).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let checks = failed_checks(&body);
+    assert!(
+        !checks.iter().any(|failed| failed == "Aggregator"),
+        "{checks:?}"
+    );
+    assert!(checks.iter().any(|failed| failed == "Route"), "{checks:?}");
+    assert!(setup.sent.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn withdraw_is_submitted_through_the_lookup_table() {
+    let mut setup = setup(true);
+    let lookup_table = Pubkey::new_unique();
+    let addresses = protocol_addresses(
+        0,
+        0,
+        &setup.relayer.pubkey(),
+        &[get_associated_token_address_with_program_id(
+            &setup.fee_recipient,
+            &setup.mint,
+            &anchor_spl::token::ID,
+        )],
+        &LightTrees::localnet(),
+    );
+    let table = AddressLookupTable {
+        meta: LookupTableMeta::new(setup.relayer.pubkey()),
+        addresses: Cow::Borrowed(&addresses),
+    };
+    setup.accounts.insert(
+        lookup_table,
+        Account {
+            lamports: 1,
+            data: table.serialize_for_tests().unwrap(),
+            owner: solana_sdk::address_lookup_table::program::ID,
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+    setup.lookup_table = Some(lookup_table);
+    let withdraw = withdraw_request(&setup, setup.mint, Pubkey::new_unique());
+
+    let (status, body) = send(router(&setup), post_withdraw(&withdraw)).await;
+
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let sent = setup.sent.lock().unwrap();
+    assert_eq!(sent.len(), 1);
+    let transaction = &sent[0];
+    assert_eq!(body["signature"], transaction.signatures[0].to_string());
+    assert!(transaction.verify_with_results().iter().all(|verified| *verified));
+
+    let message = &transaction.message;
+    let static_keys = message.static_account_keys();
+    assert_eq!(static_keys[0], setup.relayer.pubkey());
+    assert_eq!(
+        static_keys[message.instructions()[0].program_id_index as usize],
+        solana_compute_budget_interface::ID
+    );
+    let lookups = message.address_table_lookups().unwrap();
+    assert_eq!(lookups.len(), 1);
+    assert_eq!(lookups[0].account_key, lookup_table);
+    // The protocol accounts are loaded from the table instead of the message
+    let (global_config, _) = find_global_config_address(&yona::ID, 0);
+    assert!(!static_keys.contains(&global_config));
+    assert!(!lookups[0].readonly_indexes.is_empty());
+}
+
+#[tokio::test]
+async fn wrapped_sol_withdraw_pays_the_recipient_through_the_relayer_token_account() {
+    let setup = setup(true);
+    let recipient = Pubkey::new_unique();
+    let mut withdraw = withdraw_request(&setup, native_mint::ID, recipient);
+    // Not an account of the cluster, fetching it would fail the request
+    withdraw["recipient_token_account"] = json!(Pubkey::new_unique().to_string());
+
+    let (status, body) = send(router(&setup), post_withdraw(&withdraw)).await;
+
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let sent = setup.sent.lock().unwrap();
+    let message = &sent[0].message;
+    let keys = message.static_account_keys();
+    let instruction = message
+        .instructions()
+        .iter()
+        .find(|instruction| keys[instruction.program_id_index as usize] == yona::ID)
+        .unwrap();
+    let account = |index: usize| instruction.accounts[index] as usize;
+    let relayer_token_account = get_associated_token_address_with_program_id(
+        &setup.relayer.pubkey(),
+        &native_mint::ID,
+        &anchor_spl::token::ID,
+    );
+    // The program unwraps into the relayer's token account and pays the lamports to `recipient`
+    assert_eq!(keys[account(7)], recipient);
+    assert!(message.is_maybe_writable(account(7), None));
+    assert_eq!(keys[account(8)], relayer_token_account);
+    assert_eq!(keys[account(10)], setup.relayer.pubkey());
+}
@@ -0,0 +1,194 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { BankrunProvider } from "anchor-bankrun";
+import { Clock, ProgramTestContext, startAnchor } from "solana-bankrun";
+import { Keypair, LAMPORTS_PER_SOL, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { Yona } from "../target/types/yona";
+import IDL from "../target/idl/yona.json";
+import { findGlobalConfigPDA, findRelayerPDA, findVerifyingKeyPDA } from "./lib/derive";
+import { executeInitialize, executeRegisterRelayer } from "./instructions";
+import { RELAYER_UNBONDING_SECONDS, WITHDRAW_FEE_RATE } from "./lib/constants";
+import { expectProgramError } from "./lib/transaction";
+import {
+  CIRCUIT_TRANSACTION2,
+  TRANSACTION2_VERIFYING_KEY,
+  VerifyingKey,
+  verifyingKeyData,
+  verifyingKeyHash
+} from "./lib/verifying_key";
+
+const TIMELOCK_SECONDS = 100;
+const MIGRATION_WINDOW_SECONDS = 50;
+const CHUNK_SIZE = 800;
+
+// Moves to the next slot (new blockhash, so retried instructions aren't deduplicated)
+// and `seconds` later
+async function advanceClock(context: ProgramTestContext, seconds: number) {
+  const clock = await context.banksClient.getClock();
+  context.warpToSlot(clock.slot + BigInt(1));
+  context.setClock(new Clock(
+    clock.slot + BigInt(1),
+    clock.epochStartTimestamp,
+    clock.epoch,
+    clock.leaderScheduleEpoch,
+    clock.unixTimestamp + BigInt(seconds)
+  ));
+}
+
+async function startProgram(): Promise<[ProgramTestContext, Program<Yona>]> {
+  const context = await startAnchor("", [], []);
+  const provider = new BankrunProvider(context);
+  anchor.setProvider(provider);
+  return [context, new Program<Yona>(IDL as Yona, provider)];
+}
+
+describe("verifying key upgrades", () => {
+  let context: ProgramTestContext;
+  let program: Program<Yona>;
+  let admin: Keypair;
+  let verifyingKey: PublicKey;
+
+  const advance = (seconds: number) => advanceClock(context, seconds);
+
+  const stage = (version: number, vk: VerifyingKey) => program.methods
+    .stageVerifyingKey(version, verifyingKeyHash(vk))
+    .accountsStrict({ verifyingKey, authority: admin.publicKey })
+    .rpc();
+
+  const write = (data: Buffer, from: number, to: number = data.length) => program.methods
+    .writeVerifyingKey(from, data.subarray(from, to))
+    .accountsStrict({ verifyingKey, authority: admin.publicKey })
+    .rpc();
+
+  const activate = () => program.methods
+    .activateVerifyingKey()
+    .accountsStrict({ verifyingKey, authority: admin.publicKey })
+    .rpc();
+
+  async function upload(vk: VerifyingKey) {
+    const data = verifyingKeyData(vk);
+    for (let offset = 0; offset < data.length; offset += CHUNK_SIZE) {
+      await write(data, offset, offset + CHUNK_SIZE);
+    }
+  }
+
+  before(async () => {
+    [context, program] = await startProgram();
+    admin = context.payer;
+    [verifyingKey] = findVerifyingKeyPDA(program.programId, CIRCUIT_TRANSACTION2);
+
+    await program.methods
+      .initializeVerifyingKey(
+        CIRCUIT_TRANSACTION2,
+        new anchor.BN(TIMELOCK_SECONDS),
+        new anchor.BN(MIGRATION_WINDOW_SECONDS)
+      )
+      .accountsStrict({
+        verifyingKey,
+        authority: admin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId
+      })
+      .rpc();
+  });
+
+  it("Activates a complete key once the timelock has passed", async () => {
+    const data = verifyingKeyData(TRANSACTION2_VERIFYING_KEY);
+    await stage(1, TRANSACTION2_VERIFYING_KEY);
+
+    // The last IC point is missing
+    await write(data, 0, data.length - 64);
+    await advance(TIMELOCK_SECONDS + 1);
+    await expectProgramError(activate(), "VerifyingKeyHashMismatch");
+
+    // The last write restarts the timelock
+    await write(data, data.length - 64);
+    await advance(0);
+    await expectProgramError(activate(), "VerifyingKeyTimelocked");
+
+    await advance(TIMELOCK_SECONDS + 1);
+    await activate();
+
+    const account = await program.account.verifyingKeyAccount.fetch(verifyingKey);
+    expect(account.current.version).to.equal(1);
+    expect(account.pending.version).to.equal(0);
+  });
+
+  it("Rejects a key with an identity point", async () => {
+    // Hash and bytes agree, but delta is the point at infinity
+    const forged: VerifyingKey = {
+      ...TRANSACTION2_VERIFYING_KEY,
+      vkDeltaG2: new Array(128).fill(0),
+    };
+    await stage(2, forged);
+    await upload(forged);
+    await advance(TIMELOCK_SECONDS + 1);
+    await expectProgramError(activate(), "InvalidVerifyingKey");
+  });
+
+  it("Keeps the previous key during the migration window", async () => {
+    await stage(2, TRANSACTION2_VERIFYING_KEY);
+    await upload(TRANSACTION2_VERIFYING_KEY);
+    await advance(TIMELOCK_SECONDS + 1);
+    await activate();
+
+    const { unixTimestamp } = await context.banksClient.getClock();
+    const account = await program.account.verifyingKeyAccount.fetch(verifyingKey);
+    expect(account.current.version).to.equal(2);
+    expect(account.previous.version).to.equal(1);
+    expect(account.previousValidUntil.toString()).to.equal(
+      (unixTimestamp + BigInt(MIGRATION_WINDOW_SECONDS)).toString()
+    );
+  });
+});
+
+describe("relayer deregistration", () => {
+  let context: ProgramTestContext;
+  let program: Program<Yona>;
+  let relayer: Keypair;
+  let relayerAccount: PublicKey;
+
+  const advance = (seconds: number) => advanceClock(context, seconds);
+
+  const requestDeregistration = () => program.methods
+    .requestRelayerDeregistration()
+    .accountsStrict({ relayerAccount, authority: relayer.publicKey })
+    .rpc();
+
+  const deregister = () => program.methods
+    .deregisterRelayer()
+    .accountsStrict({ relayerAccount, authority: relayer.publicKey })
+    .rpc();
+
+  before(async () => {
+    [context, program] = await startProgram();
+    relayer = context.payer;
+    [relayerAccount] = findRelayerPDA(program.programId, findGlobalConfigPDA(program.programId)[0], relayer.publicKey);
+
+    await executeInitialize(program, [relayer]);
+    await executeRegisterRelayer(
+      program, [relayer], WITHDRAW_FEE_RATE, Keypair.generate().publicKey, new anchor.BN(LAMPORTS_PER_SOL)
+    );
+  });
+
+  it("Returns the bond once the unbonding period has passed", async () => {
+    // Deregistration must be requested first
+    await expectProgramError(deregister(), "RelayerBondUnbonding");
+
+    await requestDeregistration();
+    await advance(0);
+    await expectProgramError(requestDeregistration(), "RelayerDeregistering");
+
+    await advance(RELAYER_UNBONDING_SECONDS - 1);
+    await expectProgramError(deregister(), "RelayerBondUnbonding");
+
+    await advance(1);
+    const balanceBefore = await context.banksClient.getBalance(relayer.publicKey);
+    await deregister();
+    const balanceAfter = await context.banksClient.getBalance(relayer.publicKey);
+
+    // Bond and rent, less the transaction fee
+    expect(await context.banksClient.getAccount(relayerAccount)).to.be.null;
+    expect(balanceAfter - balanceBefore > BigInt(LAMPORTS_PER_SOL)).to.be.true;
+  });
+});
@@ -0,0 +1,200 @@
+// `#[program]` expands to `target_os = "solana"` checks and the deprecated IDL account realloc
+#![allow(unexpected_cfgs, deprecated)]
+
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
+
+declare_id!("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4");
+
+/// Test double of the Jupiter aggregator, loaded at its address by the local validator.
+///
+/// `route` takes the instruction data and the leading accounts of Jupiter's `route`. The route
+/// plan is not executed: the input is paid into `input_vault` and the quoted output is paid out
+/// of `output_vault`, both owned by the `vault` PDA. `configure` makes the next swaps misbehave.
+#[program]
+pub mod mock_jupiter {
+    use super::*;
+
+    pub fn configure(ctx: Context<Configure>, input_shortfall: u64, drain_amount: u64) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.input_shortfall = input_shortfall;
+        config.drain_amount = drain_amount;
+        config.bump = ctx.bumps.config;
+        Ok(())
+    }
+
+    pub fn route<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Route<'info>>,
+        route_plan: Vec<RoutePlanStep>,
+        in_amount: u64,
+        quoted_out_amount: u64,
+        _slippage_bps: u16,
+        platform_fee_bps: u8,
+    ) -> Result<()> {
+        require!(
+            route_plan.is_empty() && platform_fee_bps == 0,
+            MockJupiterError::UnsupportedRoute
+        );
+        let config = &ctx.accounts.config;
+        let vault_seeds: &[&[u8]] = &[b"vault", &[ctx.bumps.vault_authority]];
+
+        // The input is pulled by the caller's transfer authority, less the configured shortfall
+        let input_amount = in_amount
+            .checked_sub(config.input_shortfall)
+            .ok_or(MockJupiterError::UnsupportedRoute)?;
+        transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.user_source_token_account.to_account_info(),
+                    mint: ctx.accounts.source_mint.to_account_info(),
+                    to: ctx.accounts.input_vault.to_account_info(),
+                    authority: ctx.accounts.user_transfer_authority.to_account_info(),
+                },
+            ),
+            input_amount,
+            ctx.accounts.source_mint.decimals,
+        )?;
+
+        transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.output_vault.to_account_info(),
+                    mint: ctx.accounts.destination_mint.to_account_info(),
+                    to: ctx.accounts.user_destination_token_account.to_account_info(),
+                    authority: ctx.accounts.vault_authority.to_account_info(),
+                },
+                &[vault_seeds],
+            ),
+            quoted_out_amount,
+            ctx.accounts.destination_mint.decimals,
+        )?;
+
+        // Takes tokens out of an account whose mint has the vault as permanent delegate
+        if config.drain_amount > 0 {
+            let [drained, drained_mint, drained_token_program, drain_destination] =
+                ctx.remaining_accounts
+            else {
+                return err!(MockJupiterError::MissingDrainAccounts);
+            };
+            let decimals = InterfaceAccount::<Mint>::try_from(drained_mint)?.decimals;
+            transfer_checked(
+                CpiContext::new_with_signer(
+                    drained_token_program.clone(),
+                    TransferChecked {
+                        from: drained.clone(),
+                        mint: drained_mint.clone(),
+                        to: drain_destination.clone(),
+                        authority: ctx.accounts.vault_authority.to_account_info(),
+                    },
+                    &[vault_seeds],
+                ),
+                config.drain_amount,
+                decimals,
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Step of a Jupiter route plan. Plans are not executed, only empty ones are accepted, so the
+/// swap variant of the step is left out.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RoutePlanStep {
+    pub percent: u8,
+    pub input_index: u8,
+    pub output_index: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct MockConfig {
+    pub input_shortfall: u64, // input left in the source token account
+    pub drain_amount: u64,    // taken out of the drained account, 0 to leave it alone
+    pub bump: u8,
+}
+
+#[derive(Accounts)]
+pub struct Configure<'info> {
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + MockConfig::INIT_SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, MockConfig>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Route<'info> {
+    // Accounts of Jupiter's `route`
+    pub token_program: Interface<'info, TokenInterface>,
+
+    pub user_transfer_authority: Signer<'info>,
+
+    #[account(mut)]
+    pub user_source_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub user_destination_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: unused, the output goes to `user_destination_token_account`
+    pub destination_token_account: Option<UncheckedAccount<'info>>,
+
+    pub destination_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// CHECK: unused, platform fees are rejected
+    pub platform_fee_account: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: unused, no events are emitted
+    pub event_authority: UncheckedAccount<'info>,
+
+    /// CHECK: unused
+    pub program: UncheckedAccount<'info>,
+
+    // Mock accounts, passed where the route plan accounts go
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, MockConfig>>,
+
+    pub source_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// CHECK: owns the vaults and signs their transfers
+    #[account(seeds = [b"vault"], bump)]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        token::mint = source_mint,
+        token::authority = vault_authority,
+        token::token_program = token_program,
+    )]
+    pub input_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = destination_mint,
+        token::authority = vault_authority,
+        token::token_program = token_program,
+    )]
+    pub output_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    // Remaining accounts, only when draining:
+    //   [drained token account, its mint, its token program, destination token account]
+}
+
+#[error_code]
+pub enum MockJupiterError {
+    #[msg("Only empty route plans without platform fee are supported")]
+    UnsupportedRoute,
+    #[msg("Draining needs the drained account, its mint, its token program and a destination")]
+    MissingDrainAccounts,
+}
@@ -1,16 +1,31 @@
 import * as anchor from "@coral-xyz/anchor";
-import { createAssociatedTokenAccountInstruction, getAssociatedTokenAddressSync, TOKEN_PROGRAM_ID, createTransferInstruction } from "@solana/spl-token";
+import { createAssociatedTokenAccountInstruction, getAssociatedTokenAddressSync, TOKEN_PROGRAM_ID, createTransferInstruction, NATIVE_MINT, createApproveCheckedInstruction } from "@solana/spl-token";
 import { ComputeBudgetProgram, Keypair, LAMPORTS_PER_SOL, PublicKey, Transaction, TransactionInstruction, AccountMeta } from "@solana/web3.js";
 import { Yona } from "../target/types/yona";
 import {
   findNullifierPDAs,
   findMerkleTreePDA,
   findTreeTokenAccountPDA,
-  findGlobalConfigPDA
+  findGlobalConfigPDA,
+  findMintConfigPDA,
+  findVerifyingKeyPDA,
+  findRelayerPDA,
+  findDepositAuthorityPDA,
+  findAggregatorConfigPDA,
+  findSwapAuthorityPDA
 } from "./lib/derive";
+import {
+  CIRCUIT_TRANSACTION2,
+  CIRCUIT_TRANSACTION4,
+  TRANSACTION2_VERIFYING_KEY,
+  VerifyingKey,
+  verifyingKeyData,
+  verifyingKeyHash
+} from "./lib/verifying_key";
 import { BanksClient, ProgramTestContext } from "solana-bankrun";
 import { createMint } from "./lib/token";
-import { ExtData, SwapData } from "./lib/types";
+import { ExtData, MintConfigParams, SwapData } from "./lib/types";
+import { DEFAULT_HEIGHT, DEFAULT_MINT_CONFIG, DEFAULT_POOL_ID, INITIAL_TREE_INDEX, ROOT_HISTORY_SIZE } from "./lib/constants";
 import {
   bn,
   defaultStaticAccountsStruct,
@@ -58,6 +73,16 @@ export interface SwapExtDataMinified {
   fee: anchor.BN;
 }
 
+/**
+ * Minified dual-token external data structure (for on-chain)
+ */
+export interface DualExtDataMinified {
+  extAmount0: anchor.BN;
+  fee0: anchor.BN;
+  extAmount1: anchor.BN;
+  fee1: anchor.BN;
+}
+
 /**
  * Helper function to create ExtDataMinified from ExtData
  * @param extData - Full ExtData object
@@ -98,6 +123,31 @@ export async function buildDepositInstruction(
   throw new Error("buildDepositInstruction is deprecated. Use buildDepositWithLightNullifiersInstruction instead.");
 }
 
+/**
+ * Pool and merkle trees a transaction operates on
+ */
+export interface PoolTarget {
+  poolId?: number; // defaults to the pool created by `initialize`
+  treeIndex?: number; // `GlobalConfig.activeTreeIndex` of the pool
+  retiredTreeIndex?: number; // tree of the proof root, when it was rolled over
+}
+
+/**
+ * Derive the global config, active tree and retired tree accounts of a pool
+ * @param programId - Program ID
+ * @param pool - Pool and trees to use
+ * @returns Global config, tree account and retired tree account (null when unset)
+ */
+export function findPoolAccounts(programId: PublicKey, pool: PoolTarget = {}) {
+  const poolId = pool.poolId ?? DEFAULT_POOL_ID;
+  const [globalConfig] = findGlobalConfigPDA(programId, poolId);
+  const [treeAccount] = findMerkleTreePDA(programId, poolId, pool.treeIndex ?? INITIAL_TREE_INDEX);
+  const retiredTreeAccount = pool.retiredTreeIndex === undefined
+    ? null
+    : findMerkleTreePDA(programId, poolId, pool.retiredTreeIndex)[0];
+  return { globalConfig, treeAccount, retiredTreeAccount };
+}
+
 /**
  * Light Protocol packed address tree info structure
  */
@@ -116,6 +166,7 @@ export interface PackedAddressTreeInfo {
  * @param signer - Transaction signer public key
  * @param inputMint - Token mint address
  * @param lightRpc - Light Protocol RPC client
+ * @param pool - Pool and trees, defaults to the first tree of the default pool
  * @returns Transaction instruction
  */
 export async function buildDepositWithLightNullifiersInstruction(
@@ -124,11 +175,11 @@ export async function buildDepositWithLightNullifiersInstruction(
   extData: ExtData,
   signer: PublicKey,
   inputMint: PublicKey,
-  lightRpc: Rpc
+  lightRpc: Rpc,
+  pool: PoolTarget = {}
 ): Promise<TransactionInstruction[]> {
   // Derive all necessary PDAs
-  const [treeAccount] = findMerkleTreePDA(program.programId);
-  const [globalConfig] = findGlobalConfigPDA(program.programId);
+  const { globalConfig, treeAccount, retiredTreeAccount } = findPoolAccounts(program.programId, pool);
 
   // Derive reserve token accounts
   const reserveTokenAccount = getAssociatedTokenAddressSync(
@@ -240,8 +291,11 @@ export async function buildDepositWithLightNullifiersInstruction(
     )
     .accountsStrict({
       treeAccount,
+      retiredTreeAccount,
       globalConfig,
+      verifyingKey: findVerifyingKeyPDA(program.programId, CIRCUIT_TRANSACTION2)[0],
       inputMint: inputMint,
+      mintConfig: findMintConfigPDA(program.programId, globalConfig, inputMint)[0],
       reserveTokenAccount: reserveTokenAccount,
       feeRecipientAccount: extData.feeRecipient,
       userTokenAccount: userTokenAccount,
@@ -257,6 +311,140 @@ export async function buildDepositWithLightNullifiersInstruction(
   return ixs;
 }
 
+/**
+ * Build deposit instruction from any token account the authority owns or is delegated over
+ * (multisig vaults, program-owned or non-associated token accounts)
+ * @param program - Anchor program instance
+ * @param proof - ZK proof data
+ * @param extData - External data (amount, fee, fee recipient)
+ * @param authority - Owner or delegate of the source token account
+ * @param payer - Pays the nullifier accounts, may be the authority
+ * @param sourceTokenAccount - Token account the deposit is taken from
+ * @param inputMint - Token mint address
+ * @param lightRpc - Light Protocol RPC client
+ * @returns Transaction instruction
+ */
+export async function buildDepositFromTokenAccountInstruction(
+  program: anchor.Program<Yona>,
+  proof: Proof,
+  extData: ExtData,
+  authority: PublicKey,
+  payer: PublicKey,
+  sourceTokenAccount: PublicKey,
+  inputMint: PublicKey,
+  lightRpc: Rpc
+): Promise<TransactionInstruction[]> {
+  const [treeAccount] = findMerkleTreePDA(program.programId);
+  const [globalConfig] = findGlobalConfigPDA(program.programId);
+  const reserveTokenAccount = getAssociatedTokenAddressSync(inputMint, globalConfig, true);
+
+  const { lightProof, addressTreeInfos, outputStateTreeIndex, remainingAccounts } =
+    await packLightNullifiers(program, proof, lightRpc);
+
+  const instruction = await program.methods
+    .depositFromTokenAccount(
+      proof,
+      createExtDataMinified(extData),
+      extData.encryptedOutput,
+      lightProof,
+      addressTreeInfos[0],
+      addressTreeInfos[1],
+      outputStateTreeIndex
+    )
+    .accountsStrict({
+      treeAccount,
+      retiredTreeAccount: null,
+      globalConfig,
+      verifyingKey: findVerifyingKeyPDA(program.programId, CIRCUIT_TRANSACTION2)[0],
+      inputMint,
+      mintConfig: findMintConfigPDA(program.programId, globalConfig, inputMint)[0],
+      reserveTokenAccount,
+      feeRecipientAccount: extData.feeRecipient,
+      userTokenAccount: sourceTokenAccount,
+      authority,
+      payer,
+      systemProgram: anchor.web3.SystemProgram.programId,
+      tokenProgram: TOKEN_PROGRAM_ID
+    })
+    .remainingAccounts(remainingAccounts)
+    .instruction();
+
+  return [instruction];
+}
+
+/**
+ * Build a relayed deposit: the depositor only signs the approval of the deposit authority,
+ * the relayer pays the transaction and the nullifier accounts
+ * @param program - Anchor program instance
+ * @param proof - ZK proof data
+ * @param extData - External data (amount, fee, relayer fee recipient token account)
+ * @param depositor - Owner of the deposited token account, signs the approval
+ * @param relayer - Registered relayer, fee payer
+ * @param inputMint - Token mint address
+ * @param decimals - Decimals of the mint
+ * @param lightRpc - Light Protocol RPC client
+ * @returns Approve and deposit instructions
+ */
+export async function buildDepositRelayedInstruction(
+  program: anchor.Program<Yona>,
+  proof: Proof,
+  extData: ExtData,
+  depositor: PublicKey,
+  relayer: PublicKey,
+  inputMint: PublicKey,
+  decimals: number,
+  lightRpc: Rpc
+): Promise<TransactionInstruction[]> {
+  const [treeAccount] = findMerkleTreePDA(program.programId);
+  const [globalConfig] = findGlobalConfigPDA(program.programId);
+  const reserveTokenAccount = getAssociatedTokenAddressSync(inputMint, globalConfig, true);
+  const userTokenAccount = getAssociatedTokenAddressSync(inputMint, depositor, true);
+  const [depositAuthority] = findDepositAuthorityPDA(program.programId, proof.outputCommitments[0]);
+
+  const approveInstruction = createApproveCheckedInstruction(
+    userTokenAccount,
+    inputMint,
+    depositAuthority,
+    depositor,
+    BigInt(extData.extAmount.toString()) + BigInt(extData.fee.toString()),
+    decimals
+  );
+
+  const { lightProof, addressTreeInfos, outputStateTreeIndex, remainingAccounts } =
+    await packLightNullifiers(program, proof, lightRpc);
+
+  const depositInstruction = await program.methods
+    .depositRelayed(
+      proof,
+      createExtDataMinified(extData),
+      extData.encryptedOutput,
+      lightProof,
+      addressTreeInfos[0],
+      addressTreeInfos[1],
+      outputStateTreeIndex
+    )
+    .accountsStrict({
+      treeAccount,
+      retiredTreeAccount: null,
+      globalConfig,
+      verifyingKey: findVerifyingKeyPDA(program.programId, CIRCUIT_TRANSACTION2)[0],
+      inputMint,
+      mintConfig: findMintConfigPDA(program.programId, globalConfig, inputMint)[0],
+      reserveTokenAccount,
+      feeRecipientAccount: extData.feeRecipient,
+      userTokenAccount,
+      depositAuthority,
+      relayer,
+      relayerAccount: findRelayerPDA(program.programId, globalConfig, relayer)[0],
+      systemProgram: anchor.web3.SystemProgram.programId,
+      tokenProgram: TOKEN_PROGRAM_ID
+    })
+    .remainingAccounts(remainingAccounts)
+    .instruction();
+
+  return [approveInstruction, depositInstruction];
+}
+
 /**
  * Build withdraw instruction (DEPRECATED - use buildWithdrawWithLightNullifiersInstruction instead)
  * This function is deprecated as withdraw now requires Light Protocol nullifiers.
@@ -280,6 +468,8 @@ export async function buildWithdrawInstruction(
  * @param signer - Transaction signer public key (relayer)
  * @param inputMint - Token mint address
  * @param lightRpc - Light Protocol RPC client
+ * @param pool - Pool and trees, defaults to the first tree of the default pool
+ * @param registeredRelayer - False for a self-relayed withdrawal by an unregistered signer, which takes no fee
  * @returns Transaction instruction
  */
 export async function buildWithdrawWithLightNullifiersInstruction(
@@ -288,11 +478,12 @@ export async function buildWithdrawWithLightNullifiersInstruction(
   extData: ExtData,
   signer: PublicKey,
   inputMint: PublicKey,
-  lightRpc: Rpc
+  lightRpc: Rpc,
+  pool: PoolTarget = {},
+  registeredRelayer: boolean = true
 ): Promise<TransactionInstruction[]> {
   // Derive all necessary PDAs
-  const [treeAccount] = findMerkleTreePDA(program.programId);
-  const [globalConfig] = findGlobalConfigPDA(program.programId);
+  const { globalConfig, treeAccount, retiredTreeAccount } = findPoolAccounts(program.programId, pool);
 
   // Derive reserve token account
   const reserveTokenAccount = getAssociatedTokenAddressSync(
@@ -402,13 +593,17 @@ export async function buildWithdrawWithLightNullifiersInstruction(
     )
     .accountsStrict({
       treeAccount,
+      retiredTreeAccount,
       globalConfig,
+      verifyingKey: findVerifyingKeyPDA(program.programId, CIRCUIT_TRANSACTION2)[0],
       inputMint: inputMint,
+      mintConfig: findMintConfigPDA(program.programId, globalConfig, inputMint)[0],
       reserveTokenAccount: reserveTokenAccount,
       recipientTokenAccount: recipientTokenAccount,
       feeRecipientAccount: extData.feeRecipient,
       recipient: extData.recipient,
       relayer: signer,
+      relayerAccount: registeredRelayer ? findRelayerPDA(program.programId, globalConfig, signer)[0] : null,
       systemProgram: anchor.web3.SystemProgram.programId,
       tokenProgram: TOKEN_PROGRAM_ID
     })
@@ -420,12 +615,225 @@ export async function buildWithdrawWithLightNullifiersInstruction(
   return ixs;
 }
 
+/**
+ * Derive the nullifier addresses, fetch the Light validity proof and pack the Light accounts
+ * @param program - Anchor program instance
+ * @param proof - ZK proof data
+ * @param lightRpc - Light Protocol RPC client
+ * @returns Light proof, packed address tree infos, output state tree index and remaining accounts
+ */
+export async function packLightNullifiers(
+  program: anchor.Program<Yona>,
+  proof: Proof,
+  lightRpc: Rpc
+) {
+  const { merkleTree: outputStateTree, addressTree, addressQueue, nullifierQueue } = defaultTestStateTreeAccounts();
+
+  const nullifierAddresses = proof.inputNullifiers.map((nullifier) =>
+    deriveAddress(
+      deriveAddressSeed([Buffer.from("nullifier"), Buffer.from(nullifier)], program.programId),
+      addressTree
+    )
+  );
+
+  // Get validity proof for the new addresses (proves they don't exist yet)
+  const proofResult = await lightRpc.getValidityProofV0(
+    [],
+    nullifierAddresses.map((address) => ({
+      tree: addressTree,
+      queue: addressQueue,
+      address: bn(address.toBytes()),
+    }))
+  );
+
+  const systemAccountConfig = SystemAccountMetaConfig.new(program.programId);
+  const packedAccounts = PackedAccounts.newWithSystemAccounts(systemAccountConfig);
+  const addressMerkleTreePubkeyIndex = packedAccounts.insertOrGet(addressTree);
+  const addressQueuePubkeyIndex = packedAccounts.insertOrGet(addressQueue);
+  const outputStateTreeIndex = packedAccounts.insertOrGet(outputStateTree);
+  packedAccounts.insertOrGet(nullifierQueue);
+
+  const addressTreeInfos: PackedAddressTreeInfo[] = nullifierAddresses.map((_, i) => ({
+    rootIndex: proofResult.rootIndices[i] ?? proofResult.rootIndices[0],
+    addressMerkleTreePubkeyIndex,
+    addressQueuePubkeyIndex,
+  }));
+
+  // Light validity proof - Anchor expects it wrapped in { 0: ... } format
+  const lightProof = {
+    0: {
+      a: Array.from(proofResult.compressedProof.a),
+      b: Array.from(proofResult.compressedProof.b),
+      c: Array.from(proofResult.compressedProof.c),
+    }
+  };
+
+  return {
+    lightProof,
+    addressTreeInfos,
+    outputStateTreeIndex,
+    remainingAccounts: packedAccounts.toAccountMetas().remainingAccounts,
+  };
+}
+
+/**
+ * Build transact instruction (private transfer, no tokens are moved)
+ * @param program - Anchor program instance
+ * @param proof - ZK proof data (both public amounts must be 0)
+ * @param encryptedOutput - Encrypted output notes
+ * @param payer - Transaction payer public key
+ * @param mint - Token mint of the notes
+ * @param lightRpc - Light Protocol RPC client
+ * @returns Transaction instruction
+ */
+export async function buildTransactInstruction(
+  program: anchor.Program<Yona>,
+  proof: Proof,
+  encryptedOutput: Buffer,
+  payer: PublicKey,
+  mint: PublicKey,
+  lightRpc: Rpc
+): Promise<TransactionInstruction[]> {
+  const [treeAccount] = findMerkleTreePDA(program.programId);
+  const [globalConfig] = findGlobalConfigPDA(program.programId);
+  const light = await packLightNullifiers(program, proof, lightRpc);
+
+  const instruction = await program.methods
+    .transact(
+      proof,
+      encryptedOutput,
+      light.lightProof,
+      light.addressTreeInfos[0],
+      light.addressTreeInfos[1],
+      light.outputStateTreeIndex
+    )
+    .accountsStrict({
+      treeAccount,
+      retiredTreeAccount: null,
+      globalConfig,
+      verifyingKey: findVerifyingKeyPDA(program.programId, CIRCUIT_TRANSACTION2)[0],
+      mint,
+      mintConfig: findMintConfigPDA(program.programId, globalConfig, mint)[0],
+      payer,
+      systemProgram: anchor.web3.SystemProgram.programId
+    })
+    .remainingAccounts(light.remainingAccounts)
+    .instruction();
+
+  return [instruction];
+}
+
+/**
+ * Build transact4 instruction (4-input / 2-output private transfer, consolidates notes).
+ * proof.inputNullifiers must hold the 4 nullifiers of the transaction4 circuit.
+ */
+export async function buildTransact4Instruction(
+  program: anchor.Program<Yona>,
+  proof: Proof,
+  encryptedOutput: Buffer,
+  payer: PublicKey,
+  mint: PublicKey,
+  lightRpc: Rpc
+): Promise<TransactionInstruction[]> {
+  const [treeAccount] = findMerkleTreePDA(program.programId);
+  const [globalConfig] = findGlobalConfigPDA(program.programId);
+  const light = await packLightNullifiers(program, proof, lightRpc);
+
+  const instruction = await program.methods
+    .transact4(
+      proof,
+      encryptedOutput,
+      light.lightProof,
+      light.addressTreeInfos,
+      light.outputStateTreeIndex
+    )
+    .accountsStrict({
+      treeAccount,
+      retiredTreeAccount: null,
+      globalConfig,
+      verifyingKey: findVerifyingKeyPDA(program.programId, CIRCUIT_TRANSACTION4)[0],
+      mint,
+      mintConfig: findMintConfigPDA(program.programId, globalConfig, mint)[0],
+      payer,
+      systemProgram: anchor.web3.SystemProgram.programId
+    })
+    .remainingAccounts(light.remainingAccounts)
+    .instruction();
+
+  return [
+    ComputeBudgetProgram.setComputeUnitLimit({ units: 1_400_000 }),
+    instruction,
+  ];
+}
+
+/**
+ * Build transact_dual instruction (deposit and/or withdraw two mints with one proof).
+ * Deposits are taken from and withdrawals sent to the user's ATAs of both mints.
+ * Fees are paid to `relayer`, a registered relayer, which can be left out when both fees are 0.
+ */
+export async function buildTransactDualInstruction(
+  program: anchor.Program<Yona>,
+  proof: Proof,
+  extDataMinified: DualExtDataMinified,
+  encryptedOutput: Buffer,
+  user: PublicKey,
+  mint0: PublicKey,
+  mint1: PublicKey,
+  feeRecipient0: PublicKey,
+  feeRecipient1: PublicKey,
+  lightRpc: Rpc,
+  relayer: PublicKey | null = null
+): Promise<TransactionInstruction[]> {
+  const [treeAccount] = findMerkleTreePDA(program.programId);
+  const [globalConfig] = findGlobalConfigPDA(program.programId);
+  const light = await packLightNullifiers(program, proof, lightRpc);
+
+  const instruction = await program.methods
+    .transactDual(
+      proof,
+      extDataMinified,
+      encryptedOutput,
+      light.lightProof,
+      light.addressTreeInfos[0],
+      light.addressTreeInfos[1],
+      light.outputStateTreeIndex
+    )
+    .accountsStrict({
+      treeAccount,
+      retiredTreeAccount: null,
+      globalConfig,
+      verifyingKey: findVerifyingKeyPDA(program.programId, CIRCUIT_TRANSACTION2)[0],
+      mint0,
+      mint1,
+      mintConfig0: findMintConfigPDA(program.programId, globalConfig, mint0)[0],
+      mintConfig1: findMintConfigPDA(program.programId, globalConfig, mint1)[0],
+      reserveTokenAccount0: getAssociatedTokenAddressSync(mint0, globalConfig, true),
+      reserveTokenAccount1: getAssociatedTokenAddressSync(mint1, globalConfig, true),
+      userTokenAccount0: getAssociatedTokenAddressSync(mint0, user),
+      userTokenAccount1: getAssociatedTokenAddressSync(mint1, user),
+      feeRecipientAccount0: feeRecipient0,
+      feeRecipientAccount1: feeRecipient1,
+      user,
+      relayerAccount: relayer ? findRelayerPDA(program.programId, globalConfig, relayer)[0] : null,
+      systemProgram: anchor.web3.SystemProgram.programId,
+      tokenProgram0: TOKEN_PROGRAM_ID,
+      tokenProgram1: TOKEN_PROGRAM_ID,
+    })
+    .remainingAccounts(light.remainingAccounts)
+    .instruction();
+
+  return [
+    ComputeBudgetProgram.setComputeUnitLimit({ units: 1_400_000 }),
+    instruction,
+  ];
+}
+
 /**
  * Build swap instruction with Light Protocol nullifiers
  * @param program - Anchor program instance
  * @param proof - ZK proof data
  * @param swapData - Swap data (amounts, fees, etc.)
- * @param signer - Transaction signer public key
+ * @param signer - Transaction signer public key (registered relayer)
  * @param inputMint - Input token mint address
  * @param outputMint - Output token mint address
  * @param lightRpc - Light Protocol RPC client
@@ -570,14 +978,21 @@ export async function buildSwapWithLightNullifiersInstruction(
     )
     .accountsStrict({
       treeAccount,
+      retiredTreeAccount: null,
       globalConfig,
+      verifyingKey: findVerifyingKeyPDA(program.programId, CIRCUIT_TRANSACTION2)[0],
       inputMint: inputMint,
       outputMint: outputMint,
+      inputMintConfig: findMintConfigPDA(program.programId, globalConfig, inputMint)[0],
+      outputMintConfig: findMintConfigPDA(program.programId, globalConfig, outputMint)[0],
       reserveTokenAccountInput: reserveTokenAccountInput,
       reserveTokenAccountOutput: reserveTokenAccountOutput,
       feeRecipientAccount: feeRecipientTokenAccount,
       jupiterProgram: jupiterProgramId,
-      user: signer,
+      aggregatorConfig: findAggregatorConfigPDA(program.programId, globalConfig, jupiterProgramId)[0],
+      swapAuthority: findSwapAuthorityPDA(program.programId, globalConfig)[0],
+      relayer: signer,
+      relayerAccount: findRelayerPDA(program.programId, globalConfig, signer)[0],
       systemProgram: anchor.web3.SystemProgram.programId,
       inputTokenProgram: TOKEN_PROGRAM_ID,
       outputTokenProgram: TOKEN_PROGRAM_ID,
@@ -617,35 +1032,119 @@ export async function buildSwapInstruction(
 }
 
 /**
- * 
- * Execute update deposit limit instruction
+ * Build initialize mint config instruction
+ * @param program - Anchor program instance
+ * @param authority - Authority public key
+ * @param mint - Token mint address to allow
+ * @param params - Mint settings (enabled, deposit bounds, optional fee overrides)
+ * @param poolId - Pool id
+ * @returns Transaction instruction
+ */
+export async function buildInitializeMintConfigInstruction(
+  program: anchor.Program<Yona>,
+  authority: PublicKey,
+  mint: PublicKey,
+  params: MintConfigParams,
+  poolId: number = DEFAULT_POOL_ID
+) {
+  const [globalConfig] = findGlobalConfigPDA(program.programId, poolId);
+  const [mintConfig] = findMintConfigPDA(program.programId, globalConfig, mint);
+
+  return await program.methods
+    .initializeMintConfig(params)
+    .accountsStrict({
+      globalConfig,
+      mint,
+      mintConfig,
+      authority,
+      systemProgram: anchor.web3.SystemProgram.programId
+    })
+    .instruction();
+}
+
+/**
+ * Build update mint config instruction
  * @param program - Anchor program instance
+ * @param authority - Authority public key
+ * @param mint - Token mint address
+ * @param params - New mint settings, replacing the current ones
+ * @param poolId - Pool id
+ * @returns Transaction instruction
+ */
+export async function buildUpdateMintConfigInstruction(
+  program: anchor.Program<Yona>,
+  authority: PublicKey,
+  mint: PublicKey,
+  params: MintConfigParams,
+  poolId: number = DEFAULT_POOL_ID
+) {
+  const [globalConfig] = findGlobalConfigPDA(program.programId, poolId);
+  const [mintConfig] = findMintConfigPDA(program.programId, globalConfig, mint);
+
+  return await program.methods
+    .updateMintConfig(params)
+    .accountsStrict({
+      globalConfig,
+      mintConfig,
+      authority
+    })
+    .instruction();
+}
+
+/**
+ * Build update deposit limit instruction (deprecated, use buildUpdateMintConfigInstruction)
+ * Sets the max deposit of the wrapped SOL mint config of the default pool.
+ * @param program - Anchor program instance
+ * @param authority - Authority public key
  * @param newLimit - New deposit limit in lamports
- * @param signers - Array of signers (should include authority)
- * @param preInstructions - Optional pre-instructions
- * @returns Transaction signature
+ * @returns Transaction instruction
  */
-export async function executeUpdateDepositLimit(
+export async function buildUpdateDepositLimitInstruction(
   program: anchor.Program<Yona>,
-  newLimit: anchor.BN,
-  signers: anchor.web3.Keypair[],
-  preInstructions?: TransactionInstruction[]
-): Promise<string> {
-  const [treeAccount] = findMerkleTreePDA(program.programId);
+  authority: PublicKey,
+  newLimit: anchor.BN
+) {
+  const [globalConfig] = findGlobalConfigPDA(program.programId);
+  const [mintConfig] = findMintConfigPDA(program.programId, globalConfig, NATIVE_MINT);
 
-  const txBuilder = program.methods
+  return await program.methods
     .updateDepositLimit(newLimit)
-    .accounts({
-      treeAccount,
-      authority: signers[0].publicKey
+    .accountsStrict({
+      globalConfig,
+      mintConfig,
+      authority
     })
-    .signers(signers);
+    .instruction();
+}
 
-  if (preInstructions && preInstructions.length > 0) {
-    txBuilder.preInstructions(preInstructions);
-  }
+/**
+ * Build set aggregator program instruction
+ * @param program - Anchor program instance
+ * @param authority - Authority public key
+ * @param aggregatorProgram - Aggregator program to allow or disallow as swap CPI target, must be Jupiter
+ * @param enabled - Whether swaps may route through the program
+ * @param poolId - Pool id
+ * @returns Transaction instruction
+ */
+export async function buildSetAggregatorProgramInstruction(
+  program: anchor.Program<Yona>,
+  authority: PublicKey,
+  aggregatorProgram: PublicKey,
+  enabled: boolean,
+  poolId: number = DEFAULT_POOL_ID
+) {
+  const [globalConfig] = findGlobalConfigPDA(program.programId, poolId);
 
-  return await txBuilder.rpc();
+  return await program.methods
+    .setAggregatorProgram(enabled)
+    .accountsStrict({
+      globalConfig,
+      aggregatorProgram,
+      aggregatorConfig: findAggregatorConfigPDA(program.programId, globalConfig, aggregatorProgram)[0],
+      authority,
+      systemProgram: anchor.web3.SystemProgram.programId
+    })
+    .instruction();
 }
 
 /**
@@ -655,6 +1154,7 @@ export async function executeUpdateDepositLimit(
  * @param depositFeeRate - Optional new deposit fee rate (in basis points, 0-10000)
  * @param withdrawalFeeRate - Optional new withdrawal fee rate (in basis points, 0-10000)
  * @param feeErrorMargin - Optional new fee error margin (in basis points, 0-10000)
+ * @param minRelayerBond - Optional new minimum relayer bond (in lamports)
  * @returns Transaction instruction
  */
 export async function buildUpdateGlobalConfigInstruction(
@@ -662,7 +1162,8 @@ export async function buildUpdateGlobalConfigInstruction(
   authority: PublicKey,
   depositFeeRate?: number | null,
   withdrawalFeeRate?: number | null,
-  feeErrorMargin?: number | null
+  feeErrorMargin?: number | null,
+  minRelayerBond?: anchor.BN | null
 ) {
   const [globalConfig] = findGlobalConfigPDA(program.programId);
 
@@ -670,7 +1171,8 @@ export async function buildUpdateGlobalConfigInstruction(
     .updateGlobalConfig(
       depositFeeRate ?? null,
       withdrawalFeeRate ?? null,
-      feeErrorMargin ?? null
+      feeErrorMargin ?? null,
+      minRelayerBond ?? null
     )
     .accounts({
       globalConfig,
@@ -686,6 +1188,7 @@ export async function buildUpdateGlobalConfigInstruction(
  * @param depositFeeRate - Optional new deposit fee rate (in basis points, 0-10000)
  * @param withdrawalFeeRate - Optional new withdrawal fee rate (in basis points, 0-10000)
  * @param feeErrorMargin - Optional new fee error margin (in basis points, 0-10000)
+ * @param minRelayerBond - Optional new minimum relayer bond (in lamports)
  * @param preInstructions - Optional pre-instructions
  * @returns Transaction signature
  */
@@ -695,6 +1198,7 @@ export async function executeUpdateGlobalConfig(
   depositFeeRate?: number | null,
   withdrawalFeeRate?: number | null,
   feeErrorMargin?: number | null,
+  minRelayerBond?: anchor.BN | null,
   preInstructions?: TransactionInstruction[]
 ): Promise<string> {
   const [globalConfig] = findGlobalConfigPDA(program.programId);
@@ -703,7 +1207,8 @@ export async function executeUpdateGlobalConfig(
     .updateGlobalConfig(
       depositFeeRate ?? null,
       withdrawalFeeRate ?? null,
-      feeErrorMargin ?? null
+      feeErrorMargin ?? null,
+      minRelayerBond ?? null
     )
     .accounts({
       globalConfig,
@@ -718,22 +1223,168 @@ export async function executeUpdateGlobalConfig(
   return await txBuilder.rpc();
 }
 
+/**
+ * Build register relayer instruction
+ * @param program - Anchor program instance
+ * @param authority - Relayer signer, pays the rent and the bond
+ * @param feeRate - Advertised fee rate (in basis points, 0-10000)
+ * @param feeRecipient - Owner of the token accounts receiving the relayer fees
+ * @param bond - Bond in lamports, at least `GlobalConfig.minRelayerBond`
+ * @param poolId - Pool id
+ * @returns Transaction instruction
+ */
+export async function buildRegisterRelayerInstruction(
+  program: anchor.Program<Yona>,
+  authority: PublicKey,
+  feeRate: number,
+  feeRecipient: PublicKey,
+  bond: anchor.BN = new anchor.BN(0),
+  poolId: number = DEFAULT_POOL_ID
+) {
+  const [globalConfig] = findGlobalConfigPDA(program.programId, poolId);
+
+  return await program.methods
+    .registerRelayer(feeRate, feeRecipient, bond)
+    .accountsStrict({
+      globalConfig,
+      relayerAccount: findRelayerPDA(program.programId, globalConfig, authority)[0],
+      authority,
+      systemProgram: anchor.web3.SystemProgram.programId
+    })
+    .instruction();
+}
+
+/**
+ * Execute register relayer instruction
+ * @param program - Anchor program instance
+ * @param signers - Signers, the first one is the relayer
+ * @param feeRate - Advertised fee rate (in basis points, 0-10000)
+ * @param feeRecipient - Owner of the token accounts receiving the relayer fees
+ * @param bond - Bond in lamports, at least `GlobalConfig.minRelayerBond`
+ * @param poolId - Pool id
+ * @returns Transaction signature
+ */
+export async function executeRegisterRelayer(
+  program: anchor.Program<Yona>,
+  signers: anchor.web3.Keypair[],
+  feeRate: number,
+  feeRecipient: PublicKey,
+  bond: anchor.BN = new anchor.BN(0),
+  poolId: number = DEFAULT_POOL_ID
+): Promise<string> {
+  const [globalConfig] = findGlobalConfigPDA(program.programId, poolId);
+  const authority = signers[0].publicKey;
+
+  return await program.methods
+    .registerRelayer(feeRate, feeRecipient, bond)
+    .accountsStrict({
+      globalConfig,
+      relayerAccount: findRelayerPDA(program.programId, globalConfig, authority)[0],
+      authority,
+      systemProgram: anchor.web3.SystemProgram.programId
+    })
+    .signers(signers)
+    .rpc();
+}
+
+/**
+ * Build request relayer deregistration instruction, the relayer stops relaying and its bond
+ * unbonds for `RELAYER_UNBONDING_SECONDS`
+ * @param program - Anchor program instance
+ * @param authority - Relayer signer
+ * @param poolId - Pool id
+ * @returns Transaction instruction
+ */
+export async function buildRequestRelayerDeregistrationInstruction(
+  program: anchor.Program<Yona>,
+  authority: PublicKey,
+  poolId: number = DEFAULT_POOL_ID
+) {
+  const [globalConfig] = findGlobalConfigPDA(program.programId, poolId);
+
+  return await program.methods
+    .requestRelayerDeregistration()
+    .accountsStrict({
+      relayerAccount: findRelayerPDA(program.programId, globalConfig, authority)[0],
+      authority
+    })
+    .instruction();
+}
+
+/**
+ * Build deregister relayer instruction, returns the bond and rent once the bond has unbonded
+ * @param program - Anchor program instance
+ * @param authority - Relayer signer
+ * @param poolId - Pool id
+ * @returns Transaction instruction
+ */
+export async function buildDeregisterRelayerInstruction(
+  program: anchor.Program<Yona>,
+  authority: PublicKey,
+  poolId: number = DEFAULT_POOL_ID
+) {
+  const [globalConfig] = findGlobalConfigPDA(program.programId, poolId);
+
+  return await program.methods
+    .deregisterRelayer()
+    .accountsStrict({
+      relayerAccount: findRelayerPDA(program.programId, globalConfig, authority)[0],
+      authority
+    })
+    .instruction();
+}
+
+/**
+ * Build slash relayer instruction, moves part of the relayer's bond to `destination`
+ * @param program - Anchor program instance
+ * @param authority - Pool authority
+ * @param relayer - Signer the relayer registered with
+ * @param destination - Receives the slashed lamports
+ * @param amount - Lamports to slash, at most the bond
+ * @param poolId - Pool id
+ * @returns Transaction instruction
+ */
+export async function buildSlashRelayerInstruction(
+  program: anchor.Program<Yona>,
+  authority: PublicKey,
+  relayer: PublicKey,
+  destination: PublicKey,
+  amount: anchor.BN,
+  poolId: number = DEFAULT_POOL_ID
+) {
+  const [globalConfig] = findGlobalConfigPDA(program.programId, poolId);
+
+  return await program.methods
+    .slashRelayer(amount)
+    .accountsStrict({
+      globalConfig,
+      relayerAccount: findRelayerPDA(program.programId, globalConfig, relayer)[0],
+      destination,
+      authority
+    })
+    .instruction();
+}
+
 /**
  * Build initialize instruction
  * @param program - Anchor program instance
  * @param authority - Authority public key
+ * @param height - Merkle tree height (at most 26)
+ * @param rootHistorySize - Number of recent roots accepted for proofs
  * @returns Transaction instruction
  */
 export async function buildInitializeInstruction(
   program: anchor.Program<Yona>,
-  authority: PublicKey
+  authority: PublicKey,
+  height: number = DEFAULT_HEIGHT,
+  rootHistorySize: number = ROOT_HISTORY_SIZE
 ) {
   const [treeAccount] = findMerkleTreePDA(program.programId);
   const [treeTokenAccount] = findTreeTokenAccountPDA(program.programId);
   const [globalConfig] = findGlobalConfigPDA(program.programId);
 
   return await program.methods
-    .initialize()
+    .initialize(height, rootHistorySize)
     .accountsStrict({
       treeAccount,
       treeTokenAccount,
@@ -749,19 +1400,23 @@ export async function buildInitializeInstruction(
  * @param program - Anchor program instance
  * @param signers - Array of signers (should include authority)
  * @param preInstructions - Optional pre-instructions
+ * @param height - Merkle tree height (at most 26)
+ * @param rootHistorySize - Number of recent roots accepted for proofs
  * @returns Transaction signature
  */
 export async function executeInitialize(
   program: anchor.Program<Yona>,
   signers: anchor.web3.Keypair[],
-  preInstructions?: TransactionInstruction[]
+  preInstructions?: TransactionInstruction[],
+  height: number = DEFAULT_HEIGHT,
+  rootHistorySize: number = ROOT_HISTORY_SIZE
 ): Promise<string> {
   const [treeAccount] = findMerkleTreePDA(program.programId);
   const [treeTokenAccount] = findTreeTokenAccountPDA(program.programId);
   const [globalConfig] = findGlobalConfigPDA(program.programId);
 
   const txBuilder = program.methods
-    .initialize()
+    .initialize(height, rootHistorySize)
     .accountsStrict({
       treeAccount,
       treeTokenAccount,
@@ -778,7 +1433,121 @@ export async function executeInitialize(
   return await txBuilder.rpc();
 }
 
+// Key bytes per write_verifying_key transaction, keeps it below the transaction size limit
+const VERIFYING_KEY_CHUNK_SIZE = 800;
 
+/**
+ * Create the verifying key account of a circuit and activate `vk` as its first key.
+ * Uses a timelock of 0 so the key is active right away (tests only).
+ * @param program - Anchor program instance
+ * @param signers - Signers, the first one is the admin
+ * @param circuit - Circuit id
+ * @param vk - Verifying key to upload
+ * @param version - Key version
+ */
+export async function executeSetupVerifyingKey(
+  program: anchor.Program<Yona>,
+  signers: anchor.web3.Keypair[],
+  circuit: number = CIRCUIT_TRANSACTION2,
+  vk: VerifyingKey = TRANSACTION2_VERIFYING_KEY,
+  version: number = 1
+): Promise<void> {
+  const authority = signers[0].publicKey;
+  const [verifyingKey] = findVerifyingKeyPDA(program.programId, circuit);
+
+  await program.methods
+    .initializeVerifyingKey(circuit, new anchor.BN(0), new anchor.BN(0))
+    .accountsStrict({
+      verifyingKey,
+      authority,
+      systemProgram: anchor.web3.SystemProgram.programId
+    })
+    .signers(signers)
+    .rpc();
+
+  await program.methods
+    .stageVerifyingKey(version, verifyingKeyHash(vk))
+    .accountsStrict({ verifyingKey, authority })
+    .signers(signers)
+    .rpc();
+
+  const data = verifyingKeyData(vk);
+  for (let offset = 0; offset < data.length; offset += VERIFYING_KEY_CHUNK_SIZE) {
+    await program.methods
+      .writeVerifyingKey(offset, data.subarray(offset, offset + VERIFYING_KEY_CHUNK_SIZE))
+      .accountsStrict({ verifyingKey, authority })
+      .signers(signers)
+      .rpc();
+  }
+
+  await program.methods
+    .activateVerifyingKey()
+    .accountsStrict({ verifyingKey, authority })
+    .signers(signers)
+    .rpc();
+}
+
+/**
+ * Build initialize pool instruction
+ * @param program - Anchor program instance
+ * @param authority - Authority public key
+ * @param poolId - Id of the new pool
+ * @param height - Merkle tree height (at most 26)
+ * @param rootHistorySize - Number of recent roots accepted for proofs
+ * @returns Transaction instruction
+ */
+export async function buildInitializePoolInstruction(
+  program: anchor.Program<Yona>,
+  authority: PublicKey,
+  poolId: number,
+  height: number = DEFAULT_HEIGHT,
+  rootHistorySize: number = ROOT_HISTORY_SIZE
+) {
+  const [treeAccount] = findMerkleTreePDA(program.programId, poolId);
+  const [treeTokenAccount] = findTreeTokenAccountPDA(program.programId, poolId);
+  const [globalConfig] = findGlobalConfigPDA(program.programId, poolId);
+
+  return await program.methods
+    .initializePool(poolId, height, rootHistorySize)
+    .accountsStrict({
+      treeAccount,
+      treeTokenAccount,
+      globalConfig,
+      authority,
+      systemProgram: anchor.web3.SystemProgram.programId
+    })
+    .instruction();
+}
+
+/**
+ * Build rollover tree instruction
+ * @param program - Anchor program instance
+ * @param payer - Payer of the new tree account (must be the pool authority if the tree is not full)
+ * @param activeTreeIndex - Current `GlobalConfig.activeTreeIndex` of the pool
+ * @param poolId - Pool id
+ * @returns Transaction instruction
+ */
+export async function buildRolloverTreeInstruction(
+  program: anchor.Program<Yona>,
+  payer: PublicKey,
+  activeTreeIndex: number,
+  poolId: number = DEFAULT_POOL_ID
+) {
+  const [globalConfig] = findGlobalConfigPDA(program.programId, poolId);
+  const [treeAccount] = findMerkleTreePDA(program.programId, poolId, activeTreeIndex);
+  const [newTreeAccount] = findMerkleTreePDA(program.programId, poolId, activeTreeIndex + 1);
+
+  return await program.methods
+    .rolloverTree()
+    .accountsStrict({
+      globalConfig,
+      treeAccount,
+      newTreeAccount,
+      payer,
+      systemProgram: anchor.web3.SystemProgram.programId
+    })
+    .instruction();
+}
 
 
 
@@ -978,6 +1747,7 @@ export async function startSetupBankrun(
   feeRecipient: Keypair
 ) {
   await executeInitialize(program, [admin]);
+  await executeSetupVerifyingKey(program, [admin]);
 
   const latestBlockhash = context.lastBlockhash;
 
@@ -1063,6 +1833,13 @@ export async function startSetupBankrun(
       mintAddressB
     )
   );
+  // Allow both mints in the pool
+  ixs.push(
+    await buildInitializeMintConfigInstruction(program, admin.publicKey, mintAddressA, DEFAULT_MINT_CONFIG)
+  );
+  ixs.push(
+    await buildInitializeMintConfigInstruction(program, admin.publicKey, mintAddressB, DEFAULT_MINT_CONFIG)
+  );
   const tx = new Transaction().add(...ixs);
   tx.recentBlockhash = latestBlockhash;
   tx.feePayer = admin.publicKey;
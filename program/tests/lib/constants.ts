@@ -212,8 +212,19 @@ export const ZERO_BYTES = [
 
 export const ROOT_HISTORY_SIZE = 100;
 export const DEFAULT_HEIGHT = 26;
+export const DEFAULT_POOL_ID = 0;
+export const INITIAL_TREE_INDEX = 0;
 export const FIELD_SIZE = new anchor.BN('21888242871839275222246405745257275088548364400416034343698204186575808495617')
 
 export const DEPOSIT_FEE_RATE = 0; // 0% - Free deposits
 export const WITHDRAW_FEE_RATE = 30; // 0.3% - Fee on withdrawals
-export const FEE_ERROR_MARGIN = 500; // 5% tolerance (minimum fee = 95% of expected)
\ No newline at end of file
+export const FEE_ERROR_MARGIN = 500; // 5% tolerance (minimum fee = 95% of expected)
+export const RELAYER_UNBONDING_SECONDS = 2 * 24 * 60 * 60; // request_relayer_deregistration -> deregister_relayer
+// Mint config used for test mints: enabled, no minimum, pool-wide fee rates
+export const DEFAULT_MINT_CONFIG = {
+  enabled: true,
+  minDepositAmount: new anchor.BN(0),
+  maxDepositAmount: new anchor.BN(1_000_000_000_000),
+  depositFeeRate: null,
+  withdrawalFeeRate: null,
+};
\ No newline at end of file
@@ -1,5 +1,17 @@
 import * as anchor from "@coral-xyz/anchor";
 import { PublicKey } from "@solana/web3.js";
+import { DEFAULT_POOL_ID, INITIAL_TREE_INDEX } from "./constants";
+
+/**
+ * Encode a pool id as a PDA seed (u16, little-endian)
+ * @param poolId - Pool id
+ * @returns Seed buffer
+ */
+export function poolIdSeed(poolId: number): Buffer {
+  const seed = Buffer.alloc(2);
+  seed.writeUInt16LE(poolId);
+  return seed;
+}
 
 /**
  * Find nullifier PDAs for the given proof
@@ -46,11 +58,19 @@ export function findCommitmentPDAs(program: anchor.Program<any>, proof: any) {
 /**
  * Find the merkle tree PDA
  * @param programId - Program ID
+ * @param poolId - Pool id, defaults to the pool created by `initialize`
+ * @param treeIndex - Tree index within the pool, see `GlobalConfig.activeTreeIndex`
  * @returns Merkle tree PDA and bump
  */
-export function findMerkleTreePDA(programId: PublicKey) {
+export function findMerkleTreePDA(
+  programId: PublicKey,
+  poolId: number = DEFAULT_POOL_ID,
+  treeIndex: number = INITIAL_TREE_INDEX
+) {
+  const treeIndexSeed = Buffer.alloc(4);
+  treeIndexSeed.writeUInt32LE(treeIndex);
   return PublicKey.findProgramAddressSync(
-    [Buffer.from("merkle_tree")],
+    [Buffer.from("merkle_tree"), poolIdSeed(poolId), treeIndexSeed],
     programId
   );
 }
@@ -58,11 +78,12 @@ export function findMerkleTreePDA(programId: PublicKey) {
 /**
  * Find the tree token account PDA
  * @param programId - Program ID
+ * @param poolId - Pool id, defaults to the pool created by `initialize`
  * @returns Tree token account PDA and bump
  */
-export function findTreeTokenAccountPDA(programId: PublicKey) {
+export function findTreeTokenAccountPDA(programId: PublicKey, poolId: number = DEFAULT_POOL_ID) {
   return PublicKey.findProgramAddressSync(
-    [Buffer.from("tree_token")],
+    [Buffer.from("tree_token"), poolIdSeed(poolId)],
     programId
   );
 }
@@ -70,12 +91,94 @@ export function findTreeTokenAccountPDA(programId: PublicKey) {
 /**
  * Find the global config PDA
  * @param programId - Program ID
+ * @param poolId - Pool id, defaults to the pool created by `initialize`
  * @returns Global config PDA and bump
  */
-export function findGlobalConfigPDA(programId: PublicKey) {
+export function findGlobalConfigPDA(programId: PublicKey, poolId: number = DEFAULT_POOL_ID) {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("global_config"), poolIdSeed(poolId)],
+    programId
+  );
+}
+
+
+/**
+ * Find the mint config PDA of a pool
+ * @param programId - Program ID
+ * @param globalConfig - Global config PDA of the pool
+ * @param mint - Token mint address
+ * @returns Mint config PDA and bump
+ */
+export function findMintConfigPDA(programId: PublicKey, globalConfig: PublicKey, mint: PublicKey) {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("mint_config"), globalConfig.toBuffer(), mint.toBuffer()],
+    programId
+  );
+}
+
+/**
+ * Find the deposit authority PDA of a relayed deposit
+ * @param programId - Program ID
+ * @param outputCommitment0 - First output commitment of the deposit proof
+ * @returns Deposit authority PDA and bump
+ */
+export function findDepositAuthorityPDA(programId: PublicKey, outputCommitment0: number[] | Uint8Array) {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("deposit_authority"), Buffer.from(outputCommitment0)],
+    programId
+  );
+}
+
+/**
+ * Find the aggregator config PDA of a pool
+ * @param programId - Program ID
+ * @param globalConfig - Global config PDA of the pool
+ * @param aggregatorProgram - Aggregator program allowed as swap CPI target
+ * @returns Aggregator config PDA and bump
+ */
+export function findAggregatorConfigPDA(programId: PublicKey, globalConfig: PublicKey, aggregatorProgram: PublicKey) {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("aggregator_config"), globalConfig.toBuffer(), aggregatorProgram.toBuffer()],
+    programId
+  );
+}
+
+/**
+ * Find the swap authority PDA of a pool, transfer authority of the Jupiter routes of swaps
+ * @param programId - Program ID
+ * @param globalConfig - Global config PDA of the pool
+ * @returns Swap authority PDA and bump
+ */
+export function findSwapAuthorityPDA(programId: PublicKey, globalConfig: PublicKey) {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("swap_authority"), globalConfig.toBuffer()],
+    programId
+  );
+}
+
+/**
+ * Find the relayer account PDA of a pool
+ * @param programId - Program ID
+ * @param globalConfig - Global config PDA of the pool
+ * @param authority - Relayer signer
+ * @returns Relayer account PDA and bump
+ */
+export function findRelayerPDA(programId: PublicKey, globalConfig: PublicKey, authority: PublicKey) {
   return PublicKey.findProgramAddressSync(
-    [Buffer.from("global_config")],
+    [Buffer.from("relayer"), globalConfig.toBuffer(), authority.toBuffer()],
     programId
   );
 }
 
+/**
+ * Find the verifying key PDA of a circuit
+ * @param programId - Program ID
+ * @param circuit - Circuit id (CIRCUIT_TRANSACTION2, CIRCUIT_TRANSACTION4)
+ * @returns Verifying key PDA and bump
+ */
+export function findVerifyingKeyPDA(programId: PublicKey, circuit: number) {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("verifying_key"), Buffer.from([circuit])],
+    programId
+  );
+}
@@ -0,0 +1,202 @@
+import { BN } from "@coral-xyz/anchor";
+import {
+  AccountMeta,
+  Connection,
+  Keypair,
+  PublicKey,
+  sendAndConfirmTransaction,
+  SystemProgram,
+  Transaction,
+  TransactionInstruction,
+} from "@solana/web3.js";
+import {
+  createInitializeMintInstruction,
+  createInitializePermanentDelegateInstruction,
+  ExtensionType,
+  getAssociatedTokenAddressSync,
+  getMintLen,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
+  TOKEN_2022_PROGRAM_ID,
+  TOKEN_PROGRAM_ID,
+} from "@solana/spl-token";
+
+// tests/fixtures/mock-jupiter, loaded by the validator at the Jupiter aggregator address:
+// light test-validator --sbf-program JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4 tests/fixtures/mock_jupiter.so
+export const JUPITER_PROGRAM_ID = new PublicKey("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4");
+
+// Anchor discriminators, `route` is the one of the Jupiter IDL
+const ROUTE_DISCRIMINATOR = Buffer.from([229, 23, 203, 151, 122, 227, 173, 42]);
+const CONFIGURE_DISCRIMINATOR = Buffer.from([245, 7, 108, 117, 95, 196, 54, 217]);
+
+/**
+ * Tokens taken out of a reserve by the mock route, through the permanent delegate of its mint
+ */
+export interface MockDrain {
+  account: PublicKey; // drained token account, its mint has the mock vault as permanent delegate
+  mint: PublicKey;
+  tokenProgram: PublicKey;
+  destination: PublicKey;
+}
+
+/**
+ * Find the mock config PDA
+ * @returns [PDA, bump]
+ */
+export function findMockConfigPDA() {
+  return PublicKey.findProgramAddressSync([Buffer.from("config")], JUPITER_PROGRAM_ID);
+}
+
+/**
+ * Find the mock vault PDA, owner of the mock liquidity and signer of its transfers
+ * @returns [PDA, bump]
+ */
+export function findMockVaultPDA() {
+  return PublicKey.findProgramAddressSync([Buffer.from("vault")], JUPITER_PROGRAM_ID);
+}
+
+/**
+ * Build the mock `configure` instruction, applies to every following route
+ * @param payer - Pays for the config account on first use
+ * @param inputShortfall - Input tokens the route leaves in the source account
+ * @param drainAmount - Tokens the route takes out of the drained account, 0 to leave it alone
+ * @returns Transaction instruction
+ */
+export function buildConfigureMockJupiterInstruction(
+  payer: PublicKey,
+  inputShortfall: BN,
+  drainAmount: BN
+): TransactionInstruction {
+  return new TransactionInstruction({
+    programId: JUPITER_PROGRAM_ID,
+    keys: [
+      { pubkey: findMockConfigPDA()[0], isSigner: false, isWritable: true },
+      { pubkey: payer, isSigner: true, isWritable: true },
+      { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+    ],
+    data: Buffer.concat([
+      CONFIGURE_DISCRIMINATOR,
+      inputShortfall.toArrayLike(Buffer, "le", 8),
+      drainAmount.toArrayLike(Buffer, "le", 8),
+    ]),
+  });
+}
+
+/**
+ * Create the mock vault of a mint, optionally funded with swap output liquidity
+ * @param connection - Solana connection
+ * @param payer - Payer and mint authority
+ * @param mint - Token mint
+ * @param liquidity - Tokens minted to the vault
+ * @returns Vault token account
+ */
+export async function fundMockVault(
+  connection: Connection,
+  payer: Keypair,
+  mint: PublicKey,
+  liquidity: number | bigint = 0
+): Promise<PublicKey> {
+  const vault = await getOrCreateAssociatedTokenAccount(
+    connection,
+    payer,
+    mint,
+    findMockVaultPDA()[0],
+    true
+  );
+  if (liquidity > 0) {
+    await mintTo(connection, payer, mint, vault.address, payer, liquidity);
+  }
+  return vault.address;
+}
+
+/**
+ * Create a Token-2022 mint with the mock vault as permanent delegate, so routes can drain its accounts
+ * @param connection - Solana connection
+ * @param payer - Payer and mint authority
+ * @param decimals - Decimals of the mint
+ * @returns Mint address
+ */
+export async function createDrainableMint(
+  connection: Connection,
+  payer: Keypair,
+  decimals: number = 9
+): Promise<PublicKey> {
+  const mint = Keypair.generate();
+  const space = getMintLen([ExtensionType.PermanentDelegate]);
+  const transaction = new Transaction().add(
+    SystemProgram.createAccount({
+      fromPubkey: payer.publicKey,
+      newAccountPubkey: mint.publicKey,
+      space,
+      lamports: await connection.getMinimumBalanceForRentExemption(space),
+      programId: TOKEN_2022_PROGRAM_ID,
+    }),
+    createInitializePermanentDelegateInstruction(mint.publicKey, findMockVaultPDA()[0], TOKEN_2022_PROGRAM_ID),
+    createInitializeMintInstruction(mint.publicKey, decimals, payer.publicKey, null, TOKEN_2022_PROGRAM_ID)
+  );
+  await sendAndConfirmTransaction(connection, transaction, [payer, mint]);
+  return mint.publicKey;
+}
+
+/**
+ * Build the data and accounts of a mock Jupiter `route`, to pass to the swap instruction
+ * @param params - Transfer authority, reserves, mints and amounts of the route
+ * @returns Route instruction data and accounts
+ */
+export function buildMockRoute(params: {
+  authority: PublicKey; // swap authority PDA, promoted to signer by the program
+  sourceTokenAccount: PublicKey;
+  destinationTokenAccount: PublicKey;
+  sourceMint: PublicKey;
+  destinationMint: PublicKey;
+  inAmount: BN;
+  quotedOutAmount: BN;
+  drain?: MockDrain;
+}): { data: Buffer; accounts: AccountMeta[] } {
+  const [vault] = findMockVaultPDA();
+  const [eventAuthority] = PublicKey.findProgramAddressSync(
+    [Buffer.from("__event_authority")],
+    JUPITER_PROGRAM_ID
+  );
+  const readonly = (pubkey: PublicKey): AccountMeta => ({ pubkey, isSigner: false, isWritable: false });
+  const writable = (pubkey: PublicKey): AccountMeta => ({ pubkey, isSigner: false, isWritable: true });
+
+  const slippageBps = Buffer.alloc(2);
+  slippageBps.writeUInt16LE(50);
+  const data = Buffer.concat([
+    ROUTE_DISCRIMINATOR,
+    Buffer.alloc(4), // empty route plan
+    params.inAmount.toArrayLike(Buffer, "le", 8),
+    params.quotedOutAmount.toArrayLike(Buffer, "le", 8),
+    slippageBps,
+    Buffer.from([0]), // platform fee bps
+  ]);
+
+  const accounts = [
+    readonly(TOKEN_PROGRAM_ID),
+    readonly(params.authority),
+    writable(params.sourceTokenAccount),
+    writable(params.destinationTokenAccount),
+    readonly(JUPITER_PROGRAM_ID), // no destination token account
+    readonly(params.destinationMint),
+    readonly(JUPITER_PROGRAM_ID), // no platform fee account
+    readonly(eventAuthority),
+    readonly(JUPITER_PROGRAM_ID),
+    // Route plan accounts of the mock
+    readonly(findMockConfigPDA()[0]),
+    readonly(params.sourceMint),
+    readonly(vault),
+    writable(getAssociatedTokenAddressSync(params.sourceMint, vault, true)),
+    writable(getAssociatedTokenAddressSync(params.destinationMint, vault, true)),
+  ];
+  if (params.drain) {
+    accounts.push(
+      writable(params.drain.account),
+      readonly(params.drain.mint),
+      readonly(params.drain.tokenProgram),
+      writable(params.drain.destination)
+    );
+  }
+
+  return { data, accounts };
+}
@@ -13,6 +13,8 @@ import {
 import * as anchor from "@coral-xyz/anchor";
 import { BanksClient } from 'solana-bankrun';
 import { TOKEN_PROGRAM_ID } from '@solana/spl-token';
+import { findGlobalConfigPDA, findMerkleTreePDA, findTreeTokenAccountPDA, findVerifyingKeyPDA } from './derive';
+import { CIRCUIT_TRANSACTION2 } from './verifying_key';
 
 // Global ALT for test session (created once, used everywhere)
 let globalTestALT: PublicKey | null = null;
@@ -138,21 +140,14 @@ export function getTestProtocolAddresses(
   feeRecipient: PublicKey
 ): PublicKey[] {
   // Derive global config PDA
-  const [globalConfigAccount] = PublicKey.findProgramAddressSync(
-    [Buffer.from('global_config')],
-    programId
-  );
+  const [globalConfigAccount] = findGlobalConfigPDA(programId);
 
   // Derive tree accounts
-  const [treeAccount] = PublicKey.findProgramAddressSync(
-    [Buffer.from('merkle_tree')],
-    programId
-  );
+  const [treeAccount] = findMerkleTreePDA(programId);
 
-  const [treeTokenAccount] = PublicKey.findProgramAddressSync(
-    [Buffer.from('tree_token')],
-    programId
-  );
+  const [treeTokenAccount] = findTreeTokenAccountPDA(programId);
+
+  const [verifyingKeyAccount] = findVerifyingKeyPDA(programId, CIRCUIT_TRANSACTION2);
 
   return [
     // Core program accounts (constant)
@@ -160,6 +155,7 @@ export function getTestProtocolAddresses(
     treeAccount,
     treeTokenAccount,
     globalConfigAccount,
+    verifyingKeyAccount,
     authority,
     feeRecipient,
     
@@ -0,0 +1,117 @@
+import { BN } from "@coral-xyz/anchor";
+import { PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { FIELD_SIZE } from "./constants";
+import { MerkleTree } from "./merkle_tree";
+import { parseProofToBytesArray, parseToBytesArray, prove } from "./prover";
+import { ProofInput, ProofToSubmit } from "./types";
+import { publicKeyToFieldElement } from "./utils";
+import { Utxo } from "./utxo";
+
+/**
+ * Inputs of a 2-input / 2-output transaction proof
+ */
+export interface TransactionProofParams {
+  tree: MerkleTree; // mirror of the tree holding the input commitments
+  inputs: Utxo[]; // zero amount inputs are padding and skip the merkle path
+  outputs: Utxo[];
+  publicAmount0: BN; // extAmount0 - fee0, may be negative
+  publicAmount1?: BN; // extAmount1 - fee1, may be negative
+  extDataHash: Uint8Array;
+  mint0: PublicKey;
+  mint1?: PublicKey; // defaults to mint0
+  keyBasePath: string; // circuit .wasm / .zkey path without extension
+}
+
+/**
+ * Generate a transaction proof and format it for the program
+ * Sets the leaf index of the spent inputs, their nullifiers depend on it
+ * @param params - Transaction inputs and outputs
+ * @returns Proof ready to submit
+ */
+export async function proveTransaction(params: TransactionProofParams): Promise<ProofToSubmit> {
+  const { tree, inputs, outputs } = params;
+  const toField = (amount: BN) => amount.add(FIELD_SIZE).mod(FIELD_SIZE).toString();
+
+  const inPathIndices = [];
+  const inPathElements = [];
+  for (const input of inputs) {
+    if (input.amount.gt(new BN(0))) {
+      input.index = tree.indexOf(await input.getCommitment());
+      if (input.index === -1) {
+        throw new Error("Input commitment is not in the tree");
+      }
+      inPathIndices.push(input.index);
+      inPathElements.push(tree.path(input.index).pathElements);
+    } else {
+      inPathIndices.push(0);
+      inPathElements.push(new Array(tree.levels).fill(0));
+    }
+  }
+
+  const proofInput: ProofInput = {
+    root: tree.root(),
+    inputNullifier: await Promise.all(inputs.map(x => x.getNullifier())),
+    outputCommitment: await Promise.all(outputs.map(x => x.getCommitment())),
+    publicAmount0: toField(params.publicAmount0),
+    publicAmount1: toField(params.publicAmount1 ?? new BN(0)),
+    extDataHash: params.extDataHash,
+    mintAddress0: publicKeyToFieldElement(params.mint0),
+    mintAddress1: publicKeyToFieldElement(params.mint1 ?? params.mint0),
+    inAmount: inputs.map(x => x.amount.toString(10)),
+    inMintAddress: inputs.map(x => x.mintAddress),
+    inPrivateKey: inputs.map(x => x.keypair.privkey),
+    inBlinding: inputs.map(x => x.blinding.toString(10)),
+    inPathIndices,
+    inPathElements,
+    outAmount: outputs.map(x => x.amount.toString(10)),
+    outMintAddress: outputs.map(x => x.mintAddress),
+    outPubkey: outputs.map(x => x.keypair.pubkey),
+    outBlinding: outputs.map(x => x.blinding.toString(10)),
+  };
+
+  const proofResult = await prove(proofInput, params.keyBasePath);
+  const proofInBytes = parseProofToBytesArray(proofResult.proof, true);
+  const inputsInBytes = parseToBytesArray(proofResult.publicSignals);
+
+  return {
+    proofA: proofInBytes.proofA,
+    proofB: proofInBytes.proofB.flat(),
+    proofC: proofInBytes.proofC,
+    root: inputsInBytes[0],
+    publicAmount0: inputsInBytes[1],
+    publicAmount1: inputsInBytes[2],
+    extDataHash: inputsInBytes[3],
+    inputNullifiers: [inputsInBytes[6], inputsInBytes[7]],
+    outputCommitments: [inputsInBytes[8], inputsInBytes[9]],
+  };
+}
+
+/**
+ * Insert the output commitments of a confirmed transaction into the tree mirror
+ * @param tree - Mirror of the tree the transaction appended to
+ * @param outputs - Transaction outputs, in order
+ */
+export async function insertOutputs(tree: MerkleTree, outputs: Utxo[]): Promise<void> {
+  for (const output of outputs) {
+    tree.insert(await output.getCommitment());
+  }
+}
+
+/**
+ * Expect a transaction to fail with the given program error
+ * @param transaction - Pending transaction
+ * @param errorName - Anchor error name, e.g. "UnknownRoot"
+ */
+export async function expectProgramError(transaction: Promise<unknown>, errorName: string): Promise<void> {
+  let failed = false;
+  try {
+    await transaction;
+  } catch (error: any) {
+    failed = true;
+    const logs: string[] = error.logs ?? error.transactionLogs ?? [];
+    const details = [error.message ?? String(error), ...logs].join("\n");
+    expect(details, details).to.include(errorName);
+  }
+  expect(failed, `expected ${errorName}`).to.be.true;
+}
@@ -22,6 +22,18 @@ export interface SwapData {
   feeRecipient: PublicKey;
   mintAddressA: PublicKey;
   mintAddressB: PublicKey;
+  routeHash: Uint8Array; // getRouteHash of the Jupiter instruction
+}
+
+/**
+ * Per-mint settings of a pool (`initializeMintConfig` / `updateMintConfig`)
+ */
+export interface MintConfigParams {
+  enabled: boolean;
+  minDepositAmount: BN;
+  maxDepositAmount: BN;
+  depositFeeRate: number | null;
+  withdrawalFeeRate: number | null;
 }
 
 
@@ -156,6 +156,7 @@ export function getSwapExtDataHash(extData: {
   feeRecipient: string | PublicKey;
   mintAddressA: string | PublicKey;
   mintAddressB: string | PublicKey;
+  routeHash: Uint8Array;  // getRouteHash of the Jupiter instruction
 }): Uint8Array {
 
   // Convert all inputs to their appropriate types
@@ -189,6 +190,7 @@ export function getSwapExtDataHash(extData: {
       feeRecipient: { array: { type: 'u8', len: 32 } },
       mintAddressA: { array: { type: 'u8', len: 32 } },
       mintAddressB: { array: { type: 'u8', len: 32 } },
+      routeHash: { array: { type: 'u8', len: 32 } },
     }
   };
 
@@ -200,6 +202,7 @@ export function getSwapExtDataHash(extData: {
     feeRecipient: feeRecipient.toBytes(),
     mintAddressA: mintAddressA.toBytes(),
     mintAddressB: mintAddressB.toBytes(),
+    routeHash: extData.routeHash,
   };
   console.log("value: ", value);
   // Serialize with Borsh
@@ -210,6 +213,95 @@ export function getSwapExtDataHash(extData: {
   return Buffer.from(hashHex.slice(2), 'hex');
 } 
 
+/**
+ * Calculates the hash of the ext data of transact_dual, amounts and fees are committed per mint
+ * @param extData - Dual ext data, side 0 settles mintAddressA and side 1 mintAddressB
+ * @returns The hash as a Uint8Array (32 bytes)
+ */
+export function getDualExtDataHash(extData: {
+  recipient: PublicKey; // the user, source of deposits and owner of the withdrawal accounts
+  extAmount0: string | number | BN;
+  extAmount1: string | number | BN;
+  encryptedOutput: string | Uint8Array;
+  fee0: string | number | BN;
+  fee1: string | number | BN;
+  feeRecipient0: PublicKey;
+  feeRecipient1: PublicKey;
+  mintAddressA: PublicKey;
+  mintAddressB: PublicKey;
+}): Uint8Array {
+  // Define the borsh schema matching the Rust struct
+  const schema = {
+    struct: {
+      recipient: { array: { type: 'u8', len: 32 } },
+      extAmount0: 'i64',
+      extAmount1: 'i64',
+      encryptedOutput: { array: { type: 'u8' } },
+      fee0: 'u64',
+      fee1: 'u64',
+      feeRecipient0: { array: { type: 'u8', len: 32 } },
+      feeRecipient1: { array: { type: 'u8', len: 32 } },
+      mintAddressA: { array: { type: 'u8', len: 32 } },
+      mintAddressB: { array: { type: 'u8', len: 32 } },
+    }
+  };
+
+  const value = {
+    recipient: extData.recipient.toBytes(),
+    extAmount0: new BN(extData.extAmount0.toString()),
+    extAmount1: new BN(extData.extAmount1.toString()),
+    encryptedOutput: Buffer.from(extData.encryptedOutput as any),
+    fee0: new BN(extData.fee0.toString()),
+    fee1: new BN(extData.fee1.toString()),
+    feeRecipient0: extData.feeRecipient0.toBytes(),
+    feeRecipient1: extData.feeRecipient1.toBytes(),
+    mintAddressA: extData.mintAddressA.toBytes(),
+    mintAddressB: extData.mintAddressB.toBytes(),
+  };
+  const serializedData = borsh.serialize(schema, value);
+  const hashHex = sha256(serializedData);
+  return Buffer.from(hashHex.slice(2), 'hex');
+}
+
+/**
+ * Calculates the hash of a Jupiter route bound in the swap ext data hash
+ * @param data - Jupiter instruction data
+ * @param accounts - Jupiter instruction accounts, in order (signer flags are not hashed)
+ * @returns The hash as a Uint8Array (32 bytes)
+ */
+export function getRouteHash(
+  data: Uint8Array,
+  accounts: { pubkey: PublicKey; isWritable: boolean }[]
+): Uint8Array {
+  // Define the borsh schema matching the Rust struct
+  const schema = {
+    struct: {
+      data: { array: { type: 'u8' } },
+      accounts: {
+        array: {
+          type: {
+            struct: {
+              pubkey: { array: { type: 'u8', len: 32 } },
+              isWritable: 'bool',
+            }
+          }
+        }
+      },
+    }
+  };
+
+  const value = {
+    data: Buffer.from(data),
+    accounts: accounts.map((account) => ({
+      pubkey: account.pubkey.toBytes(),
+      isWritable: account.isWritable,
+    })),
+  };
+  const serializedData = borsh.serialize(schema, value);
+  const hashHex = sha256(serializedData);
+  return Buffer.from(hashHex.slice(2), 'hex');
+}
+
 
 export async function setupATA(
   context: ProgramTestContext,
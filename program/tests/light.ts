@@ -17,24 +17,31 @@ import {
   createAssociatedTokenAccountInstruction,
   getAssociatedTokenAddressSync,
   TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
   createMint,
   mintTo,
   getOrCreateAssociatedTokenAccount,
   NATIVE_MINT,
+  createApproveCheckedInstruction,
+  createAccount,
+  approve,
 } from "@solana/spl-token";
 import { Yona } from "../target/types/yona";
 import { LightWasm, WasmFactory } from "@lightprotocol/hasher.rs";
 import { MerkleTree } from "./lib/merkle_tree";
-import { buildDepositInstruction, buildWithdrawInstruction, buildSwapInstruction, sendTransactionWithALT, createSwapExtDataMinified, buildDepositWithLightNullifiersInstruction, buildWithdrawWithLightNullifiersInstruction, buildSwapWithLightNullifiersInstruction } from "./instructions";
+import { buildDepositInstruction, buildWithdrawInstruction, buildSwapInstruction, sendTransactionWithALT, createSwapExtDataMinified, buildDepositWithLightNullifiersInstruction, buildWithdrawWithLightNullifiersInstruction, buildSwapWithLightNullifiersInstruction, buildTransactInstruction, buildTransactDualInstruction, buildInitializeMintConfigInstruction, buildUpdateMintConfigInstruction, buildUpdateDepositLimitInstruction, buildInitializePoolInstruction, buildRolloverTreeInstruction, executeSetupVerifyingKey, executeRegisterRelayer, executeUpdateGlobalConfig, buildRequestRelayerDeregistrationInstruction, buildDeregisterRelayerInstruction, buildSlashRelayerInstruction, buildSetAggregatorProgramInstruction, buildDepositRelayedInstruction, buildDepositFromTokenAccountInstruction, PoolTarget } from "./instructions";
+import { expectProgramError, insertOutputs, proveTransaction } from "./lib/transaction";
 import { Utxo } from "./lib/utxo";
-import { DEFAULT_HEIGHT, FIELD_SIZE, ROOT_HISTORY_SIZE, ZERO_BYTES, DEPOSIT_FEE_RATE, WITHDRAW_FEE_RATE } from "./lib/constants";
-import { getExtDataHash, getSwapExtDataHash, publicKeyToFieldElement } from "./lib/utils";
+import { DEFAULT_HEIGHT, FIELD_SIZE, ROOT_HISTORY_SIZE, ZERO_BYTES, DEPOSIT_FEE_RATE, WITHDRAW_FEE_RATE, DEFAULT_MINT_CONFIG } from "./lib/constants";
+import { getDualExtDataHash, getExtDataHash, getRouteHash, getSwapExtDataHash, publicKeyToFieldElement } from "./lib/utils";
 import { parseProofToBytesArray, parseToBytesArray, prove } from "./lib/prover";
-import { findGlobalConfigPDA } from "./lib/derive";
+import { findDepositAuthorityPDA, findGlobalConfigPDA, findMerkleTreePDA, findMintConfigPDA, findRelayerPDA, findSwapAuthorityPDA, findTreeTokenAccountPDA, findVerifyingKeyPDA } from "./lib/derive";
+import { CIRCUIT_TRANSACTION2 } from "./lib/verifying_key";
 import path from "path";
 import { ExtData, ProofToSubmit, ProofInput, SwapData } from "./lib/types";
 import { createGlobalTestALT, createNewALT, getTestProtocolAddresses } from "./lib/test_alt";
 import { buildSwapWithJupiter } from "./jup";
+import { buildConfigureMockJupiterInstruction, buildMockRoute, createDrainableMint, fundMockVault, JUPITER_PROGRAM_ID, MockDrain } from "./lib/mock_jupiter";
 import {
   bn,
   CompressedAccountWithMerkleContext,
@@ -67,6 +74,14 @@ describe("localnet", () => {
   let altAddress: PublicKey;
   let jupiterAltAddress: PublicKey | null = null;
   let lightRPC: Rpc;
+  // Second pool, the id is random so reruns against the same validator start from an empty pool
+  const secondPoolId = 1 + Math.floor(Math.random() * 60000);
+  const secondPool: PoolTarget = { poolId: secondPoolId };
+  let secondPoolConfig: PublicKey;
+  let secondPoolTree: MerkleTree;
+  let secondPoolAltAddress: PublicKey;
+  let secondPoolUtxo: Utxo;
+  let dualUtxoB: Utxo;
 
   const keyBasePath = path.resolve(__dirname, '../../circuits2/artifacts/transaction2_js/transaction2');
 
@@ -115,6 +130,7 @@ describe("localnet", () => {
 
     // Initialize merkle tree
     globalMerkleTree = new MerkleTree(DEFAULT_HEIGHT, lightWasm);
+    secondPoolTree = new MerkleTree(DEFAULT_HEIGHT, lightWasm);
 
     // Derive global config
     [globalConfig] = findGlobalConfigPDA(program.programId);
@@ -126,14 +142,8 @@ describe("localnet", () => {
   });
 
   it("Initialize", async () => {
-    const [treeAccount] = PublicKey.findProgramAddressSync(
-      [Buffer.from("merkle_tree")],
-      program.programId
-    );
-    const [treeTokenAccount] = PublicKey.findProgramAddressSync(
-      [Buffer.from("tree_token")],
-      program.programId
-    );
+    const [treeAccount] = findMerkleTreePDA(program.programId);
+    const [treeTokenAccount] = findTreeTokenAccountPDA(program.programId);
 
     // Check if globalConfig already exists
     const globalConfigInfo = await connection.getAccountInfo(globalConfig);
@@ -145,7 +155,7 @@ describe("localnet", () => {
     }
 
     const tx = await program.methods
-      .initialize()
+      .initialize(DEFAULT_HEIGHT, ROOT_HISTORY_SIZE)
       .accountsStrict({
         treeAccount,
         treeTokenAccount,
@@ -159,6 +169,39 @@ describe("localnet", () => {
     console.log("Initialize tx:", tx);
   });
 
+  it("Initialize verifying key", async () => {
+    const [verifyingKey] = findVerifyingKeyPDA(program.programId, CIRCUIT_TRANSACTION2);
+    if (await connection.getAccountInfo(verifyingKey)) {
+      console.log("Verifying key already initialized, skipping...");
+      return;
+    }
+
+    await executeSetupVerifyingKey(program, [admin]);
+    console.log("Verifying key:", verifyingKey.toString());
+  });
+
+  it("Register relayer", async () => {
+    // Withdrawals are relayed by admin, fees go to the token accounts of feeRecipient
+    const [relayerAccount] = findRelayerPDA(program.programId, globalConfig, admin.publicKey);
+    if (await connection.getAccountInfo(relayerAccount)) {
+      // feeRecipient is generated per run, point the existing registration to it
+      await program.methods
+        .updateRelayer(null, feeRecipient.publicKey, null)
+        .accountsStrict({
+          relayerAccount,
+          authority: admin.publicKey,
+          systemProgram: SystemProgram.programId
+        })
+        .signers([admin])
+        .rpc();
+      console.log("Relayer already registered, fee recipient updated");
+      return;
+    }
+
+    await executeRegisterRelayer(program, [admin], WITHDRAW_FEE_RATE, feeRecipient.publicKey);
+    console.log("Relayer account:", relayerAccount.toString());
+  });
+
   it("Create test tokens", async () => {
     // Create mint A
     mintAddressA = await createMint(
@@ -180,6 +223,13 @@ describe("localnet", () => {
     );
     console.log("Mint B:", mintAddressB.toString());
 
+    // Allow both mints in the pool
+    const mintConfigTx = new Transaction().add(
+      await buildInitializeMintConfigInstruction(program, admin.publicKey, mintAddressA, DEFAULT_MINT_CONFIG),
+      await buildInitializeMintConfigInstruction(program, admin.publicKey, mintAddressB, DEFAULT_MINT_CONFIG)
+    );
+    await sendAndConfirmTransaction(connection, mintConfigTx, [admin]);
+
     // Mint tokens to admin
     const adminTokenAccountA = await getOrCreateAssociatedTokenAccount(
       connection,
@@ -289,6 +339,8 @@ describe("localnet", () => {
       getAssociatedTokenAddressSync(mintAddressA, globalConfig, true),
       getAssociatedTokenAddressSync(NATIVE_MINT, globalConfig, true),
       getAssociatedTokenAddressSync(mintAddressB, globalConfig, true),
+      findMintConfigPDA(program.programId, globalConfig, mintAddressA)[0],
+      findMintConfigPDA(program.programId, globalConfig, mintAddressB)[0],
       ...lightAddresses,
     ];
 
@@ -913,4 +965,1054 @@ describe("localnet", () => {
       );
     }
   });
+
+  it("Initialize a second pool", async () => {
+    [secondPoolConfig] = findGlobalConfigPDA(program.programId, secondPoolId);
+
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(
+        await buildInitializePoolInstruction(program, admin.publicKey, secondPoolId),
+        await buildInitializeMintConfigInstruction(program, admin.publicKey, mintAddressA, DEFAULT_MINT_CONFIG, secondPoolId),
+        createAssociatedTokenAccountInstruction(
+          admin.publicKey,
+          getAssociatedTokenAddressSync(mintAddressA, secondPoolConfig, true),
+          secondPoolConfig,
+          mintAddressA
+        )
+      ),
+      [admin]
+    );
+
+    // Relayers register per pool
+    await executeRegisterRelayer(program, [admin], WITHDRAW_FEE_RATE, feeRecipient.publicKey, new BN(0), secondPoolId);
+
+    const lightStaticAccounts = defaultStaticAccountsStruct();
+    const lightTreeAccounts = defaultTestStateTreeAccounts();
+    secondPoolAltAddress = await createNewALT(connection, admin, [
+      ...getTestProtocolAddresses(program.programId, admin.publicKey, feeRecipient.publicKey),
+      secondPoolConfig,
+      findMerkleTreePDA(program.programId, secondPoolId)[0],
+      findMerkleTreePDA(program.programId, secondPoolId, 1)[0],
+      findMintConfigPDA(program.programId, secondPoolConfig, mintAddressA)[0],
+      findRelayerPDA(program.programId, secondPoolConfig, admin.publicKey)[0],
+      getAssociatedTokenAddressSync(mintAddressA, secondPoolConfig, true),
+      mintAddressA,
+      LightSystemProgram.programId,
+      lightStaticAccounts.registeredProgramPda,
+      lightStaticAccounts.noopProgram,
+      lightStaticAccounts.accountCompressionProgram,
+      lightStaticAccounts.accountCompressionAuthority,
+      lightTreeAccounts.addressTree,
+      lightTreeAccounts.addressQueue,
+      lightTreeAccounts.merkleTree,
+      lightTreeAccounts.nullifierQueue,
+    ]);
+    console.log("Second pool:", secondPoolId, secondPoolConfig.toString());
+  });
+
+  it("Deposit and withdraw from the second pool", async () => {
+    const depositAmount = new BN(80000);
+    const depositFee = new BN(calculateDepositFee(depositAmount.toNumber()));
+    const feeRecipientAccount = getAssociatedTokenAddressSync(mintAddressA, feeRecipient.publicKey, true);
+
+    const depositExtData: ExtData = {
+      recipient: getAssociatedTokenAddressSync(mintAddressA, secondPoolConfig, true),
+      extAmount: depositAmount,
+      encryptedOutput: Buffer.from("1"),
+      fee: depositFee,
+      feeRecipient: feeRecipientAccount,
+      mintAddressA: mintAddressA,
+      mintAddressB: mintAddressA,
+    };
+    const depositOutputs = [
+      new Utxo({ lightWasm, amount: depositAmount.sub(depositFee).toString(), mintAddress: mintAddressA.toString() }),
+      new Utxo({ lightWasm, amount: 0, mintAddress: mintAddressA.toString() }),
+    ];
+    const depositProof = await proveTransaction({
+      tree: secondPoolTree,
+      inputs: [
+        new Utxo({ lightWasm, mintAddress: mintAddressA.toString() }),
+        new Utxo({ lightWasm, mintAddress: mintAddressA.toString() }),
+      ],
+      outputs: depositOutputs,
+      publicAmount0: depositAmount.sub(depositFee),
+      extDataHash: getExtDataHash(depositExtData),
+      mint0: mintAddressA,
+      keyBasePath,
+    });
+
+    await sendTransactionWithALT(
+      connection,
+      await buildDepositWithLightNullifiersInstruction(
+        program, depositProof, depositExtData, admin.publicKey, mintAddressA, lightRPC, secondPool
+      ),
+      admin,
+      [],
+      [secondPoolAltAddress],
+      1400000
+    );
+    await insertOutputs(secondPoolTree, depositOutputs);
+    secondPoolUtxo = depositOutputs[0];
+
+    const withdrawalAmount = new BN(30000);
+    const withdrawalFee = new BN(calculateWithdrawalFee(withdrawalAmount.toNumber()));
+    await getOrCreateAssociatedTokenAccount(connection, admin, mintAddressA, recipient.publicKey);
+    const recipientBalanceBefore = await connection.getTokenAccountBalance(
+      getAssociatedTokenAddressSync(mintAddressA, recipient.publicKey, true)
+    );
+
+    const withdrawExtData: ExtData = {
+      recipient: recipient.publicKey,
+      extAmount: withdrawalAmount.neg(),
+      encryptedOutput: Buffer.from(""),
+      fee: withdrawalFee,
+      feeRecipient: feeRecipientAccount,
+      mintAddressA: mintAddressA,
+      mintAddressB: mintAddressA,
+    };
+    const withdrawOutputs = [
+      new Utxo({
+        lightWasm,
+        amount: secondPoolUtxo.amount.sub(withdrawalAmount).sub(withdrawalFee).toString(),
+        mintAddress: mintAddressA.toString()
+      }),
+      new Utxo({ lightWasm, amount: 0, mintAddress: mintAddressA.toString() }),
+    ];
+    const withdrawProof = await proveTransaction({
+      tree: secondPoolTree,
+      inputs: [secondPoolUtxo, new Utxo({ lightWasm, mintAddress: mintAddressA.toString() })],
+      outputs: withdrawOutputs,
+      publicAmount0: withdrawalAmount.neg().sub(withdrawalFee),
+      extDataHash: getExtDataHash(withdrawExtData),
+      mint0: mintAddressA,
+      keyBasePath,
+    });
+
+    await sendTransactionWithALT(
+      connection,
+      await buildWithdrawWithLightNullifiersInstruction(
+        program, withdrawProof, withdrawExtData, admin.publicKey, mintAddressA, lightRPC, secondPool
+      ),
+      admin,
+      [],
+      [secondPoolAltAddress],
+      1400000
+    );
+    await insertOutputs(secondPoolTree, withdrawOutputs);
+    secondPoolUtxo = withdrawOutputs[0];
+
+    const recipientBalanceAfter = await connection.getTokenAccountBalance(
+      getAssociatedTokenAddressSync(mintAddressA, recipient.publicKey, true)
+    );
+    expect(
+      new BN(recipientBalanceAfter.value.amount).sub(new BN(recipientBalanceBefore.value.amount)).toString()
+    ).to.equal(withdrawalAmount.toString());
+  });
+
+  it("Should reject a default pool root in the second pool", async () => {
+    // withdrawOutputUtxo is unspent in the default pool, its root is unknown to the second pool
+    const withdrawalAmount = new BN(10000);
+    const withdrawalFee = new BN(calculateWithdrawalFee(withdrawalAmount.toNumber()));
+    const withdrawExtData: ExtData = {
+      recipient: recipient.publicKey,
+      extAmount: withdrawalAmount.neg(),
+      encryptedOutput: Buffer.from(""),
+      fee: withdrawalFee,
+      feeRecipient: getAssociatedTokenAddressSync(mintAddressA, feeRecipient.publicKey, true),
+      mintAddressA: mintAddressA,
+      mintAddressB: mintAddressA,
+    };
+    const withdrawProof = await proveTransaction({
+      tree: globalMerkleTree,
+      inputs: [withdrawOutputUtxo, new Utxo({ lightWasm, mintAddress: mintAddressA.toString() })],
+      outputs: [
+        new Utxo({
+          lightWasm,
+          amount: withdrawOutputUtxo.amount.sub(withdrawalAmount).sub(withdrawalFee).toString(),
+          mintAddress: mintAddressA.toString()
+        }),
+        new Utxo({ lightWasm, amount: 0, mintAddress: mintAddressA.toString() }),
+      ],
+      publicAmount0: withdrawalAmount.neg().sub(withdrawalFee),
+      extDataHash: getExtDataHash(withdrawExtData),
+      mint0: mintAddressA,
+      keyBasePath,
+    });
+
+    await expectProgramError(
+      sendTransactionWithALT(
+        connection,
+        await buildWithdrawWithLightNullifiersInstruction(
+          program, withdrawProof, withdrawExtData, admin.publicKey, mintAddressA, lightRPC, secondPool
+        ),
+        admin,
+        [],
+        [secondPoolAltAddress],
+        1400000
+      ),
+      "UnknownRoot"
+    );
+  });
+
+  it("Roll the second pool over and spend from the retired tree", async () => {
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(await buildRolloverTreeInstruction(program, admin.publicKey, 0, secondPoolId)),
+      [admin]
+    );
+    const activeTree = new MerkleTree(DEFAULT_HEIGHT, lightWasm);
+
+    // secondPoolUtxo was committed to tree 0, its root is only known by the retired tree
+    const withdrawalAmount = new BN(10000);
+    const withdrawalFee = new BN(calculateWithdrawalFee(withdrawalAmount.toNumber()));
+    const withdrawExtData: ExtData = {
+      recipient: recipient.publicKey,
+      extAmount: withdrawalAmount.neg(),
+      encryptedOutput: Buffer.from(""),
+      fee: withdrawalFee,
+      feeRecipient: getAssociatedTokenAddressSync(mintAddressA, feeRecipient.publicKey, true),
+      mintAddressA: mintAddressA,
+      mintAddressB: mintAddressA,
+    };
+    const withdrawOutputs = [
+      new Utxo({
+        lightWasm,
+        amount: secondPoolUtxo.amount.sub(withdrawalAmount).sub(withdrawalFee).toString(),
+        mintAddress: mintAddressA.toString()
+      }),
+      new Utxo({ lightWasm, amount: 0, mintAddress: mintAddressA.toString() }),
+    ];
+    const withdrawProof = await proveTransaction({
+      tree: secondPoolTree,
+      inputs: [secondPoolUtxo, new Utxo({ lightWasm, mintAddress: mintAddressA.toString() })],
+      outputs: withdrawOutputs,
+      publicAmount0: withdrawalAmount.neg().sub(withdrawalFee),
+      extDataHash: getExtDataHash(withdrawExtData),
+      mint0: mintAddressA,
+      keyBasePath,
+    });
+    const withdraw = async (pool: PoolTarget) => sendTransactionWithALT(
+      connection,
+      await buildWithdrawWithLightNullifiersInstruction(
+        program, withdrawProof, withdrawExtData, admin.publicKey, mintAddressA, lightRPC, pool
+      ),
+      admin,
+      [],
+      [secondPoolAltAddress],
+      1400000
+    );
+
+    await expectProgramError(withdraw({ poolId: secondPoolId, treeIndex: 1 }), "UnknownRoot");
+    await withdraw({ poolId: secondPoolId, treeIndex: 1, retiredTreeIndex: 0 });
+
+    // The change was appended to the active tree
+    await insertOutputs(activeTree, withdrawOutputs);
+    secondPoolTree = activeTree;
+    secondPoolUtxo = withdrawOutputs[0];
+    secondPool.treeIndex = 1;
+  });
+
+  it("Should enforce the mint config on deposits", async () => {
+    const updateMintConfig = async (params: typeof DEFAULT_MINT_CONFIG) => sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(await buildUpdateMintConfigInstruction(program, admin.publicKey, mintAddressB, params)),
+      [admin]
+    );
+    // Deposit proofs against the current root, the bounds are checked after the proof
+    const deposit = async (amount: number) => {
+      const extData: ExtData = {
+        recipient: getAssociatedTokenAddressSync(mintAddressB, globalConfig, true),
+        extAmount: new BN(amount),
+        encryptedOutput: Buffer.from("1"),
+        fee: new BN(0),
+        feeRecipient: getAssociatedTokenAddressSync(mintAddressB, feeRecipient.publicKey, true),
+        mintAddressA: mintAddressB,
+        mintAddressB: mintAddressB,
+      };
+      const proof = await proveTransaction({
+        tree: globalMerkleTree,
+        inputs: [
+          new Utxo({ lightWasm, mintAddress: mintAddressB.toString() }),
+          new Utxo({ lightWasm, mintAddress: mintAddressB.toString() }),
+        ],
+        outputs: [
+          new Utxo({ lightWasm, amount: amount, mintAddress: mintAddressB.toString() }),
+          new Utxo({ lightWasm, amount: 0, mintAddress: mintAddressB.toString() }),
+        ],
+        publicAmount0: new BN(amount),
+        extDataHash: getExtDataHash(extData),
+        mint0: mintAddressB,
+        keyBasePath,
+      });
+      return sendTransactionWithALT(
+        connection,
+        await buildDepositWithLightNullifiersInstruction(program, proof, extData, admin.publicKey, mintAddressB, lightRPC),
+        admin,
+        [],
+        [altAddress],
+        1400000
+      );
+    };
+
+    try {
+      await updateMintConfig({
+        ...DEFAULT_MINT_CONFIG,
+        minDepositAmount: new BN(1000),
+        maxDepositAmount: new BN(5000),
+      });
+      await expectProgramError(deposit(999), "DepositBelowMinimum");
+      await expectProgramError(deposit(5001), "DepositLimitExceeded");
+
+      await updateMintConfig({ ...DEFAULT_MINT_CONFIG, enabled: false });
+      await expectProgramError(deposit(2000), "UnsupportedMintAddress");
+    } finally {
+      await updateMintConfig(DEFAULT_MINT_CONFIG);
+    }
+  });
+
+  it("Relayed deposit from a wallet without SOL", async () => {
+    // The depositor never holds SOL, admin relays and pays for the transaction
+    const depositor = Keypair.generate();
+    const depositorTokenAccount = getAssociatedTokenAddressSync(mintAddressA, depositor.publicKey);
+    const depositAmount = new BN(20000);
+    const depositFee = new BN(calculateDepositFee(depositAmount.toNumber()));
+    const totalAmount = BigInt(depositAmount.add(depositFee).toString());
+    await getOrCreateAssociatedTokenAccount(connection, admin, mintAddressA, depositor.publicKey);
+    await mintTo(connection, admin, mintAddressA, depositorTokenAccount, admin, totalAmount);
+
+    const extData: ExtData = {
+      recipient: getAssociatedTokenAddressSync(mintAddressA, globalConfig, true),
+      extAmount: depositAmount,
+      encryptedOutput: Buffer.from("1"),
+      fee: depositFee,
+      feeRecipient: getAssociatedTokenAddressSync(mintAddressA, feeRecipient.publicKey, true),
+      mintAddressA: mintAddressA,
+      mintAddressB: mintAddressA,
+    };
+    const outputs = [
+      new Utxo({ lightWasm, amount: depositAmount.sub(depositFee).toString(), mintAddress: mintAddressA.toString() }),
+      new Utxo({ lightWasm, amount: 0, mintAddress: mintAddressA.toString() }),
+    ];
+    const proof = await proveTransaction({
+      tree: globalMerkleTree,
+      inputs: [
+        new Utxo({ lightWasm, mintAddress: mintAddressA.toString() }),
+        new Utxo({ lightWasm, mintAddress: mintAddressA.toString() }),
+      ],
+      outputs,
+      publicAmount0: depositAmount.sub(depositFee),
+      extDataHash: getExtDataHash(extData),
+      mint0: mintAddressA,
+      keyBasePath,
+    });
+    const [approveInstruction, depositInstruction] = await buildDepositRelayedInstruction(
+      program, proof, extData, depositor.publicKey, admin.publicKey, mintAddressA, 9, lightRPC
+    );
+    const approve = (commitment: number[], amount: bigint) => createApproveCheckedInstruction(
+      depositorTokenAccount,
+      mintAddressA,
+      findDepositAuthorityPDA(program.programId, commitment)[0],
+      depositor.publicKey,
+      amount,
+      9
+    );
+    const send = (instructions: TransactionInstruction[], signers: Keypair[]) =>
+      sendTransactionWithALT(connection, instructions, admin, signers, [altAddress], 1400000);
+
+    // Without approval, below the deposit amount plus fee, or given for another deposit
+    await expectProgramError(send([depositInstruction], []), "DepositNotApproved");
+    await expectProgramError(
+      send([approve(proof.outputCommitments[0], totalAmount - BigInt(1)), depositInstruction], [depositor]),
+      "DepositNotApproved"
+    );
+    await expectProgramError(
+      send([approve(proof.outputCommitments[1], totalAmount), depositInstruction], [depositor]),
+      "DepositNotApproved"
+    );
+
+    const reserveBalanceBefore = await connection.getTokenAccountBalance(extData.recipient);
+    await send([approveInstruction, depositInstruction], [depositor]);
+    await insertOutputs(globalMerkleTree, outputs);
+
+    const reserveBalanceAfter = await connection.getTokenAccountBalance(extData.recipient);
+    const depositorBalance = await connection.getTokenAccountBalance(depositorTokenAccount);
+    expect(
+      new BN(reserveBalanceAfter.value.amount).sub(new BN(reserveBalanceBefore.value.amount)).toString()
+    ).to.equal(depositAmount.toString());
+    expect(depositorBalance.value.amount).to.equal("0");
+    expect(await connection.getBalance(depositor.publicKey)).to.equal(0);
+  });
+
+  it("Deposit from a non-associated token account as its delegate", async () => {
+    // Stands for a multisig vault: the owner only delegates, the delegate signs the deposit
+    const vaultOwner = Keypair.generate();
+    const delegate = Keypair.generate();
+    const stranger = Keypair.generate();
+    const depositAmount = new BN(30000);
+    const depositFee = new BN(calculateDepositFee(depositAmount.toNumber()));
+    const totalAmount = BigInt(depositAmount.add(depositFee).toString());
+    const sourceTokenAccount = await createAccount(
+      connection, admin, mintAddressA, vaultOwner.publicKey, Keypair.generate()
+    );
+    await mintTo(connection, admin, mintAddressA, sourceTokenAccount, admin, totalAmount);
+    const delegateAmount = (amount: bigint) =>
+      approve(connection, admin, sourceTokenAccount, delegate.publicKey, vaultOwner, amount);
+
+    const extData: ExtData = {
+      recipient: getAssociatedTokenAddressSync(mintAddressA, globalConfig, true),
+      extAmount: depositAmount,
+      encryptedOutput: Buffer.from("1"),
+      fee: depositFee,
+      feeRecipient: getAssociatedTokenAddressSync(mintAddressA, feeRecipient.publicKey, true),
+      mintAddressA: mintAddressA,
+      mintAddressB: mintAddressA,
+    };
+    const outputs = [
+      new Utxo({ lightWasm, amount: depositAmount.sub(depositFee).toString(), mintAddress: mintAddressA.toString() }),
+      new Utxo({ lightWasm, amount: 0, mintAddress: mintAddressA.toString() }),
+    ];
+    const proof = await proveTransaction({
+      tree: globalMerkleTree,
+      inputs: [
+        new Utxo({ lightWasm, mintAddress: mintAddressA.toString() }),
+        new Utxo({ lightWasm, mintAddress: mintAddressA.toString() }),
+      ],
+      outputs,
+      publicAmount0: depositAmount.sub(depositFee),
+      extDataHash: getExtDataHash(extData),
+      mint0: mintAddressA,
+      keyBasePath,
+    });
+    const deposit = async (authority: Keypair) => sendTransactionWithALT(
+      connection,
+      await buildDepositFromTokenAccountInstruction(
+        program, proof, extData, authority.publicKey, admin.publicKey, sourceTokenAccount, mintAddressA, lightRPC
+      ),
+      admin,
+      [authority],
+      [altAddress],
+      1400000
+    );
+
+    // Neither owner nor delegate, then a delegate approved below the deposit amount plus fee
+    await expectProgramError(deposit(stranger), "InvalidTokenAccountAuthority");
+    await delegateAmount(totalAmount - BigInt(1));
+    await expectProgramError(deposit(delegate), "InvalidTokenAccountAuthority");
+
+    await delegateAmount(totalAmount);
+    const reserveBalanceBefore = await connection.getTokenAccountBalance(extData.recipient);
+    await deposit(delegate);
+    await insertOutputs(globalMerkleTree, outputs);
+
+    const reserveBalanceAfter = await connection.getTokenAccountBalance(extData.recipient);
+    const sourceBalance = await connection.getTokenAccountBalance(sourceTokenAccount);
+    expect(
+      new BN(reserveBalanceAfter.value.amount).sub(new BN(reserveBalanceBefore.value.amount)).toString()
+    ).to.equal(depositAmount.toString());
+    expect(sourceBalance.value.amount).to.equal("0");
+  });
+
+  it("Private transfer", async () => {
+    // Split withdrawOutputUtxo between a new owner and a change note, no tokens move
+    const reserveTokenAccount = getAssociatedTokenAddressSync(mintAddressA, globalConfig, true);
+    const reserveBalanceBefore = await connection.getTokenAccountBalance(reserveTokenAccount);
+    const encryptedOutput = Buffer.from("transfer");
+    const transferAmount = withdrawOutputUtxo.amount.divn(3);
+
+    const transferOutputs = [
+      new Utxo({ lightWasm, amount: transferAmount, mintAddress: mintAddressA.toString() }),
+      new Utxo({
+        lightWasm,
+        amount: withdrawOutputUtxo.amount.sub(transferAmount),
+        keypair: withdrawOutputUtxo.keypair,
+        mintAddress: mintAddressA.toString()
+      }),
+    ];
+    const transferProof = await proveTransaction({
+      tree: globalMerkleTree,
+      inputs: [withdrawOutputUtxo, new Utxo({ lightWasm, mintAddress: mintAddressA.toString() })],
+      outputs: transferOutputs,
+      publicAmount0: new BN(0),
+      // transact binds no recipient and no fee
+      extDataHash: getExtDataHash({
+        recipient: PublicKey.default,
+        extAmount: new BN(0),
+        encryptedOutput,
+        fee: new BN(0),
+        feeRecipient: PublicKey.default,
+        mintAddressA: mintAddressA,
+        mintAddressB: mintAddressA,
+      }),
+      mint0: mintAddressA,
+      keyBasePath,
+    });
+
+    await sendTransactionWithALT(
+      connection,
+      await buildTransactInstruction(program, transferProof, encryptedOutput, admin.publicKey, mintAddressA, lightRPC),
+      admin,
+      [],
+      [altAddress],
+      1400000
+    );
+    await insertOutputs(globalMerkleTree, transferOutputs);
+    withdrawOutputUtxo = transferOutputs[1];
+
+    const reserveBalanceAfter = await connection.getTokenAccountBalance(reserveTokenAccount);
+    expect(reserveBalanceAfter.value.amount).to.equal(reserveBalanceBefore.value.amount);
+  });
+
+  // Proves and sends a transact_dual signed by admin, side 0 settles mint0 and side 1 mint1.
+  // Fees go to the token accounts of feeOwner (the fee recipient of the admin relayer by default)
+  async function sendTransactDual(params: {
+    mint0: PublicKey;
+    mint1: PublicKey;
+    inputs: Utxo[];
+    outputs: Utxo[];
+    extAmount0: BN;
+    fee0: BN;
+    extAmount1: BN;
+    fee1: BN;
+    feeOwner?: PublicKey;
+  }) {
+    const encryptedOutput = Buffer.from("dual");
+    const feeOwner = params.feeOwner ?? feeRecipient.publicKey;
+    const feeRecipient0 = getAssociatedTokenAddressSync(params.mint0, feeOwner, true);
+    const feeRecipient1 = getAssociatedTokenAddressSync(params.mint1, feeOwner, true);
+    const proof = await proveTransaction({
+      tree: globalMerkleTree,
+      inputs: params.inputs,
+      outputs: params.outputs,
+      publicAmount0: params.extAmount0.sub(params.fee0),
+      publicAmount1: params.extAmount1.sub(params.fee1),
+      extDataHash: getDualExtDataHash({
+        recipient: admin.publicKey,
+        extAmount0: params.extAmount0,
+        extAmount1: params.extAmount1,
+        encryptedOutput,
+        fee0: params.fee0,
+        fee1: params.fee1,
+        feeRecipient0,
+        feeRecipient1,
+        mintAddressA: params.mint0,
+        mintAddressB: params.mint1,
+      }),
+      mint0: params.mint0,
+      mint1: params.mint1,
+      keyBasePath,
+    });
+    const send = async (mint1: PublicKey, relayer: PublicKey | null = admin.publicKey) => sendTransactionWithALT(
+      connection,
+      await buildTransactDualInstruction(
+        program,
+        proof,
+        { extAmount0: params.extAmount0, fee0: params.fee0, extAmount1: params.extAmount1, fee1: params.fee1 },
+        encryptedOutput,
+        admin.publicKey,
+        params.mint0,
+        mint1,
+        feeRecipient0,
+        getAssociatedTokenAddressSync(mint1, feeOwner, true),
+        lightRPC,
+        relayer
+      ),
+      admin,
+      [],
+      [altAddress],
+      1400000
+    );
+    return {
+      send: () => send(params.mint1),
+      sendWithSameMints: () => send(params.mint0),
+      sendWithoutRelayer: () => send(params.mint1, null),
+    };
+  }
+
+  it("Dual-token deposit, then deposit mintA while withdrawing mintB", async () => {
+    const adminTokenAccountA = getAssociatedTokenAddressSync(mintAddressA, admin.publicKey);
+    const adminTokenAccountB = getAssociatedTokenAddressSync(mintAddressB, admin.publicKey);
+    const zero = new BN(0);
+
+    // Both sides deposit
+    const depositA = new BN(20000);
+    const depositB = new BN(30000);
+    const depositOutputs = [
+      new Utxo({ lightWasm, amount: depositA, mintAddress: mintAddressA.toString() }),
+      new Utxo({ lightWasm, amount: depositB, mintAddress: mintAddressB.toString() }),
+    ];
+    const dualDeposit = await sendTransactDual({
+      mint0: mintAddressA,
+      mint1: mintAddressB,
+      inputs: [
+        new Utxo({ lightWasm, mintAddress: mintAddressA.toString() }),
+        new Utxo({ lightWasm, mintAddress: mintAddressB.toString() }),
+      ],
+      outputs: depositOutputs,
+      extAmount0: depositA,
+      fee0: zero,
+      extAmount1: depositB,
+      fee1: zero,
+    });
+    await dualDeposit.send();
+    await insertOutputs(globalMerkleTree, depositOutputs);
+
+    // Deposit mintA and withdraw mintB with one proof
+    const balanceA = new BN((await connection.getTokenAccountBalance(adminTokenAccountA)).value.amount);
+    const balanceB = new BN((await connection.getTokenAccountBalance(adminTokenAccountB)).value.amount);
+    const withdrawB = new BN(10000);
+    const withdrawFeeB = new BN(calculateWithdrawalFee(withdrawB.toNumber()));
+    const outputs = [
+      new Utxo({ lightWasm, amount: depositA, mintAddress: mintAddressA.toString() }),
+      new Utxo({ lightWasm, amount: depositB.sub(withdrawB).sub(withdrawFeeB), mintAddress: mintAddressB.toString() }),
+    ];
+    const depositAndWithdraw = await sendTransactDual({
+      mint0: mintAddressA,
+      mint1: mintAddressB,
+      inputs: [depositOutputs[1], new Utxo({ lightWasm, mintAddress: mintAddressA.toString() })],
+      outputs,
+      extAmount0: depositA,
+      fee0: zero,
+      extAmount1: withdrawB.neg(),
+      fee1: withdrawFeeB,
+    });
+    await depositAndWithdraw.send();
+    await insertOutputs(globalMerkleTree, outputs);
+    dualUtxoB = outputs[1];
+
+    const balanceAAfter = new BN((await connection.getTokenAccountBalance(adminTokenAccountA)).value.amount);
+    const balanceBAfter = new BN((await connection.getTokenAccountBalance(adminTokenAccountB)).value.amount);
+    expect(balanceA.sub(balanceAAfter).toString()).to.equal(depositA.toString());
+    expect(balanceBAfter.sub(balanceB).toString()).to.equal(withdrawB.toString());
+  });
+
+  it("Should enforce the idle side and distinct mints of dual transactions", async () => {
+    // Side 1 doesn't move but charges a fee, the proof commits to publicAmount1 = -fee1
+    const depositA = new BN(5000);
+    const idleFee = await sendTransactDual({
+      mint0: mintAddressA,
+      mint1: mintAddressB,
+      inputs: [dualUtxoB, new Utxo({ lightWasm, mintAddress: mintAddressA.toString() })],
+      outputs: [
+        new Utxo({ lightWasm, amount: depositA, mintAddress: mintAddressA.toString() }),
+        new Utxo({ lightWasm, amount: dualUtxoB.amount.subn(1), mintAddress: mintAddressB.toString() }),
+      ],
+      extAmount0: depositA,
+      fee0: new BN(0),
+      extAmount1: new BN(0),
+      fee1: new BN(1),
+    });
+    await expectProgramError(idleFee.send(), "InvalidPublicAmountData");
+
+    // Same-mint transactions go through deposit and withdraw
+    await expectProgramError(idleFee.sendWithSameMints(), "UnsupportedMintAddress");
+  });
+
+  it("Should only pay dual transaction fees to a registered relayer", async () => {
+    // dualUtxoB stays unspent, every attempt fails before the nullifiers are created
+    const withdrawB = new BN(1000);
+    const withdrawFeeB = new BN(calculateWithdrawalFee(withdrawB.toNumber()));
+    const withdrawDual = (fee1: BN, feeOwner?: PublicKey) => sendTransactDual({
+      mint0: mintAddressA,
+      mint1: mintAddressB,
+      inputs: [dualUtxoB, new Utxo({ lightWasm, mintAddress: mintAddressA.toString() })],
+      outputs: [
+        new Utxo({ lightWasm, amount: 0, mintAddress: mintAddressA.toString() }),
+        new Utxo({ lightWasm, amount: dualUtxoB.amount.sub(withdrawB).sub(fee1), mintAddress: mintAddressB.toString() }),
+      ],
+      extAmount0: new BN(0),
+      fee0: new BN(0),
+      extAmount1: withdrawB.neg(),
+      fee1,
+      feeOwner,
+    });
+
+    // A fee needs the relayer registration
+    await expectProgramError((await withdrawDual(withdrawFeeB)).sendWithoutRelayer(), "InvalidFeeRecipient");
+
+    // The fee token account must be owned by the registered fee recipient (admin is the relayer, not its fee recipient)
+    await expectProgramError((await withdrawDual(withdrawFeeB, admin.publicKey)).send(), "InvalidFeeRecipient");
+
+    // The advertised rate caps the fee
+    await expectProgramError((await withdrawDual(withdrawFeeB.muln(2))).send(), "RelayerFeeTooHigh");
+  });
+
+  it("Should only accept withdrawals within the relayer registration", async () => {
+    // withdrawOutputUtxo stays unspent, every attempt fails before the nullifiers are created
+    const withdrawalAmount = new BN(10000);
+    const withdrawalFee = new BN(calculateWithdrawalFee(withdrawalAmount.toNumber()));
+    const withdrawExtData: ExtData = {
+      recipient: recipient.publicKey,
+      extAmount: withdrawalAmount.neg(),
+      encryptedOutput: Buffer.from(""),
+      fee: withdrawalFee,
+      feeRecipient: getAssociatedTokenAddressSync(mintAddressA, feeRecipient.publicKey, true),
+      mintAddressA: mintAddressA,
+      mintAddressB: mintAddressA,
+    };
+    const withdrawProof = await proveTransaction({
+      tree: globalMerkleTree,
+      inputs: [withdrawOutputUtxo, new Utxo({ lightWasm, mintAddress: mintAddressA.toString() })],
+      outputs: [
+        new Utxo({
+          lightWasm,
+          amount: withdrawOutputUtxo.amount.sub(withdrawalAmount).sub(withdrawalFee).toString(),
+          mintAddress: mintAddressA.toString()
+        }),
+        new Utxo({ lightWasm, amount: 0, mintAddress: mintAddressA.toString() }),
+      ],
+      publicAmount0: withdrawalAmount.neg().sub(withdrawalFee),
+      extDataHash: getExtDataHash(withdrawExtData),
+      mint0: mintAddressA,
+      keyBasePath,
+    });
+    const withdraw = async (
+      relayer: Keypair,
+      extData: ExtData = withdrawExtData,
+      registeredRelayer: boolean = true
+    ) => sendTransactionWithALT(
+      connection,
+      await buildWithdrawWithLightNullifiersInstruction(
+        program, withdrawProof, extData, relayer.publicKey, mintAddressA, lightRPC, {}, registeredRelayer
+      ),
+      relayer,
+      [],
+      [altAddress],
+      1400000
+    );
+
+    // The fee token account must belong to the registered fee recipient
+    await getOrCreateAssociatedTokenAccount(connection, admin, mintAddressA, recipient.publicKey);
+    await expectProgramError(
+      withdraw(admin, {
+        ...withdrawExtData,
+        feeRecipient: getAssociatedTokenAddressSync(mintAddressA, recipient.publicKey, true),
+      }),
+      "InvalidFeeRecipient"
+    );
+
+    // The unbonded relayer falls below a raised pool minimum
+    await executeUpdateGlobalConfig(program, [admin], null, null, null, new BN(LAMPORTS_PER_SOL));
+    try {
+      await expectProgramError(withdraw(admin), "RelayerBondTooLow");
+    } finally {
+      await executeUpdateGlobalConfig(program, [admin], null, null, null, new BN(0));
+    }
+
+    // The fee meets the pool rate but not the lower rate this relayer advertises
+    const otherRelayer = Keypair.generate();
+    await connection.confirmTransaction(
+      await connection.requestAirdrop(otherRelayer.publicKey, LAMPORTS_PER_SOL)
+    );
+
+    // Without a registration the signer can only relay its own withdrawal, without fee
+    await expectProgramError(withdraw(otherRelayer, withdrawExtData, false), "InvalidFeeRecipient");
+
+    const bond = new BN(LAMPORTS_PER_SOL / 10);
+    await executeRegisterRelayer(program, [otherRelayer], WITHDRAW_FEE_RATE / 3, feeRecipient.publicKey, bond);
+    await expectProgramError(withdraw(otherRelayer), "RelayerFeeTooHigh");
+
+    // Relayers stop relaying as soon as they ask to leave, the bond stays locked for the delay
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(await buildRequestRelayerDeregistrationInstruction(program, otherRelayer.publicKey)),
+      [otherRelayer]
+    );
+    await expectProgramError(withdraw(otherRelayer), "RelayerDeregistering");
+    await expectProgramError(
+      sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(await buildDeregisterRelayerInstruction(program, otherRelayer.publicKey)),
+        [otherRelayer]
+      ),
+      "RelayerBondUnbonding"
+    );
+
+    // The pool authority can still slash the locked bond
+    const [otherRelayerAccount] = findRelayerPDA(program.programId, globalConfig, otherRelayer.publicKey);
+    const slash = async (amount: BN) => sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(
+        await buildSlashRelayerInstruction(program, admin.publicKey, otherRelayer.publicKey, feeRecipient.publicKey, amount)
+      ),
+      [admin]
+    );
+    await expectProgramError(slash(bond.addn(1)), "SlashExceedsRelayerBond");
+    const destinationBefore = await connection.getBalance(feeRecipient.publicKey);
+    await slash(bond);
+    expect(await connection.getBalance(feeRecipient.publicKey)).to.equal(destinationBefore + bond.toNumber());
+    expect((await program.account.relayerAccount.fetch(otherRelayerAccount)).bond.toNumber()).to.equal(0);
+    await expectProgramError(
+      sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(
+          await buildSlashRelayerInstruction(program, otherRelayer.publicKey, otherRelayer.publicKey, otherRelayer.publicKey, new BN(0))
+        ),
+        [otherRelayer]
+      ),
+      "Unauthorized"
+    );
+  });
+
+  it("Self-relayed withdrawal without a registered relayer", async () => {
+    // The recipient pays the transaction itself, there is no relayer to pay a fee to
+    const withdrawalAmount = new BN(10000);
+    const withdrawExtData: ExtData = {
+      recipient: recipient.publicKey,
+      extAmount: withdrawalAmount.neg(),
+      encryptedOutput: Buffer.from("self-relayed"),
+      fee: new BN(0),
+      feeRecipient: getAssociatedTokenAddressSync(mintAddressA, recipient.publicKey, true),
+      mintAddressA: mintAddressA,
+      mintAddressB: mintAddressA,
+    };
+    const withdrawOutputs = [
+      new Utxo({
+        lightWasm,
+        amount: withdrawOutputUtxo.amount.sub(withdrawalAmount).toString(),
+        keypair: withdrawOutputUtxo.keypair,
+        mintAddress: mintAddressA.toString()
+      }),
+      new Utxo({ lightWasm, amount: 0, mintAddress: mintAddressA.toString() }),
+    ];
+    const withdrawProof = await proveTransaction({
+      tree: globalMerkleTree,
+      inputs: [withdrawOutputUtxo, new Utxo({ lightWasm, mintAddress: mintAddressA.toString() })],
+      outputs: withdrawOutputs,
+      publicAmount0: withdrawalAmount.neg(),
+      extDataHash: getExtDataHash(withdrawExtData),
+      mint0: mintAddressA,
+      keyBasePath,
+    });
+    const recipientTokenAccount = getAssociatedTokenAddressSync(mintAddressA, recipient.publicKey, true);
+    const recipientBalanceBefore = await connection.getTokenAccountBalance(recipientTokenAccount);
+
+    await sendTransactionWithALT(
+      connection,
+      await buildWithdrawWithLightNullifiersInstruction(
+        program, withdrawProof, withdrawExtData, recipient.publicKey, mintAddressA, lightRPC, {}, false
+      ),
+      recipient,
+      [],
+      [altAddress],
+      1400000
+    );
+    await insertOutputs(globalMerkleTree, withdrawOutputs);
+    withdrawOutputUtxo = withdrawOutputs[0];
+
+    const recipientBalanceAfter = await connection.getTokenAccountBalance(recipientTokenAccount);
+    expect(new BN(recipientBalanceAfter.value.amount).sub(new BN(recipientBalanceBefore.value.amount)).toString())
+      .to.equal(withdrawalAmount.toString());
+  });
+
+  // Makes the following mock routes leave part of the input or drain the passed account
+  const configureMockJupiter = (inputShortfall: BN, drainAmount: BN) => sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(buildConfigureMockJupiterInstruction(admin.publicKey, inputShortfall, drainAmount)),
+    [admin]
+  );
+
+  // Fee admin takes as relayer on a swap output of quotedOutAmount, at its advertised rate
+  const relayerSwapFee = (quotedOutAmount: BN) => quotedOutAmount.muln(WITHDRAW_FEE_RATE).divn(10000);
+
+  // Proves a swap of part of withdrawOutputUtxo into mintB through the mock aggregator, relayed
+  // by admin. The mock pays quotedOutAmount, admin's fee recipient keeps what exceeds minAmountOut
+  async function proveSwap(params: {
+    swapAmount: BN;
+    minAmountOut: BN;
+    quotedOutAmount: BN;
+    fee?: BN;
+    drain?: MockDrain;
+  }) {
+    const fee = params.fee ?? new BN(0);
+    const encryptedOutput = Buffer.from("swap");
+    const [swapAuthority] = findSwapAuthorityPDA(program.programId, globalConfig);
+    const route = buildMockRoute({
+      authority: swapAuthority,
+      sourceTokenAccount: getAssociatedTokenAddressSync(mintAddressA, globalConfig, true),
+      destinationTokenAccount: getAssociatedTokenAddressSync(mintAddressB, globalConfig, true),
+      sourceMint: mintAddressA,
+      destinationMint: mintAddressB,
+      inAmount: params.swapAmount,
+      quotedOutAmount: params.quotedOutAmount,
+      drain: params.drain,
+    });
+    const swapData: SwapData = {
+      extAmount: params.swapAmount.neg(),
+      extMinAmountOut: params.minAmountOut,
+      encryptedOutput,
+      fee,
+      feeRecipient: feeRecipient.publicKey,
+      mintAddressA: mintAddressA,
+      mintAddressB: mintAddressB,
+      routeHash: getRouteHash(route.data, route.accounts),
+    };
+    const outputs = [
+      new Utxo({
+        lightWasm,
+        amount: withdrawOutputUtxo.amount.sub(params.swapAmount).sub(fee),
+        keypair: withdrawOutputUtxo.keypair,
+        mintAddress: mintAddressA.toString()
+      }),
+      new Utxo({ lightWasm, amount: params.minAmountOut, mintAddress: mintAddressB.toString() }),
+    ];
+    const proof = await proveTransaction({
+      tree: globalMerkleTree,
+      inputs: [withdrawOutputUtxo, new Utxo({ lightWasm, mintAddress: mintAddressA.toString() })],
+      outputs,
+      publicAmount0: params.swapAmount.neg().sub(fee),
+      publicAmount1: params.minAmountOut,
+      // The builder takes the fee recipient owner, the hash binds its output mint token account
+      extDataHash: getSwapExtDataHash({
+        ...swapData,
+        feeRecipient: getAssociatedTokenAddressSync(mintAddressB, feeRecipient.publicKey, true),
+      }),
+      mint0: mintAddressA,
+      mint1: mintAddressB,
+      keyBasePath,
+    });
+
+    // The route accounts don't fit in the transaction next to the protocol ALT
+    const routeAltAddress = await createNewALT(connection, admin, route.accounts.map(account => account.pubkey));
+    // dropAccounts leaves out the last remaining accounts of the swap instruction
+    const send = async (dropAccounts = 0) => {
+      const instructions = await buildSwapWithLightNullifiersInstruction(
+        program, proof, swapData, admin.publicKey, mintAddressA, mintAddressB, lightRPC, route.data, route.accounts
+      );
+      const swapInstruction = instructions[instructions.length - 1];
+      swapInstruction.keys = swapInstruction.keys.slice(0, swapInstruction.keys.length - dropAccounts);
+      return sendTransactionWithALT(connection, instructions, admin, [], [altAddress, routeAltAddress], 1400000);
+    };
+    return { outputs, route, send };
+  }
+
+  it("Relayed swap of mintA to mintB", async () => {
+    // Allow the aggregator, the mock swaps at the quoted amount out of its own liquidity
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(
+        await buildSetAggregatorProgramInstruction(program, admin.publicKey, JUPITER_PROGRAM_ID, true),
+        buildConfigureMockJupiterInstruction(admin.publicKey, new BN(0), new BN(0))
+      ),
+      [admin]
+    );
+    await fundMockVault(connection, admin, mintAddressA);
+    await fundMockVault(connection, admin, mintAddressB, 1000 * 10 ** 9);
+
+    const swapAmount = withdrawOutputUtxo.amount.divn(2);
+    const quotedOutAmount = swapAmount;
+    const relayerFee = relayerSwapFee(quotedOutAmount);
+    const minAmountOut = quotedOutAmount.sub(relayerFee);
+
+    // The relayer is paid out of the output, a fee on the input side is rejected
+    const withInputFee = await proveSwap({ swapAmount, minAmountOut, quotedOutAmount, fee: new BN(1) });
+    await expectProgramError(withInputFee.send(), "InvalidFeeAmount");
+
+    // A surplus above the relayer's rate is rejected rather than left in the reserve
+    const aboveRate = await proveSwap({ swapAmount, minAmountOut: minAmountOut.subn(1), quotedOutAmount });
+    await expectProgramError(aboveRate.send(), "RelayerFeeTooHigh");
+
+    const reserveA = getAssociatedTokenAddressSync(mintAddressA, globalConfig, true);
+    const reserveB = getAssociatedTokenAddressSync(mintAddressB, globalConfig, true);
+    const feeRecipientB = getAssociatedTokenAddressSync(mintAddressB, feeRecipient.publicKey, true);
+    const balance = async (account: PublicKey) =>
+      new BN((await connection.getTokenAccountBalance(account)).value.amount);
+    const [reserveABefore, reserveBBefore, feeBefore] = await Promise.all(
+      [reserveA, reserveB, feeRecipientB].map(balance)
+    );
+
+    const swap = await proveSwap({ swapAmount, minAmountOut, quotedOutAmount });
+    await swap.send();
+    await insertOutputs(globalMerkleTree, swap.outputs);
+    withdrawOutputUtxo = swap.outputs[0];
+    swapOutputUtxoMintB = swap.outputs[1];
+
+    // The whole surplus above the user's minimum pays the relayer
+    expect(reserveABefore.sub(await balance(reserveA)).toString()).to.equal(swapAmount.toString());
+    expect((await balance(reserveB)).sub(reserveBBefore).toString()).to.equal(minAmountOut.toString());
+    expect((await balance(feeRecipientB)).sub(feeBefore).toString()).to.equal(relayerFee.toString());
+  });
+
+  it("Should reject a route that drains another reserve", async () => {
+    // Reserve of a mint whose permanent delegate is the mock, passed along with the route
+    const drainedMint = await createDrainableMint(connection, admin);
+    const drainedReserve = await getOrCreateAssociatedTokenAccount(
+      connection, admin, drainedMint, globalConfig, true, undefined, undefined, TOKEN_2022_PROGRAM_ID
+    );
+    const drainDestination = await getOrCreateAssociatedTokenAccount(
+      connection, admin, drainedMint, admin.publicKey, false, undefined, undefined, TOKEN_2022_PROGRAM_ID
+    );
+    await mintTo(connection, admin, drainedMint, drainedReserve.address, admin, 1000, [], undefined, TOKEN_2022_PROGRAM_ID);
+
+    const swapAmount = withdrawOutputUtxo.amount.divn(2);
+    const swap = await proveSwap({
+      swapAmount,
+      minAmountOut: swapAmount.sub(relayerSwapFee(swapAmount)),
+      quotedOutAmount: swapAmount,
+      drain: {
+        account: drainedReserve.address,
+        mint: drainedMint,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        destination: drainDestination.address,
+      },
+    });
+
+    await configureMockJupiter(new BN(0), new BN(1));
+    try {
+      await expectProgramError(swap.send(), "ReserveBalanceDecreased");
+    } finally {
+      await configureMockJupiter(new BN(0), new BN(0));
+    }
+
+    // The same route goes through once it leaves the reserve alone
+    await swap.send();
+    await insertOutputs(globalMerkleTree, swap.outputs);
+    withdrawOutputUtxo = swap.outputs[0];
+    expect((await connection.getTokenAccountBalance(drainedReserve.address)).value.amount).to.equal("1000");
+  });
+
+  it("Should reject a route that spends another amount than the proven one", async () => {
+    const swapAmount = withdrawOutputUtxo.amount.divn(2);
+    const swap = await proveSwap({
+      swapAmount,
+      minAmountOut: swapAmount.sub(relayerSwapFee(swapAmount)),
+      quotedOutAmount: swapAmount,
+    });
+
+    // The mock leaves one token of the input in the reserve, the output is still paid in full
+    await configureMockJupiter(new BN(1), new BN(0));
+    try {
+      await expectProgramError(swap.send(), "SwapInputMismatch");
+    } finally {
+      await configureMockJupiter(new BN(0), new BN(0));
+    }
+  });
+
+  it("Should reject a swap missing Light Protocol accounts", async () => {
+    const swapAmount = withdrawOutputUtxo.amount.divn(2);
+    const swap = await proveSwap({
+      swapAmount,
+      minAmountOut: swapAmount.sub(relayerSwapFee(swapAmount)),
+      quotedOutAmount: swapAmount,
+    });
+
+    // Without the route and half of the 12 Light Protocol accounts
+    await expectProgramError(swap.send(swap.route.accounts.length + 6), "NotEnoughLightAccounts");
+  });
+
+  it("Should map the deprecated deposit limit onto the wrapped SOL mint config", async () => {
+    const [mintConfig] = findMintConfigPDA(program.programId, globalConfig, NATIVE_MINT);
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(
+        await buildInitializeMintConfigInstruction(program, admin.publicKey, NATIVE_MINT, DEFAULT_MINT_CONFIG),
+        await buildUpdateDepositLimitInstruction(program, admin.publicKey, new BN(5 * LAMPORTS_PER_SOL))
+      ),
+      [admin]
+    );
+
+    const config = await program.account.mintConfig.fetch(mintConfig);
+    expect(config.maxDepositAmount.toString()).to.equal((5 * LAMPORTS_PER_SOL).toString());
+    expect(config.enabled).to.be.true;
+  });
 });
@@ -0,0 +1,40 @@
+/**
+ * Cross-language test vectors shared with the Rust client (client/tests/utxo_vectors.rs)
+ */
+import { expect } from "chai";
+import BN from "bn.js";
+import { LightWasm, WasmFactory } from "@lightprotocol/hasher.rs";
+import { Keypair } from "./lib/keypair";
+import { Utxo } from "./lib/utxo";
+import { publicKeyToFieldElement } from "./lib/utils";
+import fixture from "./fixtures/utxo_vectors.json";
+
+describe("utxo vectors", () => {
+  let lightWasm: LightWasm;
+
+  before(async () => {
+    lightWasm = await WasmFactory.getInstance();
+  });
+
+  fixture.vectors.forEach((vector, i) => {
+    it(`matches vector ${i}`, async () => {
+      const keypair = new Keypair(vector.privateKey, lightWasm);
+      expect(keypair.privkey.toString()).to.equal(vector.expected.privateKeyField);
+      expect(keypair.pubkey.toString()).to.equal(vector.expected.publicKey);
+      expect(publicKeyToFieldElement(vector.mintAddress)).to.equal(vector.expected.mintAddressField);
+
+      const utxo = new Utxo({
+        lightWasm,
+        amount: new BN(vector.amount),
+        keypair,
+        blinding: new BN(vector.blinding),
+        index: vector.index,
+        mintAddress: vector.mintAddress,
+      });
+      const commitment = await utxo.getCommitment();
+      expect(commitment).to.equal(vector.expected.commitment);
+      expect(keypair.sign(commitment, new BN(vector.index).toString())).to.equal(vector.expected.signature);
+      expect(await utxo.getNullifier()).to.equal(vector.expected.nullifier);
+    });
+  });
+});